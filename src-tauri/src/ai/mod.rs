@@ -1,7 +1,7 @@
-use crate::model::{AIModel, AIModelType, AIProvider};
-use crate::storage::get_db_path;
+use crate::model::{AIModelType, AIProvider};
+use crate::storage::repository::{ConfigRepo, ProviderRepo};
+use crate::storage::secrets;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, Row};
 
 use tysm::chat_completions::{
     ChatClient, ChatMessage, ChatMessageContent, ImageUrl, ResponseFormat, Role,
@@ -31,6 +31,13 @@ struct TitleOptions {
     options: Vec<String>,
 }
 
+/// AI 为某个账号改写的笔记
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PostVariation {
+    pub title: String,
+    pub content: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModelTestResult {
     pub model_name: String,
@@ -46,6 +53,7 @@ pub async fn generate_ai_text(
     provider: AIProvider,
     model_name: String,
 ) -> Result<String, String> {
+    let provider = resolve_provider(provider).await?;
     let api_key = provider.api_key;
     let base_url = provider
         .base_url
@@ -111,6 +119,7 @@ pub async fn polish_title_with_options(
     provider: AIProvider,
     model_name: String,
 ) -> Result<Vec<String>, String> {
+    let provider = resolve_provider(provider).await?;
     let api_key = provider.api_key;
     let base_url = provider
         .base_url
@@ -224,6 +233,47 @@ pub async fn polish_title_with_options(
     }
 }
 
+/// 为多账号发布生成笔记的改写版本，避免多个账号发布完全相同的内容
+pub async fn generate_post_variation(
+    provider: &AIProvider,
+    model_name: &str,
+    title: &str,
+    content: &str,
+    instruction: Option<&str>,
+) -> Result<PostVariation, String> {
+    let base_url = provider
+        .base_url
+        .clone()
+        .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+    let url = base_url.trim_end_matches('/').to_string();
+    let client = ChatClient::new(provider.api_key.clone(), model_name).with_url(&url);
+
+    let mut system_prompt = "你是一个小红书运营专家。请在保留原意和关键信息的前提下，改写用户提供的笔记标题和正文，让它读起来像另一个博主写的。标题不超过 20 个字。请使用结构化输出返回 title 和 content。".to_string();
+    if let Some(inst) = instruction.filter(|i| !i.trim().is_empty()) {
+        system_prompt.push_str(&format!(" 额外要求：{}", inst));
+    }
+
+    let messages = vec![
+        ChatMessage {
+            role: Role::System,
+            content: vec![ChatMessageContent::Text {
+                text: system_prompt,
+            }],
+        },
+        ChatMessage {
+            role: Role::User,
+            content: vec![ChatMessageContent::Text {
+                text: format!("标题：{}\n\n正文：\n{}", title, content),
+            }],
+        },
+    ];
+
+    client
+        .chat_with_messages::<PostVariation>(messages)
+        .await
+        .map_err(|e| format!("AI 改写失败: {}", e))
+}
+
 #[tauri::command]
 pub async fn generate_ai_image(
     prompt: String,
@@ -231,6 +281,7 @@ pub async fn generate_ai_image(
     model_name: String,
     size: Option<String>,
 ) -> Result<String, String> {
+    let provider = resolve_provider(provider).await?;
     let client = reqwest::Client::new();
     let mut url = provider
         .base_url
@@ -280,171 +331,103 @@ pub async fn generate_ai_image(
     }
 }
 
+/// 获取 AI 提供商列表，API Key 已脱敏
 #[tauri::command]
 pub async fn get_ai_providers() -> Result<Vec<AIProvider>, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let provider_rows = sqlx::query("SELECT id, name, api_key, base_url FROM ai_providers")
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let mut providers = Vec::new();
-    for row in provider_rows {
-        let id: i64 = row.get(0);
+    let pool = crate::storage::pool().await?;
+    let mut providers = ProviderRepo::new(pool).list().await?;
+    for provider in &mut providers {
+        provider.api_key = secrets::mask_secret(&provider.api_key);
+    }
+    Ok(providers)
+}
 
-        let model_rows =
-            sqlx::query("SELECT id, name, model_type FROM ai_models WHERE provider_id = ?")
-                .bind(id)
-                .fetch_all(&pool)
-                .await
-                .map_err(|e| e.to_string())?;
-
-        let models = model_rows
-            .into_iter()
-            .map(|m_row| {
-                let m_type: String = m_row.get(2);
-                AIModel {
-                    id: Some(m_row.get(0)),
-                    provider_id: Some(id),
-                    name: m_row.get(1),
-                    model_type: if m_type == "text" {
-                        AIModelType::Text
-                    } else {
-                        AIModelType::Image
-                    },
-                    supports_structured_output: None,
-                    test_status: None,
-                }
-            })
-            .collect();
-
-        providers.push(AIProvider {
-            id: Some(id),
-            name: row.get(1),
-            api_key: row.get(2),
-            base_url: row.get(3),
-            models,
-        });
+/// 按 id 获取单个 AI 提供商，API Key 已解密，仅供后端发起请求使用
+pub async fn get_ai_provider(id: i64) -> Result<Option<AIProvider>, String> {
+    let pool = crate::storage::pool().await?;
+    match ProviderRepo::new(pool).get(id).await? {
+        Some(mut provider) => {
+            provider.api_key = secrets::decrypt_secret(&provider.api_key)?;
+            Ok(Some(provider))
+        }
+        None => Ok(None),
     }
+}
 
-    Ok(providers)
+/// 前端传回的提供商只带脱敏的 API Key，发起请求前换成数据库里的真实值
+async fn resolve_provider(provider: AIProvider) -> Result<AIProvider, String> {
+    if !secrets::is_masked(&provider.api_key) {
+        return Ok(provider);
+    }
+    let id = provider.id.ok_or("API Key 缺失")?;
+    let stored = get_ai_provider(id)
+        .await?
+        .ok_or_else(|| "未找到 AI 提供商".to_string())?;
+    Ok(AIProvider {
+        api_key: stored.api_key,
+        ..provider
+    })
 }
 
 #[tauri::command]
-pub async fn save_ai_provider(provider: AIProvider) -> Result<i64, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let provider_id = if let Some(id) = provider.id {
-        sqlx::query("UPDATE ai_providers SET name = ?, api_key = ?, base_url = ? WHERE id = ?")
-            .bind(&provider.name)
-            .bind(&provider.api_key)
-            .bind(&provider.base_url)
-            .bind(id)
-            .execute(&pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        // Delete old models and re-insert (simpler than update logic)
-        sqlx::query("DELETE FROM ai_models WHERE provider_id = ?")
-            .bind(id)
-            .execute(&pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        id
-    } else {
-        let res =
-            sqlx::query("INSERT INTO ai_providers (name, api_key, base_url) VALUES (?, ?, ?)")
-                .bind(&provider.name)
-                .bind(&provider.api_key)
-                .bind(&provider.base_url)
-                .execute(&pool)
-                .await
-                .map_err(|e| e.to_string())?;
-        res.last_insert_rowid()
+pub async fn save_ai_provider(mut provider: AIProvider) -> Result<i64, String> {
+    let pool = crate::storage::pool().await?;
+    let repo = ProviderRepo::new(pool);
+
+    // 脱敏值表示 API Key 没有修改，沿用已保存的密文
+    provider.api_key = match provider.id {
+        Some(id) if secrets::is_masked(&provider.api_key) => repo
+            .get(id)
+            .await?
+            .map(|p| p.api_key)
+            .ok_or_else(|| "未找到 AI 提供商".to_string())?,
+        _ => secrets::encrypt_secret(&provider.api_key)?,
     };
 
-    for model in provider.models {
-        let m_type = match model.model_type {
-            AIModelType::Text => "text",
-            AIModelType::Image => "image",
-        };
-        sqlx::query("INSERT INTO ai_models (provider_id, name, model_type) VALUES (?, ?, ?)")
-            .bind(provider_id)
-            .bind(&model.name)
-            .bind(m_type)
-            .execute(&pool)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    Ok(provider_id)
+    repo.save(&provider).await
 }
 
 #[tauri::command]
 pub async fn delete_ai_provider(id: i64) -> Result<(), String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    sqlx::query("DELETE FROM ai_models WHERE provider_id = ?")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    sqlx::query("DELETE FROM ai_providers WHERE id = ?")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+    let pool = crate::storage::pool().await?;
+    ProviderRepo::new(pool).delete(id).await
 }
+
 #[tauri::command]
 pub async fn save_config(key: String, value: String) -> Result<(), String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    sqlx::query("INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)")
-        .bind(key)
-        .bind(value)
-        .execute(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+    if secrets::SECRET_CONFIG_KEYS.contains(&key.as_str()) {
+        return save_secret_config(&key, &value).await;
+    }
+    let pool = crate::storage::pool().await?;
+    ConfigRepo::new(pool).set(&key, &value).await
 }
 
+/// 读取配置项，密钥类配置只返回脱敏值
 #[tauri::command]
 pub async fn get_config_value(key: String) -> Result<Option<String>, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
+    let pool = crate::storage::pool().await?;
+    let value = ConfigRepo::new(pool).get(&key).await?;
+    if secrets::SECRET_CONFIG_KEYS.contains(&key.as_str()) {
+        return Ok(value.map(|v| secrets::mask_secret(&v)));
+    }
+    Ok(value)
+}
 
-    let row = sqlx::query("SELECT value FROM config WHERE key = ?")
-        .bind(key)
-        .fetch_optional(&pool)
+/// 加密保存密钥类配置，例如 REST API Key 和 MCP token
+pub async fn save_secret_config(key: &str, value: &str) -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    ConfigRepo::new(pool)
+        .set(key, &secrets::encrypt_secret(value)?)
         .await
-        .map_err(|e: sqlx::Error| e.to_string())?;
+}
 
-    Ok(row.map(|r: sqlx::sqlite::SqliteRow| r.get(0)))
+/// 读取并解密密钥类配置
+pub async fn get_secret_config(key: &str) -> Result<Option<String>, String> {
+    let pool = crate::storage::pool().await?;
+    match ConfigRepo::new(pool).get(key).await? {
+        Some(stored) => Ok(Some(secrets::decrypt_secret(&stored)?)),
+        None => Ok(None),
+    }
 }
 
 /// 获取无头浏览器模式设置
@@ -547,6 +530,7 @@ pub async fn test_model_chat(
     provider: AIProvider,
     model_name: String,
 ) -> Result<ModelTestResult, String> {
+    let provider = resolve_provider(provider).await?;
     println!("开始测试对话: {}", model_name);
 
     let api_key = provider.api_key.clone();
@@ -610,6 +594,7 @@ pub async fn test_model_structured_output(
     provider: AIProvider,
     model_name: String,
 ) -> Result<ModelTestResult, String> {
+    let provider = resolve_provider(provider).await?;
     println!("开始测试结构化输出: {}", model_name);
 
     let api_key = provider.api_key.clone();
@@ -683,6 +668,7 @@ pub async fn analyze_local_image(
     provider: AIProvider,
     model_name: String,
 ) -> Result<String, String> {
+    let provider = resolve_provider(provider).await?;
     use base64::{engine::general_purpose, Engine as _};
 
     // 读取本地图片文件
@@ -1,12 +1,9 @@
-use crate::ai::get_headless_mode;
-use crate::automation::take_screenshot;
+use crate::automation::driver::Driver;
+use crate::automation::trace::{self, RunKind};
+use crate::automation::{human, site, take_screenshot, wait};
 use crate::model::AIProvider;
-use crate::storage::get_browser_data_dir;
-use crate::util::utils::kill_browser_process;
 use anyhow::anyhow;
 use anyhow::Result;
-use headless_chrome::browser::default_executable;
-use headless_chrome::{Browser, LaunchOptions, Tab};
 use serde::{Deserialize, Serialize};
 use tysm::chat_completions::{ChatClient, ChatMessage, ChatMessageContent, Role};
 #[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
@@ -38,10 +35,12 @@ pub struct UserAnalytics {
     pub period: String,
 }
 
-pub fn fetch_text_only(tab: &Tab) -> Result<String> {
-    let remote_object = tab.evaluate("document.body.innerText", false)?;
+pub fn fetch_text_only(tab: &dyn Driver) -> Result<String> {
+    let value = tab
+        .evaluate("document.body.innerText", false)
+        .map_err(|e| anyhow!(e))?;
 
-    match remote_object.value {
+    match value {
         Some(val) => {
             let text = serde_json::from_value(val)?;
             Ok(text)
@@ -49,57 +48,43 @@ pub fn fetch_text_only(tab: &Tab) -> Result<String> {
         None => Err(anyhow!("Empty value from browser")),
     }
 }
-#[tauri::command]
-pub async fn fetch_user_analytics(phone: String) -> Result<UserAnalytics, String> {
-    println!("Fetching user analytics for phone: {}", phone);
+/// 打开创作者主页并读取页面文字，读取完立即释放浏览器
+pub async fn fetch_home_text(phone: &str) -> Result<String, String> {
+    trace::scope(RunKind::Analytics, phone, read_home_text(phone)).await
+}
 
-    // 清理可能的 SingletonLock 锁文件
-    crate::storage::clear_browser_lock(&phone);
-
-    // 获取无头模式设置
-    let headless = get_headless_mode().await;
-
-    let data_dir = get_browser_data_dir(&phone);
-    let browser = Browser::new(
-        LaunchOptions::default_builder()
-            .headless(headless)
-            .user_data_dir(Some(data_dir.clone()))
-            .path(Some(default_executable().unwrap()))
-            .window_size(Some((1920, 1080)))
-            .enable_gpu(false)
-            .args(vec![
-                std::ffi::OsStr::new("--disable-extensions"),
-                std::ffi::OsStr::new("--disable-blink-features=AutomationControlled"),
-                std::ffi::OsStr::new("--no-first-run"),
-                std::ffi::OsStr::new("--no-default-browser-check"),
-            ])
-            .build()
-            .map_err(|e| format!("Browser build failed: {}", e))?,
-    )
-    .map_err(|e| format!("Browser init failed: {}", e))?;
-
-    let tab = browser
-        .new_tab()
-        .map_err(|e| format!("New tab failed: {}", e))?;
+async fn read_home_text(phone: &str) -> Result<String, String> {
+    trace::step("open_browser");
+    let session = crate::automation::session::acquire(phone).await?;
+    let tab = session.new_tab().await?;
+    trace::attach(&tab);
 
     // 跳转到创作者主页
     println!("Navigating to creator home page...");
-    tab.navigate_to("https://creator.xiaohongshu.com/new/home")
-        .map_err(|e| format!("Navigation failed: {}", e))?;
+    trace::step("navigate");
+    tab.navigate(&site::creator_url("/new/home"))?;
 
-    // 等待页面加载
+    // 等待数据请求结束，不阻塞 tokio 线程
     println!("等待页面加载完成");
+    wait::settle(tab.as_ref()).await;
 
-    std::thread::sleep(std::time::Duration::from_secs(5));
+    // 像浏览一样滚动到底部，同时触发懒加载的数据
+    trace::step("scroll");
+    if let Err(e) = human::scroll_to_bottom(tab.as_ref()).await {
+        println!("Scroll failed: {}", e);
+    }
 
-    take_screenshot(&tab, "数据分析");
+    trace::step("read_text");
+    take_screenshot(tab.as_ref(), "数据分析");
     // 获取页面 HTML
-    let html = fetch_text_only(&tab).map_err(|e| format!("Failed to get page content: {}", e))?;
-
-    kill_browser_process(&browser);
+    fetch_text_only(tab.as_ref()).map_err(|e| format!("Failed to get page content: {}", e))
+}
 
-    //关掉进程
+#[tauri::command]
+pub async fn fetch_user_analytics(phone: String) -> Result<UserAnalytics, String> {
+    println!("Fetching user analytics for phone: {}", phone);
 
+    let html = fetch_home_text(&phone).await?;
     println!("Page HTML length: {}", html.len());
 
     // 检查是否配置了数据分析 AI
@@ -122,10 +107,8 @@ pub async fn fetch_user_analytics(phone: String) -> Result<UserAnalytics, String
         .to_string();
 
     // 获取 AI Provider
-    let providers = crate::ai::get_ai_providers().await?;
-    let provider = providers
-        .into_iter()
-        .find(|p| p.id == Some(provider_id))
+    let provider = crate::ai::get_ai_provider(provider_id)
+        .await?
         .ok_or_else(|| "未找到配置的 AI Provider".to_string())?;
 
     // 使用 AI 分析 HTML 并提取结构化数据
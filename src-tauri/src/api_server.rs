@@ -1,3 +1,5 @@
+use crate::automation::options::PublishOptions;
+use crate::automation::outcome::PublishErrorKind;
 use crate::{ai, auth, automation};
 use salvo::cors::{Cors, CorsHandler};
 use salvo::oapi::extract::*;
@@ -40,7 +42,7 @@ impl EndpointOutRegister for ApiError {
 #[handler]
 async fn auth_middleware(req: &mut Request, res: &mut Response, ctrl: &mut FlowCtrl) {
     // 从配置中读取 API Key
-    let expected_key = match ai::get_config_value("api_key".to_string()).await {
+    let expected_key = match ai::get_secret_config("api_key").await {
         Ok(Some(key)) => key,
         _ => {
             res.status_code(StatusCode::UNAUTHORIZED);
@@ -237,12 +239,9 @@ struct GenerateImageRequest {
 async fn generate_text_api(
     body: JsonBody<GenerateTextRequest>,
 ) -> Result<Json<serde_json::Value>, StatusError> {
-    let providers = ai::get_ai_providers()
+    let provider = ai::get_ai_provider(body.provider_id)
         .await
-        .map_err(|e| StatusError::internal_server_error().brief(e))?;
-    let provider = providers
-        .into_iter()
-        .find(|p| p.id == Some(body.provider_id))
+        .map_err(|e| StatusError::internal_server_error().brief(e))?
         .ok_or_else(|| StatusError::not_found().brief("Provider not found"))?;
 
     match ai::generate_ai_text(body.prompt.clone(), None, provider, body.model_name.clone()).await {
@@ -269,12 +268,9 @@ async fn generate_text_api(
 async fn generate_image_api(
     body: JsonBody<GenerateImageRequest>,
 ) -> Result<Json<serde_json::Value>, StatusError> {
-    let providers = ai::get_ai_providers()
+    let provider = ai::get_ai_provider(body.provider_id)
         .await
-        .map_err(|e| StatusError::internal_server_error().brief(e))?;
-    let provider = providers
-        .into_iter()
-        .find(|p| p.id == Some(body.provider_id))
+        .map_err(|e| StatusError::internal_server_error().brief(e))?
         .ok_or_else(|| StatusError::not_found().brief("Provider not found"))?;
 
     match ai::generate_ai_image(
@@ -309,6 +305,18 @@ struct Post {
     /// 封面图片路径
     #[serde(skip_serializing_if = "Option::is_none")]
     cover_image: Option<String>,
+    /// 视频路径，填写时保存为视频笔记
+    #[serde(skip_serializing_if = "Option::is_none")]
+    video_path: Option<String>,
+    /// 视频封面取第几秒的画面
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cover_time: Option<f64>,
+    /// 话题名称
+    #[serde(default)]
+    topics: Vec<String>,
+    /// 要 @ 的用户昵称
+    #[serde(default)]
+    mentions: Vec<String>,
     /// 用户 ID
     user_id: i64,
 }
@@ -352,6 +360,10 @@ async fn save_post_api(
         body.post.content.clone(),
         body.post.images.clone(),
         body.post.cover_image.clone(),
+        body.post.video_path.clone(),
+        body.post.cover_time,
+        Some(body.post.topics.clone()),
+        Some(body.post.mentions.clone()),
     )
     .await
     {
@@ -424,11 +436,27 @@ struct PublishPostRequest {
     /// 正文内容
     #[salvo(schema(example = "今天给大家分享几个超美的拍照地点..."))]
     content: String,
-    /// 图片路径列表
+    /// 图片列表，每项可以是本地路径、http(s) 链接、`asset:素材文件名` 或 base64 数据
+    #[serde(default)]
     images: Vec<String>,
-    /// 封面图片路径
+    /// 封面图片，格式同 images
     #[serde(skip_serializing_if = "Option::is_none")]
     cover_image: Option<String>,
+    /// 视频路径，填写时发布视频笔记
+    #[serde(skip_serializing_if = "Option::is_none")]
+    video_path: Option<String>,
+    /// 视频封面取第几秒的画面，未设置 cover_image 时生效
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cover_time: Option<f64>,
+    /// 话题名称，不需要带 #
+    #[serde(skip_serializing_if = "Option::is_none")]
+    topics: Option<Vec<String>>,
+    /// 要 @ 的用户昵称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mentions: Option<Vec<String>>,
+    /// 发布设置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<PublishOptions>,
 }
 
 /// 发布笔记到小红书
@@ -437,10 +465,10 @@ struct PublishPostRequest {
 #[endpoint(
     tags("发布功能"),
     responses(
-        (status_code = 200, description = "发布任务已提交", body = inline(serde_json::Value)),
-        (status_code = 400, description = "参数错误"),
-        (status_code = 401, description = "用户未登录"),
-        (status_code = 500, description = "发布失败"),
+        (status_code = 200, description = "发布成功，返回笔记 id 和链接；试运行时返回各步骤截图", body = inline(serde_json::Value)),
+        (status_code = 400, description = "参数错误或笔记未通过发布前检查"),
+        (status_code = 401, description = "账号登录已失效"),
+        (status_code = 500, description = "发布失败，brief 中包含失败类型"),
     ),
     security(
         ("api_key" = [])
@@ -449,19 +477,42 @@ struct PublishPostRequest {
 async fn publish_post_api(
     body: JsonBody<PublishPostRequest>,
 ) -> Result<Json<serde_json::Value>, StatusError> {
+    if let Some(options) = &body.options {
+        options
+            .validate()
+            .map_err(|e| StatusError::bad_request().brief(e))?;
+    }
     match automation::publish_post(
         body.phone.clone(),
         body.title.clone(),
         body.content.clone(),
         body.images.clone(),
         body.cover_image.clone(),
+        body.video_path.clone(),
+        body.cover_time,
+        body.topics.clone(),
+        body.mentions.clone(),
+        body.options.clone(),
     )
     .await
     {
-        Ok(_) => Ok(Json(
-            serde_json::json!({"success": true, "message": "发布任务已提交"}),
-        )),
-        Err(e) => Err(StatusError::internal_server_error().brief(e)),
+        Ok(note) => Ok(Json(serde_json::json!({
+            "success": true,
+            "message": note.mode.done_message(),
+            "mode": note.mode,
+            "note_id": note.note_id,
+            "note_url": note.note_url,
+            "screenshots": note.screenshots,
+            "unresolved_topics": note.unresolved_topics,
+            "unresolved_mentions": note.unresolved_mentions,
+        }))),
+        Err(failure) if failure.kind == PublishErrorKind::SessionExpired => {
+            Err(StatusError::unauthorized().brief(failure.to_string()))
+        }
+        Err(failure) if failure.kind == PublishErrorKind::InvalidContent => {
+            Err(StatusError::bad_request().brief(failure.to_string()))
+        }
+        Err(failure) => Err(StatusError::internal_server_error().brief(failure.to_string())),
     }
 }
 
@@ -1,15 +1,17 @@
-use crate::automation::take_screenshot;
-use crate::storage::{get_browser_data_dir, get_db_path};
-use headless_chrome::browser::default_executable;
-use headless_chrome::{Browser, LaunchOptions, Tab};
+use crate::automation::driver::Driver;
+use crate::automation::session::{self, BrowserSession};
+use crate::automation::trace::{self, RunKind};
+use crate::automation::wait::{self, Step};
+use crate::automation::{is_login_page, selectors, site, take_screenshot};
+use crate::model::{FieldChange, MediaType, Post, PostRevision, User};
+use crate::storage::get_browser_data_dir;
+use crate::storage::repository::{PostEdit, PostRepo, UserRepo};
 use lazy_static::lazy_static;
 use serde::Deserialize;
-use sqlx::{sqlite::SqlitePoolOptions, Row};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tokio::time::sleep;
+use std::time::{Duration, Instant};
 
 // 小红书用户信息结构
 #[derive(Debug, Deserialize)]
@@ -23,13 +25,14 @@ pub struct XhsUserInfo {
     pub role: String,
 }
 
-// 用于保存在进行的登录会话
+// 用于保存在进行的登录会话，登录完成或取消前独占该账号的浏览器
 #[derive(Clone)]
 pub struct LoginSession {
-    pub tab: Arc<Tab>,
-    pub browser: Browser,
+    pub tab: Arc<dyn Driver>,
+    pub session: Arc<BrowserSession>,
     pub phone: String,
     pub data_dir: PathBuf,
+    pub started_at: Instant,
 }
 
 lazy_static! {
@@ -42,6 +45,14 @@ pub fn get_active_session(phone: &str) -> Option<LoginSession> {
     sessions.get(phone).cloned()
 }
 
+/// 清理开始超过 `max_age` 仍未完成的登录会话
+pub fn evict_stale_logins(max_age: Duration) {
+    BROWSER_SESSIONS
+        .lock()
+        .unwrap()
+        .retain(|_, session| session.started_at.elapsed() < max_age);
+}
+
 #[tauri::command]
 pub async fn start_login_process(phone: String) -> Result<String, String> {
     println!("开始登录流程: {:?}", phone);
@@ -65,65 +76,47 @@ pub async fn start_login_process(phone: String) -> Result<String, String> {
     }
 
     // 未登录，执行正常的登录流程
+    trace::scope(RunKind::Login, &phone, send_login_code(&phone)).await
+}
+
+/// 打开登录页并发送验证码，浏览器保留到提交验证码或取消登录
+async fn send_login_code(phone: &str) -> Result<String, String> {
     println!("开始新的登录流程: {:?}", phone);
-    let data_dir = get_browser_data_dir(&phone);
+    let data_dir = get_browser_data_dir(phone);
 
-    // 启动前先清理可能的 SingletonLock 锁文件，防止进程卡死
-    crate::storage::clear_browser_lock(&phone);
-
-    // 获取无头模式设置
-    let headless = crate::ai::get_headless_mode().await;
-
-    let browser = Browser::new(
-        LaunchOptions::default_builder()
-            .headless(headless)
-            .user_data_dir(Some(data_dir.clone()))
-            .path(Some(default_executable().unwrap()))
-            .window_size(Some((1920, 1080)))
-            .enable_gpu(false)
-            .args(vec![
-                std::ffi::OsStr::new("--disable-extensions"),
-                std::ffi::OsStr::new("--disable-blink-features=AutomationControlled"),
-                std::ffi::OsStr::new("--no-first-run"),
-                std::ffi::OsStr::new("--no-default-browser-check"),
-            ])
-            .build()
-            .map_err(|e| e.to_string())?,
-    )
-    .map_err(|e| e.to_string())?;
+    // 重新发送验证码时先结束上一次的登录会话
+    BROWSER_SESSIONS.lock().unwrap().remove(phone);
+    trace::step("open_browser");
+    let session = session::acquire(phone).await?;
 
     println!("创建浏览器完毕,开始执行登录流程");
-    let tab = browser.new_tab().map_err(|e| e.to_string())?;
-    tab.navigate_to("https://creator.xiaohongshu.com/login")
-        .map_err(|e| e.to_string())?;
-
-    take_screenshot(&tab, "登录页面start01");
-    sleep(Duration::from_secs(3)).await;
-    take_screenshot(&tab, "登录页面start02");
-    // 等待手机号输入框并输入
-    let phone_input = tab
-        .wait_for_element("input[placeholder='手机号']")
-        .map_err(|e| e.to_string())?;
-    phone_input.type_into(&phone).map_err(|e| e.to_string())?;
+    let tab = session.new_tab().await?;
+    trace::attach(&tab);
+    trace::step("navigate");
+    tab.navigate(&site::creator_url("/login"))?;
+
+    take_screenshot(tab.as_ref(), "登录页面start01");
+    wait::for_selector(tab.as_ref(), "login.phone_input", Step::PageLoad).await?;
+    take_screenshot(tab.as_ref(), "登录页面start02");
+    // 输入手机号
+    trace::step("send_code");
+    selectors::type_into(tab.as_ref(), "login.phone_input", phone).await?;
 
     // 点击发送验证码
-    let code_button = tab
-        .wait_for_elements_by_xpath("//div[text()='发送验证码']")
-        .map_err(|e| e.to_string())?
-        .into_iter()
-        .next()
-        .ok_or("无法找到发送验证码按钮")?;
-    code_button.click().map_err(|e| e.to_string())?;
+    selectors::click(tab.as_ref(), "login.send_code")
+        .await
+        .map_err(|e| format!("无法找到发送验证码按钮: {}", e))?;
 
     // 保存会话到全局 Map
     let mut sessions = BROWSER_SESSIONS.lock().unwrap();
     sessions.insert(
-        phone.clone(),
+        phone.to_string(),
         LoginSession {
             tab,
-            browser,
-            phone,
+            session: Arc::new(session),
+            phone: phone.to_string(),
             data_dir,
+            started_at: Instant::now(),
         },
     );
 
@@ -134,33 +127,36 @@ pub async fn start_login_process(phone: String) -> Result<String, String> {
 pub async fn submit_verification_code(
     phone: String,
     code: String,
-) -> Result<crate::model::User, String> {
-    let session_opt = get_active_session(&phone);
+) -> Result<User, String> {
+    trace::scope(RunKind::Login, &phone, login_with_code(&phone, &code)).await
+}
+
+async fn login_with_code(phone: &str, code: &str) -> Result<User, String> {
+    let session_opt = get_active_session(phone);
 
     if let Some(session) = session_opt {
         let tab = session.tab;
+        trace::attach(&tab);
 
-        let code_input = tab
-            .wait_for_element("input[placeholder='验证码']")
-            .map_err(|e| e.to_string())?;
-        code_input.type_into(&code).map_err(|e| e.to_string())?;
-
-        let submit_button = tab
-            .wait_for_elements_by_xpath("//button[contains(., '登 录')]")
-            .map_err(|e| e.to_string())?
-            .into_iter()
-            .next()
-            .ok_or("无法找到登录按钮")?;
-        submit_button.click().map_err(|e| e.to_string())?;
+        trace::step("submit_code");
+        selectors::type_into(tab.as_ref(), "login.code_input", code).await?;
 
-        // 等待登录成功跳转并确保数据刷盘 - 使用异步休眠，防止阻塞 tokio executor
-        tab.wait_until_navigated().map_err(|e| e.to_string())?;
+        selectors::click(tab.as_ref(), "login.submit")
+            .await
+            .map_err(|e| format!("无法找到登录按钮: {}", e))?;
 
-        let cookies = tab.get_cookies().map_err(|e| e.to_string())?;
-        println!("Cookies: {:#?}", cookies);
+        // 等待登录成功离开登录页，再等页面请求结束让用户信息写入 localStorage
+        trace::step("wait_redirect");
+        wait::for_url(tab.as_ref(), "登录成功跳转", Step::Login, |url| {
+            !is_login_page(url)
+        })
+        .await?;
+        wait::settle(tab.as_ref()).await;
 
         // 获取用户信息
-        let local_user_info: Option<XhsUserInfo> = tab.get_storage("USER_INFO_FOR_BIZ").ok();
+        let local_user_info: Option<XhsUserInfo> = tab
+            .local_storage("USER_INFO_FOR_BIZ")
+            .and_then(|value| serde_json::from_str(&value).ok());
         println!("Local Storage: {:#?}", local_user_info);
 
         let (nickname, avatar) = if let Some(info) = local_user_info {
@@ -169,35 +165,14 @@ pub async fn submit_verification_code(
             ("小红书用户".to_string(), None)
         };
 
-        // 迁移临时目录到正式的 profile 目录 (可选，或者直接重命名)
-        // 这里简单处理：记录到数据库
-
-        // 记录到数据库
-        let db_url = get_db_path();
-        let pool = SqlitePoolOptions::new()
-            .connect(&db_url)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        sqlx::query(
-            "INSERT OR REPLACE INTO users (nickname, phone, avatar, created_at) VALUES (?, ?, ?, ?)"
-        )
-        .bind(&nickname)
-        .bind(&session.phone)
-        .bind(&avatar)
-        .bind(chrono::Local::now().to_rfc3339())
-        .execute(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let user = crate::model::User {
-            id: chrono::Local::now().timestamp(), // 这里之后可以查下插入后的 ID
-            nickname,
-            phone: session.phone.clone(),
-            avatar,
-            created_at: chrono::Local::now().to_rfc3339(),
-        };
+        // 记录到数据库，返回带真实 id 的用户
+        let pool = crate::storage::pool().await?;
+        let user = UserRepo::new(pool)
+            .upsert(&nickname, &session.phone, avatar.as_deref())
+            .await?;
 
+        // 登录完成，释放浏览器给其他任务
+        BROWSER_SESSIONS.lock().unwrap().remove(phone);
         Ok(user)
     } else {
         Err("No active login session found".to_string())
@@ -205,58 +180,15 @@ pub async fn submit_verification_code(
 }
 
 #[tauri::command]
-pub async fn get_users() -> Result<Vec<crate::model::User>, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let rows = sqlx::query("SELECT id, nickname, phone, avatar, created_at FROM users")
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let users = rows
-        .into_iter()
-        .map(|row| crate::model::User {
-            id: row.get(0),
-            nickname: row.get(1),
-            phone: row.get(2),
-            avatar: row.get(3),
-            created_at: row.get(4),
-        })
-        .collect();
-
-    Ok(users)
+pub async fn get_users() -> Result<Vec<User>, String> {
+    let pool = crate::storage::pool().await?;
+    UserRepo::new(pool).list().await
 }
 
 #[tauri::command]
-pub async fn find_user(query: String) -> Result<Option<crate::model::User>, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let row = sqlx::query(
-        "SELECT id, nickname, phone, avatar, created_at FROM users WHERE phone = ? OR nickname = ?",
-    )
-    .bind(&query)
-    .bind(&query)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    let user = row.map(|r| crate::model::User {
-        id: r.get(0),
-        nickname: r.get(1),
-        phone: r.get(2),
-        avatar: r.get(3),
-        created_at: r.get(4),
-    });
-
-    Ok(user)
+pub async fn find_user(query: String) -> Result<Option<User>, String> {
+    let pool = crate::storage::pool().await?;
+    UserRepo::new(pool).find(&query).await
 }
 
 #[tauri::command]
@@ -280,11 +212,12 @@ pub async fn open_user_data_dir(phone: String) -> Result<(), String> {
 
 #[tauri::command]
 pub async fn logout_user(phone: String) -> Result<(), String> {
-    // 1. 清理内存会话
+    // 1. 清理内存会话并关闭浏览器
     {
         let mut sessions = BROWSER_SESSIONS.lock().unwrap();
         sessions.remove(&phone);
     }
+    session::close_account(&phone);
 
     // 2. 删除文件数据
     let data_dir = get_browser_data_dir(&phone);
@@ -292,117 +225,124 @@ pub async fn logout_user(phone: String) -> Result<(), String> {
         std::fs::remove_dir_all(data_dir).map_err(|e| e.to_string())?;
     }
 
-    // 2. 数据库操作
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 获取 user_id 以便删除关联数据
-    let user_row = sqlx::query("SELECT id FROM users WHERE phone = ?")
-        .bind(&phone)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if let Some(row) = user_row {
-        let user_id: i64 = row.get(0);
-
-        // 删除该用户的所有帖子 (处理外键约束)
-        sqlx::query("DELETE FROM posts WHERE user_id = ?")
-            .bind(user_id)
-            .execute(&pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        // 删除用户记录
-        sqlx::query("DELETE FROM users WHERE id = ?")
-            .bind(user_id)
-            .execute(&pool)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    Ok(())
+    // 3. 数据库操作
+    let pool = crate::storage::pool().await?;
+    UserRepo::new(pool).delete_by_phone(&phone).await
 }
 
+/// 保存草稿，传入 `video_path` 时保存为视频笔记
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn save_post(
     user_id: i64,
     title: String,
     content: String,
     images: Vec<String>,
     cover_image: Option<String>,
+    video_path: Option<String>,
+    cover_time: Option<f64>,
+    topics: Option<Vec<String>>,
+    mentions: Option<Vec<String>>,
 ) -> Result<i64, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let images_json = serde_json::to_string(&images).map_err(|e| e.to_string())?;
-
-    let res = sqlx::query(
-        "INSERT INTO posts (user_id, title, content, images, cover_image, status) VALUES (?, ?, ?, ?, ?, 'draft')",
-    )
-    .bind(user_id)
-    .bind(title)
-    .bind(content)
-    .bind(images_json)
-    .bind(cover_image)
-    .execute(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    Ok(res.last_insert_rowid())
+    let pool = crate::storage::pool().await?;
+    let edit = PostEdit {
+        title,
+        content,
+        images,
+        cover_image,
+        media_type: MediaType::from_video_path(video_path.as_deref()),
+        video_path,
+        cover_time,
+        topics: topics.unwrap_or_default(),
+        mentions: mentions.unwrap_or_default(),
+    };
+    PostRepo::new(pool).create(user_id, &edit).await
 }
+
+/// 更新草稿，`expected_version` 与当前版本不一致时返回冲突错误
 #[tauri::command]
-pub async fn get_posts(user_id: i64) -> Result<Vec<serde_json::Value>, String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
+#[allow(clippy::too_many_arguments)]
+pub async fn update_post(
+    post_id: i64,
+    expected_version: i64,
+    title: String,
+    content: String,
+    images: Vec<String>,
+    cover_image: Option<String>,
+    video_path: Option<String>,
+    cover_time: Option<f64>,
+    topics: Option<Vec<String>>,
+    mentions: Option<Vec<String>>,
+    source: Option<String>,
+) -> Result<Post, String> {
+    let pool = crate::storage::pool().await?;
+    let edit = PostEdit {
+        title,
+        content,
+        images,
+        cover_image,
+        media_type: MediaType::from_video_path(video_path.as_deref()),
+        video_path,
+        cover_time,
+        topics: topics.unwrap_or_default(),
+        mentions: mentions.unwrap_or_default(),
+    };
+    PostRepo::new(pool)
+        .update(
+            post_id,
+            expected_version,
+            &edit,
+            source.as_deref().unwrap_or("manual"),
+        )
         .await
-        .map_err(|e| e.to_string())?;
+}
 
-    let rows = sqlx::query("SELECT id, title, content, images, cover_image, status, created_at FROM posts WHERE user_id = ? ORDER BY created_at DESC")
-        .bind(user_id)
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let mut posts = Vec::new();
-    for row in rows {
-        let images_str: String = row.get(3);
-        let images: Vec<String> = serde_json::from_str(&images_str).unwrap_or_default();
-
-        posts.push(serde_json::json!({
-            "id": row.get::<i64, _>(0),
-            "title": row.get::<String, _>(1),
-            "content": row.get::<String, _>(2),
-            "images": images,
-            "coverImage": row.get::<Option<String>, _>(4),
-            "status": row.get::<String, _>(5),
-            "created_at": row.get::<String, _>(6),
-        }));
-    }
+#[tauri::command]
+pub async fn list_post_revisions(post_id: i64) -> Result<Vec<PostRevision>, String> {
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool).list_revisions(post_id).await
+}
 
-    Ok(posts)
+/// 逐字段比较草稿的两个版本
+#[tauri::command]
+pub async fn diff_post_revisions(
+    post_id: i64,
+    from_version: i64,
+    to_version: i64,
+) -> Result<Vec<FieldChange>, String> {
+    let pool = crate::storage::pool().await?;
+    let repo = PostRepo::new(pool);
+    let from = repo
+        .get_revision(post_id, from_version)
+        .await?
+        .ok_or_else(|| format!("版本 v{} 不存在", from_version))?;
+    let to = repo
+        .get_revision(post_id, to_version)
+        .await?
+        .ok_or_else(|| format!("版本 v{} 不存在", to_version))?;
+    Ok(from.diff(&to))
 }
 
 #[tauri::command]
-pub async fn delete_post(post_id: i64) -> Result<(), String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
+pub async fn restore_post_revision(
+    post_id: i64,
+    version: i64,
+    expected_version: i64,
+) -> Result<Post, String> {
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool)
+        .restore_revision(post_id, version, expected_version)
         .await
-        .map_err(|e| e.to_string())?;
+}
 
-    sqlx::query("DELETE FROM posts WHERE id = ?")
-        .bind(post_id)
-        .execute(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub async fn get_posts(user_id: i64) -> Result<Vec<Post>, String> {
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool).list_by_user(user_id).await
+}
 
-    Ok(())
+#[tauri::command]
+pub async fn delete_post(post_id: i64) -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool).delete(post_id).await
 }
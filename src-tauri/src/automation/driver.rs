@@ -0,0 +1,263 @@
+use headless_chrome::browser::tab::ResponseHandler;
+use headless_chrome::protocol::cdp::{Input, Page};
+use headless_chrome::{Element, Tab};
+use serde_json::Value;
+
+/// 网络响应回调，参数为响应的 url 和内容
+pub type ResponseCallback = Box<dyn Fn(&str, &str) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    Pressed,
+    /// 按住左键移动，用于拖动
+    Moved,
+    Released,
+    /// 不按键移动指针
+    Hover,
+}
+
+/// 自动化流程对页面的全部操作
+///
+/// 元素都用 CSS 选择器或 XPath 定位，流程代码不直接依赖具体的浏览器库。
+pub trait Driver: Send + Sync {
+    /// 打开页面并等待加载完成
+    fn navigate(&self, url: &str) -> Result<(), String>;
+    fn current_url(&self) -> String;
+
+    /// 等待元素出现
+    fn wait_for(&self, selector: &str) -> Result<(), String>;
+    /// 等待 XPath 匹配到元素，返回匹配数量
+    fn wait_for_xpath(&self, xpath: &str) -> Result<usize, String>;
+    /// 当前匹配选择器的元素数量，不等待
+    fn count(&self, selector: &str) -> usize;
+    /// 当前匹配 XPath 的元素数量，不等待
+    fn count_xpath(&self, xpath: &str) -> usize;
+
+    /// 等待元素出现后点击第一个
+    fn click(&self, selector: &str) -> Result<(), String>;
+    /// 点击当前匹配选择器的第 `index` 个元素，不等待
+    fn click_nth(&self, selector: &str, index: usize) -> Result<(), String>;
+    /// 点击 XPath 匹配的第 `index` 个元素
+    fn click_xpath(&self, xpath: &str, index: usize) -> Result<(), String>;
+
+    /// 点击元素后输入文字
+    fn type_into(&self, selector: &str, text: &str) -> Result<(), String>;
+    /// 向当前获得焦点的元素输入文字
+    fn type_text(&self, text: &str) -> Result<(), String>;
+    fn press_key(&self, key: &str) -> Result<(), String>;
+    /// 给文件输入框设置文件
+    fn set_files(&self, selector: &str, files: &[&str]) -> Result<(), String>;
+
+    fn inner_text(&self, selector: &str) -> Result<String, String>;
+    fn attribute(&self, selector: &str, name: &str) -> Result<Option<String>, String>;
+    /// 执行脚本，返回可以序列化的结果
+    fn evaluate(&self, script: &str, await_promise: bool) -> Result<Option<Value>, String>;
+    fn local_storage(&self, key: &str) -> Option<String>;
+    /// 之后每次打开新页面时先执行的脚本
+    fn add_init_script(&self, script: &str) -> Result<(), String>;
+
+    /// 当前页面的 PNG 截图
+    fn screenshot(&self) -> Result<Vec<u8>, String>;
+    /// 在页面坐标上发送鼠标事件
+    fn mouse(&self, action: MouseAction, x: f64, y: f64) -> Result<(), String>;
+
+    /// 监听 url 满足 `accept` 的网络响应
+    fn on_response(
+        &self,
+        name: &str,
+        accept: fn(&str) -> bool,
+        callback: ResponseCallback,
+    ) -> Result<(), String>;
+    fn remove_response_handler(&self, name: &str);
+}
+
+fn nth_element<'a>(tab: &'a Tab, selector: &str, index: usize) -> Result<Element<'a>, String> {
+    tab.find_elements(selector)
+        .map_err(|e| format!("Cannot find '{}': {}", selector, e))?
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| format!("Cannot find '{}' (No match found at {})", selector, index))
+}
+
+impl Driver for Tab {
+    fn navigate(&self, url: &str) -> Result<(), String> {
+        self.navigate_to(url)
+            .and_then(|tab| tab.wait_until_navigated())
+            .map(|_| ())
+            .map_err(|e| format!("Navigation failed: {}", e))
+    }
+
+    fn current_url(&self) -> String {
+        self.get_url()
+    }
+
+    fn wait_for(&self, selector: &str) -> Result<(), String> {
+        self.wait_for_element(selector)
+            .map(|_| ())
+            .map_err(|e| format!("Error waiting for {}: {}", selector, e))
+    }
+
+    fn wait_for_xpath(&self, xpath: &str) -> Result<usize, String> {
+        self.wait_for_elements_by_xpath(xpath)
+            .map(|elements| elements.len())
+            .map_err(|e| format!("Error waiting for {}: {}", xpath, e))
+    }
+
+    fn count(&self, selector: &str) -> usize {
+        self.find_elements(selector).map(|e| e.len()).unwrap_or(0)
+    }
+
+    fn count_xpath(&self, xpath: &str) -> usize {
+        self.find_elements_by_xpath(xpath)
+            .map(|e| e.len())
+            .unwrap_or(0)
+    }
+
+    fn click(&self, selector: &str) -> Result<(), String> {
+        self.wait_for_element(selector)
+            .map_err(|e| format!("Error waiting for {}: {}", selector, e))?
+            .click()
+            .map(|_| ())
+            .map_err(|e| format!("Click {} failed: {}", selector, e))
+    }
+
+    fn click_nth(&self, selector: &str, index: usize) -> Result<(), String> {
+        nth_element(self, selector, index)?
+            .click()
+            .map(|_| ())
+            .map_err(|e| format!("Click {} failed: {}", selector, e))
+    }
+
+    fn click_xpath(&self, xpath: &str, index: usize) -> Result<(), String> {
+        self.wait_for_elements_by_xpath(xpath)
+            .map_err(|e| format!("Cannot find {}: {}", xpath, e))?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| format!("Cannot find {} (No match found)", xpath))?
+            .click()
+            .map(|_| ())
+            .map_err(|e| format!("Click {} failed: {}", xpath, e))
+    }
+
+    fn type_into(&self, selector: &str, text: &str) -> Result<(), String> {
+        let element = self
+            .wait_for_element(selector)
+            .map_err(|e| format!("Error waiting for {}: {}", selector, e))?;
+        element
+            .click()
+            .map_err(|e| format!("Click {} failed: {}", selector, e))?;
+        element
+            .type_into(text)
+            .map(|_| ())
+            .map_err(|e| format!("Type into {} failed: {}", selector, e))
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), String> {
+        self.type_str(text).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    fn press_key(&self, key: &str) -> Result<(), String> {
+        Tab::press_key(self, key)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_files(&self, selector: &str, files: &[&str]) -> Result<(), String> {
+        self.find_element(selector)
+            .map_err(|e| format!("Cannot find {}: {}", selector, e))?
+            .set_input_files(files)
+            .map(|_| ())
+            .map_err(|e| format!("Set files on {} failed: {}", selector, e))
+    }
+
+    fn inner_text(&self, selector: &str) -> Result<String, String> {
+        self.wait_for_element(selector)
+            .and_then(|el| el.get_inner_text())
+            .map_err(|e| format!("Read {} failed: {}", selector, e))
+    }
+
+    fn attribute(&self, selector: &str, name: &str) -> Result<Option<String>, String> {
+        let attrs = self
+            .wait_for_element(selector)
+            .and_then(|el| el.get_attributes())
+            .map_err(|e| format!("Read {} failed: {}", selector, e))?
+            .unwrap_or_default();
+        // 属性列表按 名称, 值, 名称, 值... 排列
+        Ok(attrs
+            .chunks(2)
+            .find(|pair| pair[0] == name)
+            .and_then(|pair| pair.get(1).cloned()))
+    }
+
+    fn evaluate(&self, script: &str, await_promise: bool) -> Result<Option<Value>, String> {
+        Tab::evaluate(self, script, await_promise)
+            .map(|r| r.value)
+            .map_err(|e| e.to_string())
+    }
+
+    fn local_storage(&self, key: &str) -> Option<String> {
+        let script = format!("localStorage.getItem({})", serde_json::json!(key));
+        Driver::evaluate(self, &script, false)
+            .ok()
+            .flatten()
+            .and_then(|v| v.as_str().map(str::to_string))
+    }
+
+    fn add_init_script(&self, script: &str) -> Result<(), String> {
+        self.call_method(Page::AddScriptToEvaluateOnNewDocument {
+            source: script.to_string(),
+            ..Default::default()
+        })
+        .map(|_| ())
+        .map_err(|e| format!("Add init script failed: {}", e))
+    }
+
+    fn screenshot(&self) -> Result<Vec<u8>, String> {
+        self.capture_screenshot(Page::CaptureScreenshotFormatOption::Png, None, None, true)
+            .map_err(|e| e.to_string())
+    }
+
+    fn mouse(&self, action: MouseAction, x: f64, y: f64) -> Result<(), String> {
+        use Input::DispatchMouseEventTypeOption as Kind;
+        let (kind, button, buttons) = match action {
+            MouseAction::Pressed => (Kind::MousePressed, Input::MouseButton::Left, 1),
+            MouseAction::Moved => (Kind::MouseMoved, Input::MouseButton::Left, 1),
+            MouseAction::Released => (Kind::MouseReleased, Input::MouseButton::Left, 0),
+            MouseAction::Hover => (Kind::MouseMoved, Input::MouseButton::None, 0),
+        };
+        self.call_method(Input::DispatchMouseEvent {
+            Type: kind,
+            x,
+            y,
+            button: Some(button),
+            buttons: Some(buttons),
+            click_count: Some(1),
+            ..Default::default()
+        })
+        .map(|_| ())
+        .map_err(|e| format!("Mouse event failed: {}", e))
+    }
+
+    fn on_response(
+        &self,
+        name: &str,
+        accept: fn(&str) -> bool,
+        callback: ResponseCallback,
+    ) -> Result<(), String> {
+        let handler: ResponseHandler = Box::new(move |params, fetch_body| {
+            if !accept(&params.response.url) {
+                return;
+            }
+            if let Ok(body) = fetch_body() {
+                callback(&params.response.url, &body.body);
+            }
+        });
+        self.register_response_handling(name, handler)
+            .map(|_| ())
+            .map_err(|e| format!("Register response handler failed: {}", e))
+    }
+
+    fn remove_response_handler(&self, name: &str) {
+        let _ = self.deregister_response_handling(name);
+    }
+}
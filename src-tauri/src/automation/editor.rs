@@ -0,0 +1,120 @@
+use super::driver::Driver;
+use super::human;
+use super::selectors;
+use super::wait::{self, Step};
+
+/// 去掉首尾空白和开头的 `#` / `@`，并去重
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches(['#', '@']).trim();
+        if !tag.is_empty() && !result.iter().any(|t| t == tag) {
+            result.push(tag.to_string());
+        }
+    }
+    result
+}
+
+/// 在候选列表中找与 `wanted` 完全一致的一项
+///
+/// 候选项第一行是话题名或昵称，之后是浏览量、小红书号等说明。
+pub fn match_suggestion(candidates: &[String], wanted: &str) -> Option<usize> {
+    let clean = |line: &str| {
+        line.trim()
+            .trim_start_matches(['#', '@'])
+            .trim_start_matches("小红书号：")
+            .trim()
+            .to_string()
+    };
+    candidates
+        .iter()
+        .position(|c| c.lines().next().map(clean).as_deref() == Some(wanted))
+        .or_else(|| {
+            candidates
+                .iter()
+                .position(|c| c.lines().skip(1).any(|line| clean(line) == wanted))
+        })
+}
+
+/// 未能在编辑器中转换为链接的话题和用户
+#[derive(Debug, Default)]
+pub struct UnresolvedTags {
+    pub topics: Vec<String>,
+    pub mentions: Vec<String>,
+}
+
+/// 在正文末尾通过编辑器的候选弹窗插入 @用户 和 #话题
+///
+/// 光标需要已经在正文末尾。找不到对应候选项的保留为普通文字并返回。
+pub async fn insert_tags(
+    tab: &dyn Driver,
+    topics: &[String],
+    mentions: &[String],
+) -> UnresolvedTags {
+    let topics = normalize_tags(topics);
+    let mentions = normalize_tags(mentions);
+    let mut unresolved = UnresolvedTags::default();
+    if topics.is_empty() && mentions.is_empty() {
+        return unresolved;
+    }
+
+    let _ = tab.press_key("Enter");
+    let mention_items = selectors::css("editor.mention_suggestions");
+    let topic_items = selectors::css("editor.topic_suggestions");
+    for name in &mentions {
+        if !insert_one(tab, '@', name, &mention_items).await {
+            unresolved.mentions.push(name.clone());
+        }
+    }
+    for name in &topics {
+        if !insert_one(tab, '#', name, &topic_items).await {
+            unresolved.topics.push(name.clone());
+        }
+    }
+
+    if !unresolved.topics.is_empty() || !unresolved.mentions.is_empty() {
+        log::warn!("Unresolved tags: {:?}", unresolved);
+    }
+    unresolved
+}
+
+async fn insert_one(tab: &dyn Driver, trigger: char, name: &str, selector: &str) -> bool {
+    log::info!("Inserting {}{}", trigger, name);
+    if human::type_text(tab, &format!("{}{}", trigger, name))
+        .await
+        .is_err()
+    {
+        return false;
+    }
+
+    let found = wait::until(Step::Suggestions, "候选列表", || {
+        match_suggestion(&read_suggestions(tab, selector), name)
+    })
+    .await;
+    if let Ok(index) = found {
+        if human::click(tab, selector, index).await.is_ok() {
+            // 等候选列表关闭，再输入下一个话题
+            let _ = wait::until(Step::Suggestions, "候选列表关闭", || {
+                read_suggestions(tab, selector).is_empty().then_some(())
+            })
+            .await;
+            return true;
+        }
+    }
+
+    // 输入空格关闭候选列表，保留普通文字
+    let _ = tab.type_text(" ");
+    false
+}
+
+fn read_suggestions(tab: &dyn Driver, selector: &str) -> Vec<String> {
+    let script = format!(
+        "JSON.stringify(Array.from(document.querySelectorAll({})).map(e => e.innerText))",
+        serde_json::json!(selector)
+    );
+    tab.evaluate(&script, false)
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_str().and_then(|s| serde_json::from_str(s).ok()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,365 @@
+use super::driver::{Driver, MouseAction};
+use super::selectors::is_xpath;
+use crate::storage::repository::ConfigRepo;
+use lazy_static::lazy_static;
+use rand::distr::uniform::SampleUniform;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// 保存在 config 表中的模拟输入设置
+pub const SETTINGS_KEY: &str = "human_input";
+/// 各项间隔允许设置的最大值（毫秒）
+const MAX_DELAY_MS: u64 = 5000;
+
+/// 模拟真人输入的设置，范围都是 `[最小值, 最大值]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HumanInput {
+    /// 关闭时直接填入文字、点击元素中心
+    pub enabled: bool,
+    /// 相邻两个字符的输入间隔（毫秒）
+    pub type_delay_ms: (u64, u64),
+    /// 每输入一个字符后停顿一下的概率
+    pub pause_chance: f64,
+    pub pause_ms: (u64, u64),
+    /// 鼠标移到目标的步数和每步间隔（毫秒）
+    pub move_steps: (usize, usize),
+    pub move_step_ms: (u64, u64),
+    /// 每次滚动的距离（像素）和间隔（毫秒）
+    pub scroll_step_px: (u32, u32),
+    pub scroll_delay_ms: (u64, u64),
+}
+
+impl Default for HumanInput {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            type_delay_ms: (60, 180),
+            pause_chance: 0.05,
+            pause_ms: (300, 900),
+            move_steps: (12, 28),
+            move_step_ms: (8, 20),
+            scroll_step_px: (80, 220),
+            scroll_delay_ms: (30, 90),
+        }
+    }
+}
+
+impl HumanInput {
+    pub fn validate(&self) -> Result<(), String> {
+        let delays = [
+            ("type_delay_ms", self.type_delay_ms),
+            ("pause_ms", self.pause_ms),
+            ("move_step_ms", self.move_step_ms),
+            ("scroll_delay_ms", self.scroll_delay_ms),
+        ];
+        for (name, (min, max)) in delays {
+            if min > max || max > MAX_DELAY_MS {
+                return Err(format!(
+                    "{} 需要满足 最小值 <= 最大值 <= {}",
+                    name, MAX_DELAY_MS
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.pause_chance) {
+            return Err("pause_chance 需要在 0 到 1 之间".to_string());
+        }
+        if self.move_steps.0 == 0 || self.move_steps.0 > self.move_steps.1 {
+            return Err("move_steps 需要满足 1 <= 最小值 <= 最大值".to_string());
+        }
+        if self.scroll_step_px.0 == 0 || self.scroll_step_px.0 > self.scroll_step_px.1 {
+            return Err("scroll_step_px 需要满足 1 <= 最小值 <= 最大值".to_string());
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref SETTINGS: RwLock<HumanInput> = RwLock::new(HumanInput::default());
+    /// 上一次鼠标停留的位置，下一次移动从这里开始
+    static ref POINTER: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+}
+
+pub fn settings() -> HumanInput {
+    SETTINGS.read().unwrap().clone()
+}
+
+/// 从 config 表读取设置，启动时和修改后调用
+pub async fn load() -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    let loaded = ConfigRepo::new(pool)
+        .get(SETTINGS_KEY)
+        .await?
+        .and_then(|value| serde_json::from_str::<HumanInput>(&value).ok())
+        .filter(|s| s.validate().is_ok())
+        .unwrap_or_default();
+    *SETTINGS.write().unwrap() = loaded;
+    Ok(())
+}
+
+fn pick<T: SampleUniform + PartialOrd + Copy>(rng: &mut impl Rng, (min, max): (T, T)) -> T {
+    rng.random_range(min..=max)
+}
+
+/// 每个字符输入后的等待时间
+pub fn typing_delays(settings: &HumanInput, chars: usize, rng: &mut impl Rng) -> Vec<Duration> {
+    (0..chars)
+        .map(|_| {
+            let mut ms = pick(rng, settings.type_delay_ms);
+            if rng.random_bool(settings.pause_chance) {
+                ms += pick(rng, settings.pause_ms);
+            }
+            Duration::from_millis(ms)
+        })
+        .collect()
+}
+
+/// 从 `from` 到 `to` 的弯曲路径，先快后慢，最后一个点正好是 `to`
+pub fn mouse_path(
+    from: (f64, f64),
+    to: (f64, f64),
+    steps: usize,
+    rng: &mut impl Rng,
+) -> Vec<(f64, f64)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let distance = (dx * dx + dy * dy).sqrt();
+    // 控制点沿垂直方向随机偏移，让路径成为一条弧线
+    let (nx, ny) = if distance > 0.0 {
+        (-dy / distance, dx / distance)
+    } else {
+        (0.0, 0.0)
+    };
+    let mut control = |ratio: f64| {
+        let offset = rng.random_range(-0.3..=0.3) * distance;
+        (
+            from.0 + dx * ratio + nx * offset,
+            from.1 + dy * ratio + ny * offset,
+        )
+    };
+    let (c1, c2) = (control(0.3), control(0.7));
+
+    let steps = steps.max(1);
+    (1..=steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            let t = 1.0 - (1.0 - t) * (1.0 - t);
+            let u = 1.0 - t;
+            let x = u * u * u * from.0
+                + 3.0 * u * u * t * c1.0
+                + 3.0 * u * t * t * c2.0
+                + t * t * t * to.0;
+            let y = u * u * u * from.1
+                + 3.0 * u * u * t * c1.1
+                + 3.0 * u * t * t * c2.1
+                + t * t * t * to.1;
+            (x, y)
+        })
+        .collect()
+}
+
+/// 把 `distance` 拆成若干次滚动，正数向下
+pub fn scroll_steps(distance: f64, settings: &HumanInput, rng: &mut impl Rng) -> Vec<f64> {
+    let sign = distance.signum();
+    let mut remaining = distance.abs();
+    let mut steps = Vec::new();
+    while remaining >= 1.0 {
+        let step = (pick(rng, settings.scroll_step_px) as f64).min(remaining);
+        steps.push(sign * step);
+        remaining -= step;
+    }
+    steps
+}
+
+/// 元素在视口中的位置
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub viewport_height: f64,
+}
+
+/// 在元素中间区域随机取一个点击位置
+pub fn click_point(rect: &Rect, rng: &mut impl Rng) -> (f64, f64) {
+    (
+        rect.x + rect.width * rng.random_range(0.3..=0.7),
+        rect.y + rect.height * rng.random_range(0.3..=0.7),
+    )
+}
+
+fn element_rect(tab: &dyn Driver, selector: &str, index: usize) -> Option<Rect> {
+    let find = if is_xpath(selector) {
+        format!(
+            "document.evaluate({}, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null).snapshotItem({})",
+            serde_json::json!(selector),
+            index
+        )
+    } else {
+        format!(
+            "document.querySelectorAll({})[{}]",
+            serde_json::json!(selector),
+            index
+        )
+    };
+    let script = format!(
+        "(() => {{ const el = {}; if (!el) return null; const r = el.getBoundingClientRect(); return JSON.stringify({{ x: r.left, y: r.top, width: r.width, height: r.height, viewport_height: window.innerHeight }}); }})()",
+        find
+    );
+    tab.evaluate(&script, false)
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_str().and_then(|s| serde_json::from_str(s).ok()))
+}
+
+fn direct_click(tab: &dyn Driver, selector: &str, index: usize) -> Result<(), String> {
+    if is_xpath(selector) {
+        tab.click_xpath(selector, index)
+    } else {
+        tab.click_nth(selector, index)
+    }
+}
+
+/// 沿弯曲路径把鼠标移到 `to`
+pub async fn move_to(tab: &dyn Driver, to: (f64, f64)) -> Result<(), String> {
+    move_along(tab, to, MouseAction::Hover).await
+}
+
+/// 按住左键从 `from` 拖到 `to`，拖拽组件需要连续的移动事件才会开始排序
+pub async fn drag(tab: &dyn Driver, from: (f64, f64), to: (f64, f64)) -> Result<(), String> {
+    move_to(tab, from).await?;
+    tab.mouse(MouseAction::Pressed, from.0, from.1)?;
+    move_along(tab, to, MouseAction::Moved).await?;
+    tab.mouse(MouseAction::Released, to.0, to.1)
+}
+
+async fn move_along(tab: &dyn Driver, to: (f64, f64), action: MouseAction) -> Result<(), String> {
+    let settings = settings();
+    let (path, delays) = {
+        let mut rng = rand::rng();
+        let from = POINTER.lock().unwrap().unwrap_or_else(|| {
+            (
+                to.0 + rng.random_range(-300.0..=300.0),
+                to.1 + rng.random_range(-200.0..=200.0),
+            )
+        });
+        let steps = pick(&mut rng, settings.move_steps);
+        let path = mouse_path(from, to, steps, &mut rng);
+        let delays: Vec<u64> = path
+            .iter()
+            .map(|_| pick(&mut rng, settings.move_step_ms))
+            .collect();
+        (path, delays)
+    };
+    for ((x, y), delay) in path.into_iter().zip(delays) {
+        tab.mouse(action, x, y)?;
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+    *POINTER.lock().unwrap() = Some(to);
+    Ok(())
+}
+
+/// 点击 `selector` 匹配的第 `index` 个元素，`selector` 可以是 CSS 或 XPath
+///
+/// 元素不在视口内时先逐步滚动过去，再移动鼠标到元素内的随机位置按下。
+pub async fn click(tab: &dyn Driver, selector: &str, index: usize) -> Result<(), String> {
+    if !settings().enabled {
+        return direct_click(tab, selector, index);
+    }
+    let Some(mut rect) = element_rect(tab, selector, index) else {
+        return direct_click(tab, selector, index);
+    };
+    if rect.y < 0.0 || rect.y + rect.height > rect.viewport_height {
+        scroll_by(tab, rect.y + rect.height / 2.0 - rect.viewport_height / 2.0).await?;
+        rect = element_rect(tab, selector, index).unwrap_or(rect);
+    }
+    if rect.width <= 0.0 || rect.height <= 0.0 {
+        return direct_click(tab, selector, index);
+    }
+
+    let (x, y) = click_point(&rect, &mut rand::rng());
+    move_to(tab, (x, y)).await?;
+    tab.mouse(MouseAction::Pressed, x, y)?;
+    let hold = rand::rng().random_range(40..=120);
+    tokio::time::sleep(Duration::from_millis(hold)).await;
+    tab.mouse(MouseAction::Released, x, y)
+}
+
+/// 向当前获得焦点的元素逐字输入
+pub async fn type_text(tab: &dyn Driver, text: &str) -> Result<(), String> {
+    let settings = settings();
+    if !settings.enabled {
+        return tab.type_text(text);
+    }
+    let delays = typing_delays(&settings, text.chars().count(), &mut rand::rng());
+    let mut buffer = [0u8; 4];
+    for (ch, delay) in text.chars().zip(delays) {
+        tab.type_text(ch.encode_utf8(&mut buffer))?;
+        tokio::time::sleep(delay).await;
+    }
+    Ok(())
+}
+
+/// 点击元素后逐字输入
+pub async fn type_into(tab: &dyn Driver, selector: &str, text: &str) -> Result<(), String> {
+    if !settings().enabled && !is_xpath(selector) {
+        return tab.type_into(selector, text);
+    }
+    click(tab, selector, 0).await?;
+    type_text(tab, text).await
+}
+
+/// 分多次滚动页面，正数向下
+pub async fn scroll_by(tab: &dyn Driver, distance: f64) -> Result<(), String> {
+    let settings = settings();
+    if !settings.enabled {
+        return tab
+            .evaluate(&format!("window.scrollBy(0, {})", distance), false)
+            .map(|_| ());
+    }
+    let (steps, delays): (Vec<f64>, Vec<u64>) = {
+        let mut rng = rand::rng();
+        scroll_steps(distance, &settings, &mut rng)
+            .into_iter()
+            .map(|step| (step, pick(&mut rng, settings.scroll_delay_ms)))
+            .unzip()
+    };
+    for (step, delay) in steps.into_iter().zip(delays) {
+        tab.evaluate(&format!("window.scrollBy(0, {})", step), false)?;
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+    Ok(())
+}
+
+/// 逐步滚动到页面底部
+pub async fn scroll_to_bottom(tab: &dyn Driver) -> Result<(), String> {
+    let remaining = tab
+        .evaluate(
+            "Math.max(0, document.documentElement.scrollHeight - window.innerHeight - window.scrollY)",
+            false,
+        )?
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0);
+    scroll_by(tab, remaining).await
+}
+
+#[tauri::command]
+pub async fn get_human_input() -> Result<HumanInput, String> {
+    Ok(settings())
+}
+
+#[tauri::command]
+pub async fn set_human_input(settings: HumanInput) -> Result<(), String> {
+    settings.validate()?;
+    let pool = crate::storage::pool().await?;
+    ConfigRepo::new(pool)
+        .set(
+            SETTINGS_KEY,
+            &serde_json::to_string(&settings).map_err(|e| e.to_string())?,
+        )
+        .await?;
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use super::validate::MAX_IMAGE_BYTES;
+use base64::Engine;
+use reqwest::{redirect, Url};
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 下载网络图片的超时时间
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+/// 下载网络图片时最多跟随的重定向次数
+const MAX_REDIRECTS: usize = 5;
+
+/// 发布接口接收的图片来源
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// 本机文件路径
+    Path(String),
+    /// http(s) 图片链接
+    Url(String),
+    /// 素材库中的文件名，写作 `asset:文件名`
+    Asset(String),
+    /// base64 图片数据，写作 `data:image/png;base64,...` 或 `base64:...`
+    Base64(String),
+}
+
+/// 判断一个图片参数是哪种来源
+pub fn parse_image_source(input: &str) -> ImageSource {
+    let input = input.trim();
+    let lower = input.to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return ImageSource::Url(input.to_string());
+    }
+    if lower.starts_with("data:") {
+        let data = input.split_once(',').map(|(_, d)| d).unwrap_or_default();
+        return ImageSource::Base64(data.to_string());
+    }
+    if let Some(data) = input.strip_prefix("base64:") {
+        return ImageSource::Base64(data.to_string());
+    }
+    if let Some(id) = input.strip_prefix("asset:") {
+        return ImageSource::Asset(id.trim().to_string());
+    }
+    ImageSource::Path(input.to_string())
+}
+
+/// 根据文件头判断图片格式，只接受平台支持的 jpg、png、webp
+pub fn sniff_image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+/// 检查下载或解码得到的图片内容，返回对应的扩展名
+///
+/// `content_type` 是网络响应的 Content-Type，不是图片类型时直接拒绝。
+pub fn check_image_bytes(bytes: &[u8], content_type: Option<&str>) -> Result<&'static str, String> {
+    if let Some(content_type) = content_type {
+        let content_type = content_type.to_ascii_lowercase();
+        if !content_type.starts_with("image/")
+            && !content_type.starts_with("application/octet-stream")
+        {
+            return Err(format!("不是图片内容: {}", content_type));
+        }
+    }
+    if bytes.is_empty() {
+        return Err("图片内容为空".to_string());
+    }
+    if bytes.len() as u64 > MAX_IMAGE_BYTES {
+        return Err(format!("图片超过 {}MB", MAX_IMAGE_BYTES / 1024 / 1024));
+    }
+    sniff_image_extension(bytes).ok_or_else(|| "不支持的图片格式，支持 jpg/png/webp".to_string())
+}
+
+/// 保存到素材库，文件名取内容的哈希，同一张图片只保存一次
+fn save_to_images_dir(bytes: &[u8], extension: &str) -> Result<String, String> {
+    let digest = Sha256::digest(bytes);
+    let hash: String = digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let path = crate::storage::get_images_dir().join(format!("remote_{}.{}", hash, extension));
+    if !path.exists() {
+        std::fs::write(&path, bytes).map_err(|e| format!("保存图片失败: {}", e))?;
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 是否为公网地址
+///
+/// 图片链接来自 REST 和 MCP 调用方，本机、内网、链路本地等地址一律拒绝，
+/// 避免借下载图片访问本机或内网中的服务。
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // 100.64.0.0/10 运营商级 NAT
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7 唯一本地地址
+                || (first & 0xfe00) == 0xfc00
+                // fe80::/10 链路本地地址
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// 检查链接的协议并解析主机，只有全部地址都是公网地址时才返回
+async fn resolve_public(url: &Url) -> Result<Vec<SocketAddr>, String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("只支持 http(s) 图片链接: {}", url));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("图片链接缺少主机名: {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> =
+        tokio::net::lookup_host((host.trim_start_matches('[').trim_end_matches(']'), port))
+            .await
+            .map_err(|e| format!("解析 {} 失败: {}", host, e))?
+            .collect();
+    if addrs.is_empty() {
+        return Err(format!("解析 {} 失败", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!(
+            "不允许下载内网地址的图片: {} ({})",
+            host,
+            addr.ip()
+        ));
+    }
+    Ok(addrs)
+}
+
+/// 下载网络图片，每次重定向都重新检查目标地址
+async fn download(url: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    let mut url = Url::parse(url).map_err(|e| format!("图片链接无效: {}", e))?;
+    for _ in 0..=MAX_REDIRECTS {
+        let addrs = resolve_public(&url).await?;
+        // 固定使用检查过的地址，防止检查之后 DNS 解析到别的地址；也不走系统代理
+        let mut builder = reqwest::Client::builder()
+            .timeout(DOWNLOAD_TIMEOUT)
+            .redirect(redirect::Policy::none())
+            .no_proxy();
+        if let Some(domain) = url.domain() {
+            builder = builder.resolve_to_addrs(domain, &addrs);
+        }
+        let client = builder.build().map_err(|e| e.to_string())?;
+        let response = client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("下载失败: {}", e))?;
+        if !response.status().is_redirection() {
+            return read_image_response(response).await;
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| format!("下载失败，状态码 {} 但没有跳转地址", response.status()))?;
+        url = url
+            .join(location)
+            .map_err(|e| format!("跳转地址无效: {}", e))?;
+    }
+    Err(format!("下载失败，重定向超过 {} 次", MAX_REDIRECTS))
+}
+
+async fn read_image_response(
+    mut response: reqwest::Response,
+) -> Result<(Vec<u8>, Option<String>), String> {
+    if !response.status().is_success() {
+        return Err(format!("下载失败，状态码 {}", response.status()));
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_IMAGE_BYTES)
+    {
+        return Err(format!("图片超过 {}MB", MAX_IMAGE_BYTES / 1024 / 1024));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    // 服务器没有返回长度时边下载边检查大小
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("下载失败: {}", e))?
+    {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() as u64 > MAX_IMAGE_BYTES {
+            return Err(format!("图片超过 {}MB", MAX_IMAGE_BYTES / 1024 / 1024));
+        }
+    }
+    Ok((bytes, content_type))
+}
+
+/// 把一个图片参数转换为本机文件路径，网络图片和 base64 数据会保存到素材库
+pub async fn resolve_image(input: &str) -> Result<String, String> {
+    match parse_image_source(input) {
+        ImageSource::Path(path) => Ok(path),
+        ImageSource::Asset(id) => {
+            if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+                return Err(format!("素材 id 无效: {}", id));
+            }
+            let path: PathBuf = crate::storage::get_images_dir().join(&id);
+            if !path.is_file() {
+                return Err(format!("素材库中没有图片 {}", id));
+            }
+            Ok(path.to_string_lossy().to_string())
+        }
+        ImageSource::Base64(data) => {
+            let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| format!("base64 解码失败: {}", e))?;
+            let extension = check_image_bytes(&bytes, None)?;
+            save_to_images_dir(&bytes, extension)
+        }
+        ImageSource::Url(url) => {
+            let (bytes, content_type) = download(&url).await?;
+            let extension = check_image_bytes(&bytes, content_type.as_deref())?;
+            save_to_images_dir(&bytes, extension)
+        }
+    }
+}
+
+/// 依次转换图片列表，出错时说明是第几张
+pub async fn resolve_images(inputs: &[String]) -> Result<Vec<String>, String> {
+    let mut paths = Vec::with_capacity(inputs.len());
+    for (i, input) in inputs.iter().enumerate() {
+        let path = resolve_image(input)
+            .await
+            .map_err(|e| format!("images[{}]: {}", i, e))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
@@ -0,0 +1,241 @@
+use super::driver::Driver;
+use super::human;
+use super::outcome::{PublishErrorKind, PublishFailure};
+use super::selectors;
+use super::take_screenshot;
+use super::wait::{self, Step};
+use serde::Deserialize;
+
+/// 标记缩略图对应笔记中第几张图片的属性
+const INDEX_ATTR: &str = "data-helper-index";
+
+/// 上传顺序：封面在最前，其余按笔记中的顺序，重复的只传一次
+pub fn upload_order(images: &[String], cover: &str) -> Vec<String> {
+    let mut order = vec![cover.to_string()];
+    for image in images {
+        if !order.contains(image) {
+            order.push(image.clone());
+        }
+    }
+    order
+}
+
+/// 一张缩略图的状态
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Thumbnail {
+    /// 已标记的图片序号，刚上传的为 None
+    pub index: Option<usize>,
+    #[serde(default)]
+    pub uploading: bool,
+    #[serde(default)]
+    pub failed: bool,
+    #[serde(default)]
+    pub text: String,
+}
+
+/// 把缩略图调整为 0, 1, 2... 顺序需要的拖动步骤，每一步是 (原位置, 目标位置)
+///
+/// 拖动按“取出后插入”计算，与编辑器的拖拽排序一致。
+pub fn reorder_moves(current: &[usize]) -> Vec<(usize, usize)> {
+    let mut order = current.to_vec();
+    let mut sorted = order.clone();
+    sorted.sort_unstable();
+
+    let mut moves = Vec::new();
+    for (target, wanted) in sorted.iter().enumerate() {
+        let from = order.iter().position(|i| i == wanted).unwrap_or(target);
+        if from != target {
+            let item = order.remove(from);
+            order.insert(target, item);
+            moves.push((from, target));
+        }
+    }
+    moves
+}
+
+fn read_thumbnails(tab: &dyn Driver) -> Vec<Thumbnail> {
+    let script = format!(
+        r#"JSON.stringify(Array.from(document.querySelectorAll({selector})).map(e => {{
+            const tag = e.getAttribute({attr});
+            const cls = e.className + ' ' + Array.from(e.querySelectorAll('*')).map(c => c.className).join(' ');
+            const text = e.innerText.trim();
+            return {{
+                index: tag === null ? null : Number(tag),
+                uploading: /loading|uploading|progress/i.test(cls) || text.includes('%'),
+                failed: /fail|error/i.test(cls) || text.includes('失败') || text.includes('重试'),
+                text,
+            }};
+        }}))"#,
+        selector = serde_json::json!(selectors::css("publish.thumbnails")),
+        attr = serde_json::json!(INDEX_ATTR),
+    );
+    tab.evaluate(&script, false)
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_str().and_then(|s| serde_json::from_str(s).ok()))
+        .unwrap_or_default()
+}
+
+/// 给还没有标记的缩略图标上图片序号
+fn tag_new_thumbnails(tab: &dyn Driver, index: usize) {
+    let script = format!(
+        "document.querySelectorAll({}).forEach(e => {{ if (!e.hasAttribute({attr})) e.setAttribute({attr}, '{}'); }})",
+        serde_json::json!(selectors::css("publish.thumbnails")),
+        index,
+        attr = serde_json::json!(INDEX_ATTR),
+    );
+    let _ = tab.evaluate(&script, false);
+}
+
+/// 等待缩略图数量达到 `expected` 且全部上传完成
+async fn wait_for_thumbnails(
+    tab: &dyn Driver,
+    expected: usize,
+    image: &str,
+) -> Result<(), PublishFailure> {
+    let what = format!("图片 {} 上传完成", image);
+    wait::until(Step::ImageUpload, &what, || {
+        let thumbnails = read_thumbnails(tab);
+        if let Some(failed) = thumbnails.iter().find(|t| t.failed) {
+            take_screenshot(tab, "error_image_upload");
+            return Some(Err(PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                format!("图片上传失败: {} {}", image, failed.text),
+            )));
+        }
+        if thumbnails.len() < expected || thumbnails.iter().any(|t| t.uploading) {
+            return None;
+        }
+        if thumbnails.len() > expected {
+            return Some(Err(PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                format!(
+                    "编辑器中有 {} 张图片，应为 {} 张",
+                    thumbnails.len(),
+                    expected
+                ),
+            )));
+        }
+        Some(Ok(()))
+    })
+    .await
+    .map_err(|e| {
+        take_screenshot(tab, "error_image_upload_timeout");
+        PublishFailure::new(
+            PublishErrorKind::Timeout,
+            format!("{}，编辑器中只有 {} 张图片", e, read_thumbnails(tab).len()),
+        )
+    })?
+}
+
+/// 封面上传后在编辑页确认，并标记为第一张
+pub async fn confirm_cover(tab: &dyn Driver, cover: &str) -> Result<(), PublishFailure> {
+    wait_for_thumbnails(tab, 1, cover).await?;
+    tag_new_thumbnails(tab, 0);
+    Ok(())
+}
+
+/// 按顺序逐张上传封面以外的图片，每张都等到编辑器中出现缩略图后再传下一张
+///
+/// `order` 是完整的上传顺序，第一张是已经上传的封面。
+pub async fn upload_remaining(tab: &dyn Driver, order: &[String]) -> Result<(), PublishFailure> {
+    if order.len() <= 1 {
+        return Ok(());
+    }
+    log::info!("Uploading remaining {} images...", order.len() - 1);
+
+    for (index, image) in order.iter().enumerate().skip(1) {
+        log::info!("Uploading image {}/{}: {}", index + 1, order.len(), image);
+        let input = selectors::find(tab, "publish.image_input").ok_or_else(|| {
+            take_screenshot(tab, "error_find_image_input");
+            PublishFailure::new(
+                PublishErrorKind::PageChanged,
+                "Cannot find image input in editor",
+            )
+        })?;
+        tab.set_files(&input, &[image.as_str()]).map_err(|e| {
+            PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                format!("Failed to set image {}: {}", image, e),
+            )
+        })?;
+        wait_for_thumbnails(tab, index + 1, image).await?;
+        tag_new_thumbnails(tab, index);
+    }
+
+    ensure_order(tab, order.len()).await
+}
+
+/// 检查缩略图顺序，不对时拖动调整，保证封面在第一张
+async fn ensure_order(tab: &dyn Driver, expected: usize) -> Result<(), PublishFailure> {
+    let current: Vec<usize> = read_thumbnails(tab)
+        .iter()
+        .filter_map(|t| t.index)
+        .collect();
+    if current.len() != expected {
+        return Err(PublishFailure::new(
+            PublishErrorKind::UploadFailed,
+            format!("编辑器中有 {} 张图片，应为 {} 张", current.len(), expected),
+        ));
+    }
+
+    let moves = reorder_moves(&current);
+    if moves.is_empty() {
+        return Ok(());
+    }
+    log::info!("Reordering images: {:?}", current);
+    for (from, to) in moves {
+        drag_thumbnail(tab, from, to).await?;
+    }
+
+    let current: Vec<usize> = read_thumbnails(tab)
+        .iter()
+        .filter_map(|t| t.index)
+        .collect();
+    if !reorder_moves(&current).is_empty() {
+        take_screenshot(tab, "error_image_order");
+        return Err(PublishFailure::new(
+            PublishErrorKind::PageChanged,
+            format!("图片顺序调整失败，当前顺序 {:?}", current),
+        ));
+    }
+    Ok(())
+}
+
+fn thumbnail_center(tab: &dyn Driver, position: usize) -> Option<(f64, f64)> {
+    let script = format!(
+        "(() => {{ const e = document.querySelectorAll({})[{}]; if (!e) return ''; const r = e.getBoundingClientRect(); return JSON.stringify([r.x + r.width / 2, r.y + r.height / 2]); }})()",
+        serde_json::json!(selectors::css("publish.thumbnails")),
+        position
+    );
+    let value = tab.evaluate(&script, false).ok()??;
+    let point: Vec<f64> = serde_json::from_str(value.as_str()?).ok()?;
+    Some((*point.first()?, *point.get(1)?))
+}
+
+/// 用鼠标把第 `from` 张缩略图拖到第 `to` 张的位置
+async fn drag_thumbnail(tab: &dyn Driver, from: usize, to: usize) -> Result<(), PublishFailure> {
+    let not_found = || {
+        PublishFailure::new(
+            PublishErrorKind::PageChanged,
+            format!("Cannot find image thumbnail {} or {}", from, to),
+        )
+    };
+    let start = thumbnail_center(tab, from).ok_or_else(not_found)?;
+    let end = thumbnail_center(tab, to).ok_or_else(not_found)?;
+    let dragged = read_thumbnails(tab).get(from).and_then(|t| t.index);
+
+    human::drag(tab, start, end)
+        .await
+        .map_err(|e| format!("Drag image failed: {}", e))?;
+    // 等拖动的图片出现在目标位置，没有移动时由 ensure_order 报告最终顺序
+    let moved = wait::until(Step::Element, "图片移动到新位置", || {
+        let current = read_thumbnails(tab).get(to).and_then(|t| t.index);
+        (current == dragged).then_some(())
+    })
+    .await;
+    if let Err(e) = moved {
+        log::warn!("{}", e);
+    }
+    Ok(())
+}
@@ -1,360 +1,741 @@
-use crate::storage::get_browser_data_dir;
-use headless_chrome::browser::default_executable;
-use headless_chrome::{Browser, LaunchOptions, Tab};
-use sqlx::Row;
+pub mod driver;
+pub mod editor;
+pub mod human;
+pub mod image_source;
+pub mod images;
+pub mod multi_account;
+pub mod options;
+pub mod outcome;
+pub mod proxy;
+pub mod selectors;
+pub mod session;
+pub mod site;
+pub mod socks;
+pub mod trace;
+pub mod validate;
+pub mod video;
+pub mod wait;
+
+use crate::model::{MediaType, Post, PostStatus, PostStatusChange};
+use crate::storage::repository::{PostEdit, PostRepo, UserRepo};
+use driver::Driver;
+use outcome::{
+    is_publish_api, parse_publish_response, toast_failure, PublishApiResponse, PublishErrorKind,
+};
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-
-pub fn take_screenshot(tab: &Tab, name: &str) {
-    let mut dir = dirs::home_dir().unwrap_or_else(|| Path::new(".").to_path_buf());
-    dir.push("xiaohongshu-helper-debug");
-
-    if !dir.exists() {
-        let _ = fs::create_dir_all(&dir);
-    }
-    let filepath = dir.join(format!("{}.png", name));
-    match tab.capture_screenshot(
-        headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png,
-        None,
-        None,
-        true,
-    ) {
+use trace::RunKind;
+use wait::Step;
+
+pub use options::{PublishMode, PublishOptions};
+pub use outcome::{PublishFailure, PublishedNote};
+
+/// 保存调试截图，成功时返回截图路径
+///
+/// 在运行记录中时保存到该次运行的目录，否则保存到调试目录。
+pub fn take_screenshot(tab: &dyn Driver, name: &str) -> Option<PathBuf> {
+    let filename = format!("{}.png", name);
+    let filepath = trace::file_path(&filename)
+        .unwrap_or_else(|| crate::storage::get_debug_dir().join(&filename));
+    match tab.screenshot() {
         Ok(data) => {
             if let Err(e) = fs::write(&filepath, data) {
                 println!("Failed to write screenshot {}: {}", name, e);
+                None
             } else {
                 println!("Screenshot saved: {:?}", filepath);
+                trace::add_file(&filepath);
+                Some(filepath)
             }
         }
-        Err(e) => println!("Failed to take screenshot {}: {}", name, e),
+        Err(e) => {
+            println!("Failed to take screenshot {}: {}", name, e);
+            None
+        }
     }
 }
 
+/// 发布笔记，返回平台生成的笔记 id 和链接
+///
+/// `images` 和 `cover_image` 可以是本机路径、http(s) 链接、`asset:素材文件名` 或 base64 数据，
+/// 非本机文件会先校验并保存到素材库。
+/// 传入 `video_path` 时发布视频笔记，封面使用 `cover_image` 或视频 `cover_time` 秒处的画面。
+/// `topics` 和 `mentions` 通过编辑器的候选列表插入，未能匹配的在返回值中列出。
+/// `options` 中的可见范围、定时发布等设置会在点击发布前逐项应用并确认；
+/// `options.mode` 可以改为保存到平台草稿箱，或只试运行并返回各步骤截图。
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn publish_post(
     phone: String,
     title: String,
     content: String,
     images: Vec<String>,
     cover_image: Option<String>,
+    video_path: Option<String>,
+    cover_time: Option<f64>,
+    topics: Option<Vec<String>>,
+    mentions: Option<Vec<String>>,
+    options: Option<PublishOptions>,
+) -> Result<PublishedNote, PublishFailure> {
+    let edit = PostEdit {
+        title,
+        content,
+        images,
+        cover_image,
+        media_type: MediaType::from_video_path(video_path.as_deref()),
+        video_path,
+        cover_time,
+        topics: topics.unwrap_or_default(),
+        mentions: mentions.unwrap_or_default(),
+    };
+    publish_note(&phone, &edit, &options.unwrap_or_default()).await
+}
+
+/// 发布已保存的笔记，并按状态机记录 queued → publishing → published | failed
+///
+/// 传入 `options` 时先保存到笔记上，否则使用笔记已保存的发布设置。试运行和保存到平台草稿箱
+/// 不改变笔记状态，需要通过 `publish_post` 进行。
+#[tauri::command]
+pub async fn publish_post_by_id(
+    post_id: i64,
+    options: Option<PublishOptions>,
+) -> Result<Post, PublishFailure> {
+    let pool = crate::storage::pool().await?;
+    let posts = PostRepo::new(pool);
+    if let Some(options) = &options {
+        options.validate_saved()?;
+        posts.set_publish_options(post_id, Some(options)).await?;
+    }
+    posts
+        .transition(post_id, PostStatus::Queued, None, None)
+        .await?;
+    run_queued_post(post_id).await
+}
+
+/// 保存笔记的发布设置，定时任务到点后按它发布；None 表示使用平台默认
+#[tauri::command]
+pub async fn set_post_publish_options(
+    post_id: i64,
+    options: Option<PublishOptions>,
 ) -> Result<(), String> {
-    println!("Starting publish_post task for phone: {}", phone);
+    if let Some(options) = &options {
+        options.validate_saved()?;
+    }
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool)
+        .set_publish_options(post_id, options.as_ref())
+        .await
+}
 
-    // 优先尝试获取活跃会话
-    let active_session = crate::auth::get_active_session(&phone);
-    let browser = if let Some(session) = active_session {
-        println!("Reusing active browser session for {}", phone);
-        session.browser
-    } else {
-        println!("No active session, starting new browser for {}", phone);
-
-        // 启动前清理可能的 SingletonLock 锁文件
-        crate::storage::clear_browser_lock(&phone);
-
-        // 获取无头模式设置
-        let headless = crate::ai::get_headless_mode().await;
-
-        let data_dir = get_browser_data_dir(&phone);
-        Browser::new(
-            LaunchOptions::default_builder()
-                .headless(headless)
-                .user_data_dir(Some(data_dir))
-                .path(Some(default_executable().unwrap()))
-                .window_size(Some((1920, 1080)))
-                .enable_gpu(false)
-                .args(vec![
-                    std::ffi::OsStr::new("--disable-extensions"),
-                    std::ffi::OsStr::new("--disable-blink-features=AutomationControlled"),
-                    std::ffi::OsStr::new("--no-first-run"),
-                    std::ffi::OsStr::new("--no-default-browser-check"),
-                ])
-                .build()
-                .map_err(|e| format!("Browser build failed: {}", e))?,
-        )
-        .map_err(|e| format!("Browser init failed: {}", e))?
+#[tauri::command]
+pub async fn get_post_publish_options(post_id: i64) -> Result<PublishOptions, String> {
+    let pool = crate::storage::pool().await?;
+    Ok(PostRepo::new(pool)
+        .get_publish_options(post_id)
+        .await?
+        .unwrap_or_default())
+}
+
+/// 发布一篇已排队的笔记，立即发布和定时任务共用
+///
+/// 使用笔记保存的发布设置，只会真正发布。成功时在笔记上记录平台返回的笔记 id 和链接，
+/// 失败时记录失败类型、原因和截图。
+pub async fn run_queued_post(post_id: i64) -> Result<Post, PublishFailure> {
+    let pool = crate::storage::pool().await?;
+    let posts = PostRepo::new(pool);
+    let post = posts
+        .get(post_id)
+        .await?
+        .ok_or_else(|| "笔记不存在".to_string())?;
+    let user = UserRepo::new(pool)
+        .get(post.user_id)
+        .await?
+        .ok_or_else(|| "笔记所属账号不存在".to_string())?;
+
+    let options: PublishOptions = posts
+        .get_publish_options(post_id)
+        .await?
+        .unwrap_or_default();
+    // 旧版本可能把试运行或平台草稿保存到了笔记上，按它执行不会真正发出，退回草稿由用户确认
+    if options.mode != PublishMode::Publish {
+        posts
+            .transition(post_id, PostStatus::Draft, None, None)
+            .await?;
+        return Err(PublishFailure::new(
+            PublishErrorKind::InvalidContent,
+            "笔记保存的发布方式不是发布，已退回草稿，请重新保存发布设置后再排队",
+        ));
+    }
+
+    posts
+        .transition(post_id, PostStatus::Publishing, None, None)
+        .await?;
+
+    let result = publish_note(&user.phone, &PostEdit::from(post), &options).await;
+
+    match result {
+        Ok(note) => {
+            // 平台上已经发出，记录失败时不能返回错误，否则重试会重复发布
+            let recorded = async {
+                posts
+                    .transition(post_id, PostStatus::Published, None, None)
+                    .await?;
+                posts
+                    .set_publish_details(
+                        post_id,
+                        note.note_id.as_deref(),
+                        note.note_url.as_deref(),
+                        None,
+                    )
+                    .await
+            }
+            .await;
+            match recorded {
+                Ok(post) => Ok(post),
+                Err(e) => {
+                    eprintln!("笔记 {} 已发布，但保存发布状态失败: {}", post_id, e);
+                    let mut post = posts
+                        .get(post_id)
+                        .await?
+                        .ok_or_else(|| "笔记不存在".to_string())?;
+                    post.note_id = note.note_id.or(post.note_id);
+                    post.note_url = note.note_url.or(post.note_url);
+                    post.warning = Some(format!(
+                        "笔记已发布，但保存发布状态失败: {}，请勿重复发布",
+                        e
+                    ));
+                    Ok(post)
+                }
+            }
+        }
+        Err(failure) => {
+            posts
+                .transition(
+                    post_id,
+                    PostStatus::Failed,
+                    Some(&failure.message),
+                    failure.screenshot.as_deref(),
+                )
+                .await?;
+            posts
+                .set_publish_details(post_id, None, None, Some(failure.kind.as_str()))
+                .await?;
+            Err(failure)
+        }
+    }
+}
+
+/// 把笔记流转到指定状态，例如撤回排队或把失败的笔记重新排队
+#[tauri::command]
+pub async fn set_post_status(post_id: i64, status: PostStatus) -> Result<Post, String> {
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool)
+        .transition(post_id, status, None, None)
+        .await
+}
+
+/// 按平台规则检查已保存的笔记，返回错误和警告
+#[tauri::command]
+pub async fn validate_saved_post(post_id: i64) -> Result<validate::ValidationReport, String> {
+    let pool = crate::storage::pool().await?;
+    let post = PostRepo::new(pool)
+        .get(post_id)
+        .await?
+        .ok_or_else(|| "笔记不存在".to_string())?;
+    let mut report = validate::validate_post(&post.clone().into());
+    report.add_word_hits(&crate::sensitive::check_with_dictionary(&post.title, &post.content).await?);
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_post_status_history(post_id: i64) -> Result<Vec<PostStatusChange>, String> {
+    let pool = crate::storage::pool().await?;
+    PostRepo::new(pool).list_status_history(post_id).await
+}
+
+/// 发布笔记，每次发布的步骤、截图和失败时的页面保存为一条运行记录
+/// 转换图片参数后发布，直接发布和发布已保存的笔记共用
+async fn publish_note(
+    phone: &str,
+    post: &PostEdit,
+    options: &PublishOptions,
+) -> Result<PublishedNote, PublishFailure> {
+    let post = resolve_media(post).await?;
+    trace::scope(RunKind::Publish, phone, run_publish(phone, &post, options)).await
+}
+
+/// 把图片和封面参数转换为本机文件，见 [`image_source::resolve_image`]
+async fn resolve_media(post: &PostEdit) -> Result<PostEdit, PublishFailure> {
+    let invalid = |e: String| PublishFailure::new(PublishErrorKind::InvalidContent, e);
+    let images = image_source::resolve_images(&post.images)
+        .await
+        .map_err(invalid)?;
+    let cover_image = match &post.cover_image {
+        Some(cover) => Some(
+            image_source::resolve_image(cover)
+                .await
+                .map_err(|e| invalid(format!("cover_image: {}", e)))?,
+        ),
+        None => None,
     };
+    Ok(PostEdit {
+        images,
+        cover_image,
+        ..post.clone()
+    })
+}
 
-    let tab = browser
-        .new_tab()
-        .map_err(|e| format!("New tab failed: {}", e))?;
+async fn run_publish(
+    phone: &str,
+    post: &PostEdit,
+    options: &PublishOptions,
+) -> Result<PublishedNote, PublishFailure> {
+    println!("Starting publish_post task for phone: {}", phone);
+    trace::step("validate");
+    let mut report = validate::validate_post(post);
+    if report.has_errors() {
+        return Err(PublishFailure::new(
+            PublishErrorKind::InvalidContent,
+            report.error_summary(),
+        ));
+    }
+    match crate::sensitive::check_with_dictionary(&post.title, &post.content).await {
+        Ok(hits) => report.add_word_hits(&hits),
+        Err(e) => println!("Sensitive word check failed: {}", e),
+    }
+    for issue in &report.issues {
+        println!("Pre-publish warning [{}]: {}", issue.field, issue.message);
+    }
+    options.validate()?;
+
+    trace::step("open_browser");
+    let session = session::acquire(phone).await?;
+    let tab = session.new_tab().await?;
+    trace::attach(&tab);
+
+    // 监听发布接口的响应，从中读取新笔记的 id 和分享链接
+    let captured: Arc<Mutex<Option<PublishApiResponse>>> = Arc::new(Mutex::new(None));
+    let sink = captured.clone();
+    tab.on_response(
+        PUBLISH_RESPONSE_HANDLER,
+        is_publish_api,
+        Box::new(move |_url: &str, body: &str| {
+            if let Some(response) = parse_publish_response(body) {
+                *sink.lock().unwrap() = Some(response);
+            }
+        }),
+    )?;
+
+    let mut shots = StepShots::default();
+    let result = fill_and_submit(tab.as_ref(), post, options, &captured, &mut shots).await;
+    tab.remove_response_handler(PUBLISH_RESPONSE_HANDLER);
+
+    result
+        .map(|note| PublishedNote {
+            run_id: trace::current_id(),
+            ..note
+        })
+        .map_err(|mut failure| {
+            failure.screenshot =
+                take_screenshot(tab.as_ref(), "failed").map(|p| p.to_string_lossy().to_string());
+            failure.run_id = trace::current_id();
+            failure
+        })
+}
 
+/// 在发布页填写内容、点击发布并等待发布结果
+async fn fill_and_submit(
+    tab: &dyn Driver,
+    post: &PostEdit,
+    options: &PublishOptions,
+    captured: &Mutex<Option<PublishApiResponse>>,
+    shots: &mut StepShots,
+) -> Result<PublishedNote, PublishFailure> {
     // 1. 跳转到发布页面
     println!("Navigating to publish page...");
-    tab.navigate_to("https://creator.xiaohongshu.com/publish/publish?from=homepage&target=image")
-        .map_err(|e| format!("Navigation failed: {}", e))?;
-
-    take_screenshot(&tab, "1_navigated");
-
-    // 2. 等待封面上传 Input
-    println!("Waiting for upload input selector: .upload-input");
-    let upload_input = tab.wait_for_element(".upload-input").map_err(|e| {
-        take_screenshot(&tab, "error_wait_upload_input");
-        format!(
-            "Error waiting for .upload-input: {}. See error_wait_upload_input.png",
-            e
-        )
-    })?;
+    trace::step("navigate");
+    tab.navigate(&site::creator_url(&format!(
+        "/publish/publish?from=homepage&target={}",
+        post.media_type.as_str()
+    )))?;
+
+    shots.take(tab, "1_navigated");
+    if is_login_page(&tab.current_url()) {
+        return Err(PublishFailure::new(
+            PublishErrorKind::SessionExpired,
+            "登录已失效，请重新登录该账号",
+        ));
+    }
 
-    // 确定封面图
-    let cover = if let Some(c) = &cover_image {
-        c.clone()
-    } else if let Some(first) = images.first() {
-        first.clone()
-    } else {
-        return Err("必须至少包含一张图片作为封面".to_string());
+    // 2. 上传封面图或视频
+    let cover = match post.media_type {
+        MediaType::Image => {
+            trace::step("upload_cover");
+            let cover = upload_cover_image(tab, post).await?;
+            shots.take(tab, "2_cover_uploaded");
+            Some(cover)
+        }
+        MediaType::Video => {
+            let video_path = post
+                .video_path
+                .as_deref()
+                .ok_or_else(|| "视频笔记缺少视频文件".to_string())?;
+            trace::step("upload_video");
+            video::upload_video(tab, video_path).await?;
+            None
+        }
     };
 
-    println!("Uploading cover image: {}", cover);
-    upload_input
-        .set_input_files(&[&cover])
-        .map_err(|e| format!("Failed to set cover image: {}", e))?;
-
-    // 给一点时间让上传触发
-    tokio::time::sleep(Duration::from_secs(2)).await;
-    take_screenshot(&tab, "2_cover_uploaded");
-
-    // 3. 等待编辑页面加载
-    // 这里容易超时，如果上传慢的话
-    println!("Waiting for editor container: .edit-container");
-    tab.wait_for_element(".edit-container")
+    // 3. 等待编辑页面加载，上传慢时可以调大 cover_upload 的超时
+    println!("Waiting for editor container...");
+    trace::step("wait_editor");
+    wait::for_selector(tab, "publish.editor", Step::CoverUpload)
+        .await
         .map_err(|e| {
-             take_screenshot(&tab, "error_wait_edit_container");
-             format!("Error waiting for .edit-container: {}. Check if cover upload worked. See error_wait_edit_container.png", e)
+            take_screenshot(tab, "error_wait_edit_container");
+            PublishFailure::new(
+                PublishErrorKind::Timeout,
+                format!(
+                    "{}. Check if cover upload worked. See error_wait_edit_container.png",
+                    e
+                ),
+            )
         })?;
 
-    take_screenshot(&tab, "3_editor_loaded");
+    if let Some(cover) = &cover {
+        images::confirm_cover(tab, cover).await?;
+    }
+    shots.take(tab, "3_editor_loaded");
 
     // 4. 填写标题
     println!("Filling title...");
-    let title_input = tab
-        .wait_for_element(".d-input-wrapper .d-text")
+    trace::step("fill_title");
+    selectors::type_into(tab, "publish.title", &post.title)
+        .await
         .map_err(|e| {
-            take_screenshot(&tab, "error_wait_title");
-            format!("Error waiting for title input: {}", e)
+            take_screenshot(tab, "error_wait_title");
+            format!("Fill title failed: {}", e)
         })?;
 
-    title_input
-        .click()
-        .map_err(|e| format!("Click title failed: {}", e))?;
-    title_input
-        .type_into(&title)
-        .map_err(|e| format!("Type title failed: {}", e))?;
-
     // 5. 填写正文
     println!("Filling content...");
-    let content_editor = tab.wait_for_element(".tiptap.ProseMirror").map_err(|e| {
-        take_screenshot(&tab, "error_wait_content");
-        format!("Error waiting for content editor: {}", e)
-    })?;
-
-    content_editor
-        .click()
-        .map_err(|e| format!("Click content failed: {}", e))?;
-    content_editor
-        .type_into(&content)
-        .map_err(|e| format!("Type content failed: {}", e))?;
-
-    take_screenshot(&tab, "4_content_filled");
-
-    // 6. 上传剩余图片
-    let remaining_images: Vec<String> = images
-        .iter()
-        .filter(|&img| img != &cover)
-        .cloned()
-        .collect();
-
-    if !remaining_images.is_empty() {
-        println!("Uploading remaining {} images...", remaining_images.len());
-        // 尝试寻找编辑页内的添加图片按钮
-        if let Ok(file_input) = tab.wait_for_element("input[type='file']") {
-            let image_refs: Vec<&str> = remaining_images.iter().map(|s| s.as_str()).collect();
-            if let Err(e) = file_input.set_input_files(&image_refs) {
-                println!("Warning: Failed to upload remaining images: {}", e);
-            } else {
-                tokio::time::sleep(Duration::from_secs(2)).await;
+    trace::step("fill_content");
+    selectors::type_into(tab, "publish.content", &post.content)
+        .await
+        .map_err(|e| {
+            take_screenshot(tab, "error_wait_content");
+            format!("Fill content failed: {}", e)
+        })?;
+    let unresolved = editor::insert_tags(tab, &post.topics, &post.mentions).await;
+
+    shots.take(tab, "4_content_filled");
+
+    // 6. 图文笔记上传剩余图片，视频笔记等待转码并设置封面
+    match cover {
+        Some(cover) => {
+            trace::step("upload_images");
+            images::upload_remaining(tab, &images::upload_order(&post.images, &cover)).await?
+        }
+        None => {
+            trace::step("wait_video");
+            video::wait_for_video_ready(tab).await?;
+            let frame;
+            let cover_image = match (post.cover_image.as_deref(), post.cover_time) {
+                (Some(image), _) => Some(image),
+                (None, Some(seconds)) => {
+                    frame = video::capture_frame(tab, seconds)?;
+                    frame.path.to_str()
+                }
+                (None, None) => None,
+            };
+            if let Some(image) = cover_image {
+                video::set_video_cover(tab, image).await?;
             }
-        } else {
-            println!("Warning: Could not find input[type='file'] for remaining images");
         }
     }
 
-    take_screenshot(&tab, "5_ready_to_publish");
-    tokio::time::sleep(Duration::from_secs(2)).await;
+    // 7. 可见范围、定时发布等附加设置
+    trace::step("apply_options");
+    options::apply(tab, options).await?;
+
+    shots.take(tab, "5_ready_to_publish");
+    wait::settle(tab).await;
 
     // 尝试滚动到底部
     println!("Scrolling to bottom...");
-    let _ = tab.evaluate("window.scrollTo(0, document.body.scrollHeight)", true);
-    tokio::time::sleep(Duration::from_millis(500)).await;
+    let _ = human::scroll_to_bottom(tab).await;
+    wait::settle(tab).await;
+
+    let filled = PublishedNote {
+        mode: options.mode,
+        unresolved_topics: unresolved.topics,
+        unresolved_mentions: unresolved.mentions,
+        ..Default::default()
+    };
+    match options.mode {
+        PublishMode::DryRun => {
+            shots.take(tab, "6_page_bottom");
+            println!("Dry run finished, publish button not clicked");
+            return Ok(PublishedNote {
+                screenshots: shots.paths.clone(),
+                ..filled
+            });
+        }
+        PublishMode::PlatformDraft => {
+            trace::step("save_draft");
+            save_platform_draft(tab).await?;
+            shots.take(tab, "6_draft_saved");
+            return Ok(PublishedNote {
+                screenshots: shots.paths.clone(),
+                ..filled
+            });
+        }
+        PublishMode::Publish => {}
+    }
 
-    // 7. 点击发布
+    // 8. 点击发布
     println!("Finding publish button...");
+    trace::step("submit");
 
-    // 灵活的 XPath：支持 button 或 role="button" 的 div，排除侧边栏的“发布笔记”，通过包含“发布”二字定位
-    let xpath = "//*[(name()='button' or @role='button') and contains(., '发布') and not(contains(., '笔记'))]";
-
-    let publish_btn = tab
-        .wait_for_elements_by_xpath(xpath)
+    selectors::click(tab, "publish.publish_button")
+        .await
         .map_err(|e| {
-            take_screenshot(&tab, "error_find_publish_btn");
+            take_screenshot(tab, "error_find_publish_btn");
             format!(
-                "Wait for publish btn failed: {}. See error_find_publish_btn.png",
+                "Click publish btn failed: {}. See error_find_publish_btn.png",
                 e
             )
-        })?
-        .into_iter()
-        .next()
-        .ok_or_else(|| {
-            take_screenshot(&tab, "error_no_publish_btn");
-            "Cannot find publish element (No match found). See error_no_publish_btn.png".to_string()
         })?;
 
-    println!("Publish button found. Clicking...");
-    publish_btn
-        .click()
-        .map_err(|e| format!("Click publish failed: {}", e))?;
+    println!("Publish command sent. Waiting for result...");
+    trace::step("wait_result");
+    let note = wait_for_publish_result(tab, captured).await?;
+    shots.take(tab, "6_published");
 
-    println!("Publish command sent. Waiting 3s...");
-    tokio::time::sleep(Duration::from_secs(3)).await;
-    take_screenshot(&tab, "6_publish_clicked");
+    Ok(PublishedNote {
+        note_id: note.note_id,
+        note_url: note.note_url,
+        ..filled
+    })
+}
 
-    Ok(())
+/// 记录各步骤的截图
+///
+/// 截图保存在本次运行的目录中，试运行和保存草稿时路径会返回给调用方。
+#[derive(Default)]
+struct StepShots {
+    paths: Vec<String>,
 }
 
-#[tauri::command]
-pub async fn validate_login_status(phone: String) -> Result<crate::model::User, String> {
-    println!("Validating login status for phone: {}", phone);
+impl StepShots {
+    fn take(&mut self, tab: &dyn Driver, name: &str) {
+        if let Some(path) = take_screenshot(tab, name) {
+            self.paths.push(path.to_string_lossy().to_string());
+        }
+    }
+}
 
-    // 清理可能的 SingletonLock 锁文件
-    crate::storage::clear_browser_lock(&phone);
-
-    // 获取无头模式设置
-    let headless = crate::ai::get_headless_mode().await;
-
-    let data_dir = get_browser_data_dir(&phone);
-    let browser = Browser::new(
-        LaunchOptions::default_builder()
-            .headless(headless)
-            .user_data_dir(Some(data_dir.clone()))
-            .path(Some(default_executable().unwrap()))
-            .window_size(Some((1920, 1080)))
-            .enable_gpu(false)
-            .args(vec![
-                std::ffi::OsStr::new("--disable-extensions"),
-                std::ffi::OsStr::new("--disable-blink-features=AutomationControlled"),
-                std::ffi::OsStr::new("--no-first-run"),
-                std::ffi::OsStr::new("--no-default-browser-check"),
-            ])
-            .build()
-            .map_err(|e| format!("Browser build failed: {}", e))?,
-    )
-    .map_err(|e| format!("Browser init failed: {}", e))?;
-
-    let tab = browser
-        .new_tab()
-        .map_err(|e| format!("New tab failed: {}", e))?;
+/// 点击「暂存离开」，等待平台提示保存成功
+async fn save_platform_draft(tab: &dyn Driver) -> Result<(), PublishFailure> {
+    println!("Saving to platform drafts...");
+    let publish_url = tab.current_url();
+    selectors::click(tab, "publish.draft_button").await?;
 
-    // 跳转到发布页以检查登录状态
-    println!("Navigating to publish page to check status...");
-    tab.navigate_to("https://creator.xiaohongshu.com/publish/publish")
-        .map_err(|e| format!("Navigation failed: {}", e))?;
-
-    // 等待用户信息元素或登录重定向
-    // 这里的选择器是用户提供的 .user-info 或里面的 .user_avatar / .name-box
-    let user_info_selector = ".user-info";
+    wait::until(Step::DraftSave, "暂存结果", || {
+        if let Some(toast) = read_toast(tab) {
+            if let Some(kind) = toast_failure(&toast) {
+                return Some(Err(PublishFailure::new(kind, toast)));
+            }
+            if toast.contains("暂存") || toast.contains("保存成功") || toast.contains("草稿")
+            {
+                return Some(Ok(()));
+            }
+        }
+        // 暂存后页面会离开编辑页
+        (tab.current_url() != publish_url).then_some(Ok(()))
+    })
+    .await
+    .map_err(|e| {
+        PublishFailure::new(
+            PublishErrorKind::Timeout,
+            format!("{}，请到创作者中心草稿箱确认", e),
+        )
+    })?
+}
 
-    // 给一点时间加载
-    tokio::time::sleep(Duration::from_secs(2)).await;
+/// 图文笔记先上传封面图进入编辑页，返回使用的封面
+async fn upload_cover_image(tab: &dyn Driver, post: &PostEdit) -> Result<String, PublishFailure> {
+    println!("Waiting for upload input...");
+    selectors::wait_for(tab, "publish.upload_input")
+        .await
+        .map_err(|e| {
+            take_screenshot(tab, "error_wait_upload_input");
+            format!("{}. See error_wait_upload_input.png", e)
+        })?;
 
-    let _user_info_el = match tab.wait_for_element(user_info_selector) {
-        Ok(el) => el,
-        Err(_) => {
-            take_screenshot(&tab, "validate_login_failed");
-            return Err("未检测到登录状态或已过期".to_string());
-        }
+    // 确定封面图
+    let cover = if let Some(c) = &post.cover_image {
+        c.clone()
+    } else if let Some(first) = post.images.first() {
+        first.clone()
+    } else {
+        return Err("必须至少包含一张图片作为封面".to_string().into());
     };
 
-    // 提取昵称和头像
-    let nickname = tab
-        .wait_for_element(".name-box")
-        .and_then(|el| el.get_inner_text())
-        .unwrap_or_else(|_| "未知用户".to_string());
+    println!("Uploading cover image: {}", cover);
+    selectors::set_files(tab, "publish.upload_input", &[&cover])
+        .await
+        .map_err(|e| {
+            PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                format!("Failed to set cover image: {}", e),
+            )
+        })?;
+    Ok(cover)
+}
+
+const PUBLISH_RESPONSE_HANDLER: &str = "publish_result";
+
+pub(crate) fn is_login_page(url: &str) -> bool {
+    url.contains("/login") || url.contains("passport")
+}
 
-    let avatar = tab
-        .wait_for_element(".user_avatar")
-        .and_then(|el| el.get_attributes())
+/// 读取页面上的提示文字（toast / message）
+fn read_toast(tab: &dyn Driver) -> Option<String> {
+    let script = format!(
+        r#"Array.from(document.querySelectorAll({})).map(e => e.innerText.trim()).filter(Boolean).join('\n')"#,
+        serde_json::json!(selectors::css("publish.toast"))
+    );
+    tab.evaluate(&script, false)
         .ok()
         .flatten()
-        .and_then(|attrs| {
-            let mut iter = attrs.iter();
-            while let Some(name) = iter.next() {
-                if name == "src" {
-                    return iter.next().cloned();
-                }
-                iter.next();
+        .and_then(|v| v.as_str().map(str::to_string))
+        .filter(|s| !s.is_empty())
+}
+
+/// 点击发布后，依次根据接口响应、页面跳转和提示文字判断发布结果
+async fn wait_for_publish_result(
+    tab: &dyn Driver,
+    captured: &Mutex<Option<PublishApiResponse>>,
+) -> Result<PublishedNote, PublishFailure> {
+    let started = std::time::Instant::now();
+    let mut page_success = false;
+
+    let outcome = wait::until(Step::PublishResult, "发布结果", || {
+        let response = captured.lock().unwrap().take();
+        match response {
+            Some(PublishApiResponse::Success(note)) => {
+                println!("Publish succeeded: {:?}", note);
+                return Some(Ok(note));
             }
-            None
-        });
+            Some(PublishApiResponse::Rejected(message)) => {
+                let kind = match PublishErrorKind::classify(&message) {
+                    PublishErrorKind::Unknown => PublishErrorKind::ContentRejected,
+                    kind => kind,
+                };
+                return Some(Err(PublishFailure::new(kind, message)));
+            }
+            None => {}
+        }
 
-    println!("Detected user: {} (Avatar: {:?})", nickname, avatar);
+        let url = tab.current_url();
+        if is_login_page(&url) {
+            return Some(Err(PublishFailure::new(
+                PublishErrorKind::SessionExpired,
+                "发布过程中登录失效，请重新登录该账号",
+            )));
+        }
 
-    // 更新或插入数据库中的用户信息
-    let db_url = crate::storage::get_db_path();
-    let pool = sqlx::sqlite::SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
+        if let Some(toast) = read_toast(tab) {
+            if let Some(kind) = toast_failure(&toast) {
+                return Some(Err(PublishFailure::new(kind, toast)));
+            }
+            if toast.contains("发布成功") {
+                page_success = true;
+            }
+        }
 
-    // 检查用户是否已存在
-    let existing_user = sqlx::query("SELECT id, created_at FROM users WHERE phone = ?")
-        .bind(&phone)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let (user_id, created_at) = if let Some(row) = existing_user {
-        // 用户已存在，更新信息
-        let id: i64 = row.get(0);
-        let created: String = row.get(1);
-
-        println!("用户已存在，更新昵称和头像");
-        sqlx::query("UPDATE users SET nickname = ?, avatar = ? WHERE phone = ?")
-            .bind(&nickname)
-            .bind(&avatar)
-            .bind(&phone)
-            .execute(&pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        (id, created)
-    } else {
-        // 用户不存在，插入新记录
-        println!("用户不存在，插入新记录");
-        let now = chrono::Local::now().to_rfc3339();
+        // 页面显示成功后再等一会儿接口响应，拿不到也算成功
+        if url.contains("/publish/success") {
+            page_success = true;
+        }
+        if page_success && started.elapsed() > Duration::from_secs(5) {
+            println!("Publish succeeded, but note id was not captured");
+            return Some(Ok(PublishedNote::default()));
+        }
+        None
+    })
+    .await;
+
+    match outcome {
+        Ok(result) => result,
+        Err(_) if page_success => Ok(PublishedNote::default()),
+        Err(timeout) => Err(PublishFailure::new(
+            PublishErrorKind::Unconfirmed,
+            format!(
+                "点击发布后 {} 秒内未检测到发布结果，请到创作者中心确认",
+                timeout.after.as_secs()
+            ),
+        )),
+    }
+}
 
-        let result = sqlx::query(
-            "INSERT INTO users (nickname, phone, avatar, created_at) VALUES (?, ?, ?, ?)",
-        )
-        .bind(&nickname)
-        .bind(&phone)
-        .bind(&avatar)
-        .bind(&now)
-        .execute(&pool)
+#[tauri::command]
+pub async fn validate_login_status(phone: String) -> Result<crate::model::User, String> {
+    trace::scope(RunKind::Validate, &phone, check_login_status(&phone)).await
+}
+
+async fn check_login_status(phone: &str) -> Result<crate::model::User, String> {
+    println!("Validating login status for phone: {}", phone);
+
+    trace::step("open_browser");
+    let session = session::acquire(phone).await?;
+    let tab = session.new_tab().await?;
+    trace::attach(&tab);
+
+    // 跳转到发布页以检查登录状态
+    println!("Navigating to publish page to check status...");
+    trace::step("navigate");
+    tab.navigate(&site::creator_url("/publish/publish"))?;
+
+    // 未登录时会跳转到登录页，已登录时会出现用户信息，哪个先出现就以哪个为准
+    let logged_in = wait::until(Step::PageLoad, "登录状态", || {
+        if is_login_page(&tab.current_url()) {
+            Some(false)
+        } else {
+            selectors::find(tab.as_ref(), "account.user_info").map(|_| true)
+        }
+    })
+    .await;
+    if !matches!(logged_in, Ok(true)) {
+        take_screenshot(tab.as_ref(), "validate_login_failed");
+        return Err(match logged_in {
+            Err(e) => format!("未检测到登录状态或已过期：{}", e),
+            _ => "未检测到登录状态或已过期".to_string(),
+        });
+    }
+
+    // 提取昵称和头像
+    trace::step("read_user");
+    let nickname = selectors::inner_text(tab.as_ref(), "account.nickname")
+        .await
+        .unwrap_or_else(|_| "未知用户".to_string());
+    let avatar = selectors::attribute(tab.as_ref(), "account.avatar", "src")
         .await
-        .map_err(|e| e.to_string())?;
+        .ok()
+        .flatten();
 
-        (result.last_insert_rowid(), now)
-    };
+    println!("Detected user: {} (Avatar: {:?})", nickname, avatar);
 
-    let user = crate::model::User {
-        id: user_id,
-        nickname,
-        phone,
-        avatar,
-        created_at,
-    };
+    // 更新或插入数据库中的用户信息
+    let pool = crate::storage::pool().await?;
+    let user = UserRepo::new(pool)
+        .upsert(&nickname, phone, avatar.as_deref())
+        .await?;
 
-    take_screenshot(&tab, "validate_login_success");
+    take_screenshot(tab.as_ref(), "validate_login_success");
 
     Ok(user)
 }
@@ -0,0 +1,343 @@
+use super::outcome::{PublishErrorKind, PublishFailure};
+use crate::model::{MediaType, Post, PostStatus};
+use crate::storage::repository::{PostEdit, PostRepo, UserRepo};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// 同时发布的账号数上限，每个账号会占用一个浏览器
+const MAX_CONCURRENCY: usize = 3;
+
+/// 单个账号的发布设置，未填写的字段沿用原笔记
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AccountOverride {
+    /// 账号手机号，对应 `users.phone`
+    pub phone: String,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    /// 图片顺序，只能使用原笔记中的图片
+    pub images: Option<Vec<String>>,
+    pub cover_image: Option<String>,
+}
+
+impl AccountOverride {
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.content.is_none()
+            && self.images.is_none()
+            && self.cover_image.is_none()
+    }
+}
+
+/// 让 AI 为没有手动填写标题和正文的账号生成改写版本
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AiVariationOptions {
+    pub provider_id: i64,
+    pub model_name: String,
+    pub instruction: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AccountPublishResult {
+    pub phone: String,
+    /// 该账号实际发布的笔记，原笔记或为该账号生成的副本
+    pub post_id: Option<i64>,
+    pub title: Option<String>,
+    pub success: bool,
+    pub note_url: Option<String>,
+    pub error: Option<String>,
+    pub failure_kind: Option<PublishErrorKind>,
+    pub screenshot: Option<String>,
+    /// 发布失败时的运行记录 id
+    pub run_id: Option<String>,
+    /// AI 改写失败时沿用原文，这里记录失败原因
+    pub variation_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MultiPublishReport {
+    pub post_id: i64,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<AccountPublishResult>,
+}
+
+impl AccountPublishResult {
+    fn failed(phone: &str, error: String) -> Self {
+        Self {
+            phone: phone.to_string(),
+            post_id: None,
+            title: None,
+            success: false,
+            note_url: None,
+            error: Some(error),
+            failure_kind: None,
+            screenshot: None,
+            run_id: None,
+            variation_error: None,
+        }
+    }
+}
+
+/// 校验并合并单个账号的图片顺序和封面
+///
+/// 视频笔记没有图片列表，封面可以换成任意图片。
+pub fn apply_image_override(
+    post: &Post,
+    images: Option<&[String]>,
+    cover_image: Option<&str>,
+) -> Result<(Vec<String>, Option<String>), String> {
+    if post.media_type == MediaType::Video {
+        if images.is_some() {
+            return Err("视频笔记不能调整图片顺序".to_string());
+        }
+        let cover = cover_image
+            .map(str::to_string)
+            .or_else(|| post.cover_image.clone());
+        return Ok((Vec::new(), cover));
+    }
+
+    let images = match images {
+        Some(list) => {
+            if list.is_empty() {
+                return Err("图片列表不能为空".to_string());
+            }
+            if let Some(unknown) = list.iter().find(|img| !post.images.contains(img)) {
+                return Err(format!("图片 {} 不在原笔记中", unknown));
+            }
+            list.to_vec()
+        }
+        None => post.images.clone(),
+    };
+
+    let cover = match cover_image {
+        Some(cover) if !images.iter().any(|img| img == cover) => {
+            return Err(format!("封面 {} 不在图片列表中", cover));
+        }
+        Some(cover) => Some(cover.to_string()),
+        // 原封面被移出图片列表时改用第一张图
+        None => post
+            .cover_image
+            .clone()
+            .filter(|c| images.contains(c))
+            .or_else(|| images.first().cloned()),
+    };
+
+    Ok((images, cover))
+}
+
+/// 准备账号要发布的笔记，返回笔记 id 和改写失败原因
+async fn prepare_account(
+    post: &Post,
+    owner_phone: &str,
+    account: &AccountOverride,
+    ai: Option<&(crate::model::AIProvider, AiVariationOptions)>,
+) -> Result<(Post, Option<String>), String> {
+    let pool = crate::storage::pool().await?;
+    let user = UserRepo::new(pool)
+        .find_by_phone(&account.phone)
+        .await?
+        .ok_or_else(|| format!("账号 {} 未登录", account.phone))?;
+
+    let wants_ai = ai.is_some() && account.title.is_none() && account.content.is_none();
+    let posts = PostRepo::new(pool);
+    if account.phone == owner_phone && account.is_empty() && !wants_ai {
+        return Ok((post.clone(), None));
+    }
+
+    let (images, cover) =
+        apply_image_override(post, account.images.as_deref(), account.cover_image.as_deref())?;
+    let mut title = account.title.clone().unwrap_or_else(|| post.title.clone());
+    let mut content = account
+        .content
+        .clone()
+        .unwrap_or_else(|| post.content.clone());
+
+    let mut variation_error = None;
+    if let (true, Some((provider, options))) = (wants_ai, ai) {
+        match crate::ai::generate_post_variation(
+            provider,
+            &options.model_name,
+            &post.title,
+            &post.content,
+            options.instruction.as_deref(),
+        )
+        .await
+        {
+            Ok(variation) => {
+                title = variation.title;
+                content = variation.content;
+            }
+            Err(e) => variation_error = Some(e),
+        }
+    }
+
+    // 为该账号保存一份副本，发布状态和失败截图分别记录
+    let edit = PostEdit {
+        title,
+        content,
+        images,
+        cover_image: cover,
+        ..PostEdit::from(post.clone())
+    };
+    let copy_id = posts.create(user.id, &edit).await?;
+    // 副本沿用原笔记的可见范围、定时发布、地点和合集
+    posts.copy_publish_options(post.id, copy_id).await?;
+    let copy = posts
+        .get(copy_id)
+        .await?
+        .ok_or_else(|| "创建笔记副本失败".to_string())?;
+    Ok((copy, variation_error))
+}
+
+/// 发布任务异常退出后让笔记可以重新编辑
+///
+/// 还没开始发布的退回草稿；已经在发布中的无法确定是否发出，标记为失败由用户确认。
+async fn release_aborted(posts: &PostRepo<'_>, post_id: i64, message: &str) -> Result<(), String> {
+    let Some(post) = posts.get(post_id).await? else {
+        return Ok(());
+    };
+    match PostStatus::parse(&post.status) {
+        Some(PostStatus::Queued) => {
+            posts
+                .transition(post_id, PostStatus::Draft, None, None)
+                .await?;
+        }
+        Some(PostStatus::Publishing) => {
+            posts
+                .transition(post_id, PostStatus::Failed, Some(message), None)
+                .await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 把一篇笔记发布到多个账号
+///
+/// 每个账号可以覆盖标题、正文、图片顺序和封面；与原笔记不同的版本会在该账号下保存为新笔记。
+/// `max_concurrency` 默认为 1，即逐个账号发布。
+#[tauri::command]
+pub async fn publish_to_accounts(
+    post_id: i64,
+    accounts: Vec<AccountOverride>,
+    max_concurrency: Option<usize>,
+    ai_variation: Option<AiVariationOptions>,
+) -> Result<MultiPublishReport, String> {
+    if accounts.is_empty() {
+        return Err("请至少选择一个账号".to_string());
+    }
+    let mut seen = HashSet::new();
+    if let Some(dup) = accounts.iter().find(|a| !seen.insert(a.phone.as_str())) {
+        return Err(format!("账号 {} 重复", dup.phone));
+    }
+
+    let pool = crate::storage::pool().await?;
+    let posts = PostRepo::new(pool);
+    let post = posts
+        .get(post_id)
+        .await?
+        .ok_or_else(|| "笔记不存在".to_string())?;
+    let owner_phone = UserRepo::new(pool)
+        .get(post.user_id)
+        .await?
+        .map(|u| u.phone)
+        .unwrap_or_default();
+
+    let ai = match ai_variation {
+        Some(options) => {
+            let provider = crate::ai::get_ai_provider(options.provider_id)
+                .await?
+                .ok_or_else(|| "未找到 AI 提供商".to_string())?;
+            Some((provider, options))
+        }
+        None => None,
+    };
+
+    // 先逐个准备并排队，准备失败的账号直接记入报告
+    let mut results: Vec<AccountPublishResult> = Vec::with_capacity(accounts.len());
+    let mut queued: Vec<(usize, i64)> = Vec::new();
+    for account in &accounts {
+        match prepare_account(&post, &owner_phone, account, ai.as_ref()).await {
+            Ok((target, variation_error)) => {
+                let index = results.len();
+                let mut result = AccountPublishResult {
+                    phone: account.phone.clone(),
+                    post_id: Some(target.id),
+                    title: Some(target.title.clone()),
+                    success: false,
+                    note_url: None,
+                    error: None,
+                    failure_kind: None,
+                    screenshot: None,
+                    run_id: None,
+                    variation_error,
+                };
+                match posts
+                    .transition(target.id, PostStatus::Queued, None, None)
+                    .await
+                {
+                    Ok(_) => queued.push((index, target.id)),
+                    Err(e) => result.error = Some(e),
+                }
+                results.push(result);
+            }
+            Err(e) => results.push(AccountPublishResult::failed(&account.phone, e)),
+        }
+    }
+
+    let concurrency = max_concurrency.unwrap_or(1).clamp(1, MAX_CONCURRENCY);
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut task_index = HashMap::new();
+    for (index, target_id) in queued {
+        let semaphore = semaphore.clone();
+        let handle = tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            super::run_queued_post(target_id).await
+        });
+        task_index.insert(handle.id(), index);
+    }
+
+    // 某个账号的任务崩溃时只记为该账号失败，继续等待其他账号，不能提前返回中断它们
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (index, outcome) = match joined {
+            Ok((id, outcome)) => (task_index[&id], outcome),
+            Err(e) => {
+                let index = task_index[&e.id()];
+                let message = format!("发布任务异常退出: {}", e);
+                if let Some(post_id) = results[index].post_id {
+                    if let Err(e) = release_aborted(&posts, post_id, &message).await {
+                        eprintln!("Failed to release post {}: {}", post_id, e);
+                    }
+                }
+                (
+                    index,
+                    Err(PublishFailure::new(PublishErrorKind::Unknown, message)),
+                )
+            }
+        };
+        let result = &mut results[index];
+        match outcome {
+            Ok(published) => {
+                result.success = true;
+                result.note_url = published.note_url;
+            }
+            Err(failure) => {
+                result.error = Some(failure.message);
+                result.failure_kind = Some(failure.kind);
+                result.screenshot = failure.screenshot;
+                result.run_id = failure.run_id;
+            }
+        }
+    }
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    Ok(MultiPublishReport {
+        post_id,
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    })
+}
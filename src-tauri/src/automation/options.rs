@@ -0,0 +1,432 @@
+use super::driver::Driver;
+use super::editor::match_suggestion;
+use super::human;
+use super::outcome::{PublishErrorKind, PublishFailure};
+use super::selectors;
+use super::take_screenshot;
+use super::wait::{self, Step};
+use chrono::{Duration as ChronoDuration, NaiveDateTime};
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// 平台定时发布允许的最早时间（距现在）
+const MIN_SCHEDULE_AHEAD_MINUTES: i64 = 60;
+/// 平台定时发布允许的最晚时间（距现在）
+const MAX_SCHEDULE_AHEAD_DAYS: i64 = 14;
+
+/// 笔记可见范围
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Public,
+    /// 仅自己可见
+    Private,
+    /// 仅互关好友可见
+    Friends,
+}
+
+impl Visibility {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "public" => Some(Visibility::Public),
+            "private" => Some(Visibility::Private),
+            "friends" => Some(Visibility::Friends),
+            _ => None,
+        }
+    }
+
+    /// 发布页上对应选项的文字
+    pub fn label(&self) -> &'static str {
+        match self {
+            Visibility::Public => "公开可见",
+            Visibility::Private => "仅自己可见",
+            Visibility::Friends => "仅互关好友可见",
+        }
+    }
+}
+
+/// 填写完成后的操作
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    schemars::JsonSchema,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishMode {
+    /// 点击发布
+    #[default]
+    Publish,
+    /// 点击「暂存离开」，保存到创作者中心的草稿箱
+    PlatformDraft,
+    /// 只填写并截图，不点击发布
+    DryRun,
+}
+
+impl PublishMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "publish" => Some(PublishMode::Publish),
+            "platform_draft" => Some(PublishMode::PlatformDraft),
+            "dry_run" => Some(PublishMode::DryRun),
+            _ => None,
+        }
+    }
+
+    /// 完成后给用户的提示
+    pub fn done_message(&self) -> &'static str {
+        match self {
+            PublishMode::Publish => "发布成功",
+            PublishMode::PlatformDraft => "已保存到平台草稿箱",
+            PublishMode::DryRun => "试运行完成，未点击发布",
+        }
+    }
+}
+
+/// 发布页上的附加设置，未填写的项保持平台默认
+///
+/// 界面、REST 接口和 MCP 共用这一个类型。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema, ToSchema)]
+pub struct PublishOptions {
+    /// 填写完成后的操作，默认点击发布
+    #[serde(default)]
+    pub mode: PublishMode,
+    pub visibility: Option<Visibility>,
+    /// 平台定时发布时间，浏览器所在时区，格式 `YYYY-MM-DD HH:MM`
+    #[salvo(schema(example = "2026-10-20 20:00"))]
+    pub scheduled_at: Option<String>,
+    /// 地点名称，从平台的地点候选中选择完全一致的一项
+    pub location: Option<String>,
+    /// 是否声明原创
+    pub original: Option<bool>,
+    /// 加入的合集名称
+    pub collection: Option<String>,
+}
+
+/// 解析定时发布时间，并检查是否在平台允许的范围内
+pub fn parse_schedule_time(value: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    let time = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .ok_or_else(|| format!("定时发布时间格式错误: {}，应为 YYYY-MM-DD HH:MM", value))?;
+
+    if time < now + ChronoDuration::minutes(MIN_SCHEDULE_AHEAD_MINUTES) {
+        return Err(format!(
+            "定时发布时间需晚于当前时间 {} 分钟以上",
+            MIN_SCHEDULE_AHEAD_MINUTES
+        ));
+    }
+    if time > now + ChronoDuration::days(MAX_SCHEDULE_AHEAD_DAYS) {
+        return Err(format!(
+            "定时发布时间不能超过 {} 天",
+            MAX_SCHEDULE_AHEAD_DAYS
+        ));
+    }
+    Ok(time)
+}
+
+impl PublishOptions {
+    /// 是否没有需要在发布页上设置的项
+    pub fn is_empty(&self) -> bool {
+        self.visibility.is_none()
+            && self.scheduled_at.is_none()
+            && self.location.is_none()
+            && self.original.is_none()
+            && self.collection.is_none()
+    }
+
+    /// 在启动浏览器前检查选项
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(time) = &self.scheduled_at {
+            parse_schedule_time(time, chrono::Local::now().naive_local())?;
+        }
+        Ok(())
+    }
+
+    /// 检查能否保存到笔记
+    ///
+    /// 定时任务和重试都按笔记保存的设置真正发布，试运行和保存到平台草稿箱只能用于单次发布。
+    pub fn validate_saved(&self) -> Result<(), String> {
+        if self.mode != PublishMode::Publish {
+            return Err("试运行和保存到平台草稿箱不能保存到笔记，请在单次发布时选择".to_string());
+        }
+        self.validate()
+    }
+}
+
+/// 在发布页上应用附加设置，每一项设置后都读回页面状态确认生效
+pub async fn apply(tab: &dyn Driver, options: &PublishOptions) -> Result<(), PublishFailure> {
+    if options.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(location) = &options.location {
+        set_location(tab, location).await?;
+    }
+    if let Some(collection) = &options.collection {
+        set_collection(tab, collection).await?;
+    }
+    if let Some(original) = options.original {
+        set_original(tab, original).await?;
+    }
+    if let Some(visibility) = options.visibility {
+        set_visibility(tab, visibility).await?;
+    }
+    if let Some(time) = &options.scheduled_at {
+        let time = parse_schedule_time(time, chrono::Local::now().naive_local())?;
+        set_schedule(tab, &time.format("%Y-%m-%d %H:%M").to_string()).await?;
+    }
+
+    take_screenshot(tab, "5_options_applied");
+    Ok(())
+}
+
+fn not_applied(tab: &dyn Driver, name: &str, detail: &str) -> PublishFailure {
+    take_screenshot(tab, &format!("error_option_{}", name));
+    PublishFailure::new(
+        PublishErrorKind::PageChanged,
+        format!("发布设置「{}」未生效: {}", name, detail),
+    )
+}
+
+fn eval_string(tab: &dyn Driver, script: &str) -> String {
+    tab.evaluate(script, false)
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn eval_bool(tab: &dyn Driver, script: &str) -> bool {
+    tab.evaluate(script, false)
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// 注册表中 `items` 对应的候选项的文字
+fn item_texts(tab: &dyn Driver, items: &str) -> Vec<String> {
+    let script = format!(
+        "JSON.stringify(Array.from(document.querySelectorAll({})).map(e => e.innerText))",
+        serde_json::json!(items)
+    );
+    serde_json::from_str(&eval_string(tab, &script)).unwrap_or_default()
+}
+
+/// 点击前获得焦点的元素，用来判断焦点是否移到了下拉框的输入框
+const FOCUS_MOVED: &str = r#"(() => {
+    const el = document.activeElement;
+    return !!el && el !== window.__helperFocus
+        && (['INPUT', 'TEXTAREA'].includes(el.tagName) || el.isContentEditable);
+})()"#;
+
+/// 在下拉搜索框中输入关键字并选择完全一致的候选项，`trigger` 和 `items` 是注册表中的名称
+async fn pick_from_dropdown(
+    tab: &dyn Driver,
+    trigger: &str,
+    keyword: &str,
+    items: &str,
+) -> Result<bool, PublishFailure> {
+    let _ = tab.evaluate("window.__helperFocus = document.activeElement", false);
+    selectors::click(tab, trigger).await?;
+    wait::until(Step::Element, "下拉框的搜索框", || {
+        eval_bool(tab, FOCUS_MOVED).then_some(())
+    })
+    .await?;
+    human::type_text(tab, keyword)
+        .await
+        .map_err(|e| format!("Type '{}' failed: {}", keyword, e))?;
+
+    let items = selectors::css(items);
+    let found = wait::until(Step::Suggestions, "下拉框候选项", || {
+        match_suggestion(&item_texts(tab, &items), keyword)
+    })
+    .await;
+    match found {
+        Ok(index) => {
+            human::click(tab, &items, index).await?;
+            Ok(true)
+        }
+        Err(_) => Ok(false),
+    }
+}
+
+/// 注册表中 `area` 对应的区域内是否有文字完全一致的元素，用于确认下拉框的选择结果
+fn page_shows(tab: &dyn Driver, area: &str, text: &str) -> bool {
+    let script = format!(
+        "Array.from(document.querySelectorAll({})).some(e => e.innerText.trim() === {})",
+        serde_json::json!(selectors::css(area)),
+        serde_json::json!(text)
+    );
+    eval_bool(tab, &script)
+}
+
+/// 等待 `area` 中显示 `text`，超时返回 false
+async fn wait_shown(tab: &dyn Driver, area: &str, text: &str) -> bool {
+    wait::until(Step::Element, text, || {
+        page_shows(tab, area, text).then_some(())
+    })
+    .await
+    .is_ok()
+}
+
+async fn set_location(tab: &dyn Driver, location: &str) -> Result<(), PublishFailure> {
+    log::info!("Setting location: {}", location);
+    let found = pick_from_dropdown(
+        tab,
+        "options.location_trigger",
+        location,
+        "options.location_items",
+    )
+    .await?;
+    if !found {
+        return Err(not_applied(tab, "地点", &format!("没有找到地点 {}", location)));
+    }
+    if !wait_shown(tab, "options.location_value", location).await {
+        return Err(not_applied(tab, "地点", "选择后页面未显示该地点"));
+    }
+    Ok(())
+}
+
+async fn set_collection(tab: &dyn Driver, collection: &str) -> Result<(), PublishFailure> {
+    log::info!("Setting collection: {}", collection);
+    let found = pick_from_dropdown(
+        tab,
+        "options.collection_trigger",
+        collection,
+        "options.collection_items",
+    )
+    .await?;
+    if !found {
+        return Err(not_applied(tab, "合集", &format!("没有找到合集 {}", collection)));
+    }
+    if !wait_shown(tab, "options.collection_value", collection).await {
+        return Err(not_applied(tab, "合集", "选择后页面未显示该合集"));
+    }
+    Ok(())
+}
+
+/// 检查复选框、单选框或开关是否选中，`node` 是它的文字或元素本身
+const CHECKED_EXPRESSION: &str = r#"(() => {
+    const box = node.closest('label, .d-checkbox, .d-radio, .d-switch, [class*="checkbox"], [class*="radio"]')
+        || node.parentElement;
+    const input = box.querySelector('input');
+    if (input) return input.checked;
+    return /checked|active/.test(box.className) || !!box.querySelector('[class*="checked"], [class*="active"]');
+})()"#;
+
+/// 注册表中 `key` 对应的复选框或开关当前是否选中
+fn is_checked(tab: &dyn Driver, key: &str) -> bool {
+    selectors::eval_on(tab, key, CHECKED_EXPRESSION)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// 等待开关变为 `expected`，超时返回 false
+async fn wait_checked(tab: &dyn Driver, key: &str, expected: bool) -> bool {
+    wait::until(Step::Element, key, || {
+        (is_checked(tab, key) == expected).then_some(())
+    })
+    .await
+    .is_ok()
+}
+
+async fn set_original(tab: &dyn Driver, original: bool) -> Result<(), PublishFailure> {
+    log::info!("Setting original declaration: {}", original);
+    let key = "options.original_switch";
+    if is_checked(tab, key) != original {
+        selectors::click(tab, key).await?;
+        // 开启原创声明时平台可能弹出须知，需要勾选同意并确认
+        if original {
+            let needs_consent = wait::until(Step::Element, "原创声明须知", || {
+                if is_checked(tab, key) {
+                    Some(false)
+                } else {
+                    selectors::find(tab, "options.original_agree").map(|_| true)
+                }
+            })
+            .await?;
+            if needs_consent {
+                selectors::click(tab, "options.original_agree").await?;
+                selectors::click(tab, "options.original_confirm").await?;
+            }
+        }
+    }
+    if !wait_checked(tab, key, original).await {
+        return Err(not_applied(tab, "原创声明", "开关状态与设置不一致"));
+    }
+    Ok(())
+}
+
+async fn set_visibility(tab: &dyn Driver, visibility: Visibility) -> Result<(), PublishFailure> {
+    log::info!("Setting visibility: {:?}", visibility);
+    let area = "options.visibility_value";
+    if !page_shows(tab, area, visibility.label()) {
+        // 可见范围在下拉框中，通过下拉框本身展开，不依赖当前选中的值
+        selectors::click(tab, "options.visibility_select").await?;
+        let items = selectors::css("options.visibility_items");
+        let index = wait::until(Step::Element, "可见范围选项", || {
+            item_texts(tab, &items)
+                .iter()
+                .position(|text| text.trim() == visibility.label())
+        })
+        .await
+        .map_err(|_| not_applied(tab, "可见范围", "下拉框中没有该选项"))?;
+        human::click(tab, &items, index).await?;
+    }
+    if !wait_shown(tab, area, visibility.label()).await {
+        return Err(not_applied(tab, "可见范围", visibility.label()));
+    }
+    Ok(())
+}
+
+async fn set_schedule(tab: &dyn Driver, time: &str) -> Result<(), PublishFailure> {
+    log::info!("Setting scheduled publish time: {}", time);
+    let label = "定时发布";
+    if !is_checked(tab, "options.schedule_switch") {
+        selectors::click(tab, "options.schedule_switch").await?;
+    }
+
+    // 打开开关后等待时间输入框出现
+    let input = "options.schedule_input";
+    selectors::click(tab, input)
+        .await
+        .map_err(|e| not_applied(tab, label, &format!("找不到时间输入框: {}", e)))?;
+    // 先清空默认时间再输入
+    let _ = tab.evaluate(
+        "document.activeElement && document.activeElement.select && document.activeElement.select()",
+        false,
+    );
+    human::type_text(tab, time)
+        .await
+        .map_err(|e| format!("Type schedule time failed: {}", e))?;
+    let _ = tab.press_key("Enter");
+
+    let read_value = || {
+        selectors::eval_on(tab, input, "node.value || ''")
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default()
+    };
+    let confirmed = wait::until(Step::Element, "定时发布时间", || {
+        read_value().starts_with(time).then_some(())
+    })
+    .await;
+    if confirmed.is_err() {
+        return Err(not_applied(
+            tab,
+            label,
+            &format!("页面时间为 {}，期望 {}", read_value(), time),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use super::options::PublishMode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// 创作者中心发布笔记的接口，用于从网络响应中读取笔记 id
+pub const PUBLISH_API_PATHS: &[&str] = &["/web_api/sns/v2/note", "/api/galaxy/creator/note/publish"];
+
+/// 发布失败的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishErrorKind {
+    /// 登录已失效，需要重新登录
+    SessionExpired,
+    UploadFailed,
+    /// 内容未通过平台审核，例如包含违规词
+    ContentRejected,
+    /// 操作过于频繁
+    RateLimited,
+    /// 页面结构变化，找不到需要的元素
+    PageChanged,
+    /// 等待页面或上传超时
+    Timeout,
+    /// 已经点击发布但没有确认结果，笔记可能已经发出
+    Unconfirmed,
+    /// 发布前检查未通过，例如标题超长、图片不存在
+    InvalidContent,
+    Unknown,
+}
+
+impl PublishErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublishErrorKind::SessionExpired => "session_expired",
+            PublishErrorKind::UploadFailed => "upload_failed",
+            PublishErrorKind::ContentRejected => "content_rejected",
+            PublishErrorKind::RateLimited => "rate_limited",
+            PublishErrorKind::PageChanged => "page_changed",
+            PublishErrorKind::Timeout => "timeout",
+            PublishErrorKind::Unconfirmed => "unconfirmed",
+            PublishErrorKind::InvalidContent => "invalid_content",
+            PublishErrorKind::Unknown => "unknown",
+        }
+    }
+
+    /// 重试可能成功的失败类型
+    ///
+    /// 登录失效、内容被拒和检查未通过需要人工处理；结果未确认和原因不明的失败
+    /// 可能已经发出笔记，重试会重复发布。
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            PublishErrorKind::UploadFailed
+                | PublishErrorKind::RateLimited
+                | PublishErrorKind::PageChanged
+                | PublishErrorKind::Timeout
+        )
+    }
+
+    /// 根据平台提示或错误信息推断失败类型
+    pub fn classify(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+        if has(&["登录", "login", "未授权", "unauthorized"]) {
+            PublishErrorKind::SessionExpired
+        } else if has(&["频繁", "稍后再试", "too many"]) {
+            PublishErrorKind::RateLimited
+        } else if has(&["违规", "敏感", "违反", "社区规范", "不符合", "审核", "风险"]) {
+            PublishErrorKind::ContentRejected
+        } else if has(&["上传", "upload", "图片"]) {
+            PublishErrorKind::UploadFailed
+        } else if has(&[
+            "wait for",
+            "waiting for",
+            "cannot find",
+            "no match",
+            "页面元素",
+        ]) {
+            PublishErrorKind::PageChanged
+        } else if has(&["超时", "timeout", "timed out"]) {
+            PublishErrorKind::Timeout
+        } else {
+            PublishErrorKind::Unknown
+        }
+    }
+}
+
+/// 发布成功后记录的笔记信息，平台未返回时为 None
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PublishedNote {
+    #[serde(default)]
+    pub mode: PublishMode,
+    pub note_id: Option<String>,
+    pub note_url: Option<String>,
+    /// 试运行时各步骤的页面截图
+    #[serde(default)]
+    pub screenshots: Vec<String>,
+    /// 没有找到对应话题、以普通文字发布的话题
+    #[serde(default)]
+    pub unresolved_topics: Vec<String>,
+    #[serde(default)]
+    pub unresolved_mentions: Vec<String>,
+    /// 本次发布的运行记录 id
+    #[serde(default)]
+    pub run_id: Option<String>,
+}
+
+/// 发布失败的类型、原因以及失败时的页面截图
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PublishFailure {
+    pub kind: PublishErrorKind,
+    pub message: String,
+    pub screenshot: Option<String>,
+    /// 失败时的运行记录 id，可用来查看步骤、页面 HTML 和控制台输出
+    #[serde(default)]
+    pub run_id: Option<String>,
+}
+
+impl PublishFailure {
+    pub fn new(kind: PublishErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            screenshot: None,
+            run_id: None,
+        }
+    }
+}
+
+impl From<String> for PublishFailure {
+    fn from(message: String) -> Self {
+        Self::new(PublishErrorKind::classify(&message), message)
+    }
+}
+
+impl fmt::Display for PublishFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.kind.as_str(), self.message)
+    }
+}
+
+/// 发布接口的响应
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublishApiResponse {
+    Success(PublishedNote),
+    Rejected(String),
+}
+
+pub fn is_publish_api(url: &str) -> bool {
+    PUBLISH_API_PATHS.iter().any(|path| url.contains(path))
+}
+
+fn find_str(value: &Value, paths: &[&str]) -> Option<String> {
+    paths.iter().find_map(|path| {
+        path.split('.')
+            .try_fold(value, |v, key| v.get(key))
+            .and_then(|v| match v {
+                Value::String(s) if !s.is_empty() => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+    })
+}
+
+/// 解析发布接口返回的 JSON，不是发布结果时返回 None
+pub fn parse_publish_response(body: &str) -> Option<PublishApiResponse> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let success = value
+        .get("success")
+        .and_then(Value::as_bool)
+        .or_else(|| value.get("code").and_then(Value::as_i64).map(|c| c == 0))?;
+
+    if !success {
+        let message = find_str(&value, &["msg", "message", "data.msg"])
+            .unwrap_or_else(|| "平台拒绝了发布请求".to_string());
+        return Some(PublishApiResponse::Rejected(message));
+    }
+
+    let note_id = find_str(&value, &["data.id", "data.note_id", "note_id", "id"]);
+    let note_url = find_str(
+        &value,
+        &["share_link", "data.share_link", "data.share_url", "data.link"],
+    )
+    .or_else(|| {
+        note_id
+            .as_ref()
+            .map(|id| format!("https://www.xiaohongshu.com/explore/{}", id))
+    });
+    Some(PublishApiResponse::Success(PublishedNote {
+        note_id,
+        note_url,
+        ..Default::default()
+    }))
+}
+
+/// 页面提示中表示失败的关键词
+const FAILURE_HINTS: &[&str] = &[
+    "失败", "违规", "错误", "频繁", "过期", "重新登录", "无法", "不符合", "敏感",
+];
+
+/// 判断页面提示是否为发布失败，返回失败类型
+pub fn toast_failure(text: &str) -> Option<PublishErrorKind> {
+    if FAILURE_HINTS.iter().any(|hint| text.contains(hint)) {
+        Some(PublishErrorKind::classify(text))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,117 @@
+use super::socks::SocksForwarder;
+use crate::model::{ProxyConfig, ProxyScheme};
+use crate::storage::repository::UserRepo;
+use crate::storage::secrets;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 查询出口 IP 的地址
+const IP_ECHO_URL: &str = "https://api.ipify.org?format=json";
+const TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyTestResult {
+    /// 没有设置代理时为 None，测试的是本机直连
+    pub proxy: Option<String>,
+    pub ip: String,
+    pub latency_ms: u64,
+}
+
+/// 读取账号的代理设置，密码已解密，仅供启动浏览器和测试使用
+pub async fn account_proxy(phone: &str) -> Result<Option<ProxyConfig>, String> {
+    let pool = crate::storage::pool().await?;
+    let Some(mut proxy) = UserRepo::new(pool).get_proxy(phone).await? else {
+        return Ok(None);
+    };
+    proxy.password = proxy
+        .password
+        .as_deref()
+        .map(secrets::decrypt_secret)
+        .transpose()?;
+    Ok(Some(proxy))
+}
+
+#[tauri::command]
+pub async fn get_account_proxy(phone: String) -> Result<Option<ProxyConfig>, String> {
+    let pool = crate::storage::pool().await?;
+    let proxy = UserRepo::new(pool).get_proxy(&phone).await?;
+    Ok(proxy.map(|p| ProxyConfig {
+        password: p.password.as_deref().map(secrets::mask_secret),
+        ..p
+    }))
+}
+
+/// 保存账号的代理，`proxy` 为 None 时改回直连
+///
+/// 账号的浏览器在下一个任务开始时用新代理重启。
+#[tauri::command]
+pub async fn set_account_proxy(phone: String, proxy: Option<ProxyConfig>) -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    let repo = UserRepo::new(pool);
+    let proxy = match proxy {
+        Some(mut proxy) => {
+            proxy.validate()?;
+            proxy.host = proxy.host.trim().to_string();
+            // 脱敏值表示密码没有修改，沿用已保存的密文
+            proxy.password = match proxy.password.filter(|p| !p.is_empty()) {
+                Some(p) if secrets::is_masked(&p) => {
+                    repo.get_proxy(&phone).await?.and_then(|old| old.password)
+                }
+                Some(p) => Some(secrets::encrypt_secret(&p)?),
+                None => None,
+            };
+            Some(proxy)
+        }
+        None => None,
+    };
+    repo.set_proxy(&phone, proxy.as_ref()).await
+}
+
+/// 通过账号的代理访问外网，返回出口 IP 和耗时
+#[tauri::command]
+pub async fn test_account_proxy(phone: String) -> Result<ProxyTestResult, String> {
+    let proxy = account_proxy(&phone).await?;
+    let mut builder = reqwest::Client::builder().timeout(TEST_TIMEOUT);
+    // 带认证的 SOCKS5 和浏览器一样经过本地转发，测试结果与实际发布一致
+    let mut forwarder = None;
+    if let Some(config) = &proxy {
+        let server = match config.scheme {
+            ProxyScheme::Socks5 if config.has_credentials() => forwarder
+                .insert(SocksForwarder::start(config.clone()).await?)
+                .server(),
+            _ => config.server(),
+        };
+        let mut p = reqwest::Proxy::all(server).map_err(|e| e.to_string())?;
+        if config.scheme == ProxyScheme::Http && config.has_credentials() {
+            p = p.basic_auth(
+                config.username.as_deref().unwrap_or_default(),
+                config.password.as_deref().unwrap_or_default(),
+            );
+        }
+        builder = builder.proxy(p);
+    }
+    let client = builder.build().map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    let body: serde_json::Value = client
+        .get(IP_ECHO_URL)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("代理连接失败: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("读取出口 IP 失败: {}", e))?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let ip = body
+        .get("ip")
+        .and_then(|v| v.as_str())
+        .ok_or("读取出口 IP 失败")?
+        .to_string();
+    Ok(ProxyTestResult {
+        proxy: proxy.map(|p| p.server()),
+        ip,
+        latency_ms,
+    })
+}
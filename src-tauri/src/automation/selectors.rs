@@ -0,0 +1,503 @@
+use super::driver::Driver;
+use super::human;
+use super::trace::{self, RunKind};
+use super::wait::{self, Step};
+use super::{is_login_page, site};
+use crate::storage::repository::ConfigRepo;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// 随应用发布的默认选择器
+const BUILTIN_REGISTRY: &str = include_str!("selectors.json");
+/// 数据目录中的选择器文件，其中的选择器覆盖内置选择器
+pub const REGISTRY_FILE: &str = "selectors.json";
+/// 保存在 config 表中的单项覆盖，值为 `{ 名称: [候选, ...] }`
+pub const OVERRIDES_KEY: &str = "selector_overrides";
+
+/// 一个页面元素的定位方式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectorEntry {
+    #[serde(default)]
+    pub description: String,
+    /// 健康检查时在哪个页面查找：login、publish 或 editor，为空表示只在发布过程中出现
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    /// 按顺序尝试的候选，`/` 或 `(` 开头的是 XPath，其余是 CSS 选择器
+    pub candidates: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectorRegistry {
+    pub version: u32,
+    pub selectors: BTreeMap<String, SelectorEntry>,
+}
+
+/// 当前生效的选择器和 config 表中的覆盖项
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorSettings {
+    pub registry: SelectorRegistry,
+    pub overrides: BTreeMap<String, Vec<String>>,
+    /// 最近一次加载时的问题，例如选择器文件格式错误
+    pub warnings: Vec<String>,
+}
+
+/// 合并后的选择器和合并过程中被忽略或需要注意的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedSelectors {
+    pub registry: SelectorRegistry,
+    pub warnings: Vec<String>,
+}
+
+pub fn is_xpath(candidate: &str) -> bool {
+    candidate.starts_with('/') || candidate.starts_with('(')
+}
+
+/// 解析并检查选择器文件
+pub fn parse_registry(text: &str) -> Result<SelectorRegistry, String> {
+    let registry: SelectorRegistry =
+        serde_json::from_str(text).map_err(|e| format!("选择器文件格式错误: {}", e))?;
+    if registry.version == 0 {
+        return Err("选择器文件缺少版本号".to_string());
+    }
+    for (key, entry) in &registry.selectors {
+        if entry.candidates.iter().all(|c| c.trim().is_empty()) {
+            return Err(format!("选择器 {} 没有候选", key));
+        }
+    }
+    Ok(registry)
+}
+
+pub fn builtin() -> SelectorRegistry {
+    parse_registry(BUILTIN_REGISTRY).expect("内置选择器文件无效")
+}
+
+/// 按 内置 → 数据目录文件 → config 覆盖 的顺序合并
+///
+/// 文件版本低于内置版本时多半是旧版本留下的，其中的候选排在内置候选之后，新版本修正的
+/// 选择器不会被旧文件挡住，并列出这些名称方便用户确认是否还需要；覆盖项只替换已有名称的候选。
+pub fn combine(
+    builtin: SelectorRegistry,
+    file: Option<SelectorRegistry>,
+    overrides: &BTreeMap<String, Vec<String>>,
+) -> LoadedSelectors {
+    let mut registry = builtin;
+    let mut warnings = Vec::new();
+    if let Some(file) = file {
+        if file.version < registry.version {
+            let mut appended = Vec::new();
+            for (key, entry) in file.selectors {
+                let Some(current) = registry.selectors.get_mut(&key) else {
+                    registry.selectors.insert(key, entry);
+                    continue;
+                };
+                let extra: Vec<String> = entry
+                    .candidates
+                    .into_iter()
+                    .filter(|c| !current.candidates.contains(c))
+                    .collect();
+                if !extra.is_empty() {
+                    current.candidates.extend(extra);
+                    appended.push(key);
+                }
+            }
+            if !appended.is_empty() {
+                warnings.push(format!(
+                    "选择器文件版本 {} 低于内置版本 {}，以下选择器中文件的候选排在内置候选之后: {}",
+                    file.version,
+                    registry.version,
+                    appended.join(", ")
+                ));
+            }
+        } else {
+            registry.version = file.version;
+            registry.selectors.extend(file.selectors);
+        }
+    }
+    for (key, candidates) in overrides {
+        match registry.selectors.get_mut(key) {
+            Some(entry) if !candidates.is_empty() => entry.candidates = candidates.clone(),
+            Some(_) => {}
+            None => warnings.push(format!("忽略未知的选择器覆盖: {}", key)),
+        }
+    }
+    LoadedSelectors { registry, warnings }
+}
+
+lazy_static! {
+    static ref ACTIVE: RwLock<SelectorRegistry> = RwLock::new(builtin());
+    static ref LOAD_WARNINGS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+}
+
+pub fn active() -> SelectorRegistry {
+    ACTIVE.read().unwrap().clone()
+}
+
+/// 替换当前生效的选择器，正在进行的任务从下一次查找开始使用新选择器
+pub fn install(registry: SelectorRegistry) {
+    *ACTIVE.write().unwrap() = registry;
+}
+
+/// 名称对应的全部候选
+pub fn candidates(key: &str) -> Vec<String> {
+    ACTIVE
+        .read()
+        .unwrap()
+        .selectors
+        .get(key)
+        .map(|entry| entry.candidates.clone())
+        .unwrap_or_default()
+}
+
+/// 名称对应的 CSS 候选合并成一个选择器，用于页面脚本中的 querySelectorAll
+pub fn css(key: &str) -> String {
+    candidates(key)
+        .into_iter()
+        .filter(|c| !is_xpath(c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn registry_path() -> std::path::PathBuf {
+    crate::storage::get_app_dir().join(REGISTRY_FILE)
+}
+
+async fn load_overrides() -> Result<BTreeMap<String, Vec<String>>, String> {
+    let pool = crate::storage::pool().await?;
+    Ok(ConfigRepo::new(pool)
+        .get(OVERRIDES_KEY)
+        .await?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+/// 读取数据目录文件和 config 覆盖并生效，启动时和修改后调用
+///
+/// 文件无法读取或格式错误时使用内置选择器，原因和合并时的问题一起放在 `warnings` 中。
+pub async fn load() -> Result<LoadedSelectors, String> {
+    let mut warnings = Vec::new();
+    let file = match std::fs::read_to_string(registry_path()) {
+        Ok(text) => match parse_registry(&text) {
+            Ok(registry) => Some(registry),
+            Err(e) => {
+                warnings.push(format!("{}，使用内置选择器", e));
+                None
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            warnings.push(format!("读取选择器文件失败: {}，使用内置选择器", e));
+            None
+        }
+    };
+    let mut loaded = combine(builtin(), file, &load_overrides().await?);
+    warnings.append(&mut loaded.warnings);
+    for warning in &warnings {
+        log::warn!("{}", warning);
+    }
+    install(loaded.registry.clone());
+    *LOAD_WARNINGS.write().unwrap() = warnings.clone();
+    Ok(LoadedSelectors {
+        registry: loaded.registry,
+        warnings,
+    })
+}
+
+fn match_count(tab: &dyn Driver, candidate: &str) -> usize {
+    if is_xpath(candidate) {
+        tab.count_xpath(candidate)
+    } else {
+        tab.count(candidate)
+    }
+}
+
+/// 第一个能在页面上找到的候选，不等待
+pub fn find(tab: &dyn Driver, key: &str) -> Option<String> {
+    candidates(key)
+        .into_iter()
+        .find(|c| match_count(tab, c) > 0)
+}
+
+/// 等待任意一个候选出现，返回找到的候选
+pub async fn wait_for(tab: &dyn Driver, key: &str) -> Result<String, String> {
+    Ok(wait::for_selector(tab, key, Step::Element).await?)
+}
+
+pub async fn click(tab: &dyn Driver, key: &str) -> Result<(), String> {
+    let found = wait_for(tab, key).await?;
+    human::click(tab, &found, 0).await
+}
+
+/// 点击最后一个匹配的元素，用于弹窗中与页面重名的按钮
+pub async fn click_last(tab: &dyn Driver, key: &str) -> Result<(), String> {
+    let found = wait_for(tab, key).await?;
+    let last = match_count(tab, &found).saturating_sub(1);
+    human::click(tab, &found, last).await
+}
+
+pub async fn type_into(tab: &dyn Driver, key: &str, text: &str) -> Result<(), String> {
+    let found = wait_for(tab, key).await?;
+    human::type_into(tab, &found, text).await
+}
+
+pub async fn set_files(tab: &dyn Driver, key: &str, files: &[&str]) -> Result<(), String> {
+    let found = wait_for(tab, key).await?;
+    if is_xpath(&found) {
+        return Err(format!(
+            "{} 的候选 {} 不是 CSS 选择器，无法上传文件",
+            key, found
+        ));
+    }
+    tab.set_files(&found, files)
+}
+
+/// 在 XPath 匹配的第一个元素上执行表达式，`node` 是该元素
+fn eval_on_xpath(tab: &dyn Driver, xpath: &str, expression: &str) -> Option<serde_json::Value> {
+    let script = format!(
+        "(() => {{ const node = document.evaluate({}, document, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue; return node ? {} : null; }})()",
+        serde_json::json!(xpath),
+        expression
+    );
+    tab.evaluate(&script, false).ok().flatten()
+}
+
+/// 在名称对应的第一个能找到的元素上执行表达式，`node` 是该元素，不等待
+pub fn eval_on(tab: &dyn Driver, key: &str, expression: &str) -> Option<serde_json::Value> {
+    let found = find(tab, key)?;
+    if is_xpath(&found) {
+        return eval_on_xpath(tab, &found, expression);
+    }
+    let script = format!(
+        "(() => {{ const node = document.querySelector({}); return node ? {} : null; }})()",
+        serde_json::json!(found),
+        expression
+    );
+    tab.evaluate(&script, false).ok().flatten()
+}
+
+pub async fn inner_text(tab: &dyn Driver, key: &str) -> Result<String, String> {
+    let found = wait_for(tab, key).await?;
+    if is_xpath(&found) {
+        return eval_on_xpath(tab, &found, "node.innerText")
+            .and_then(|v| v.as_str().map(str::to_string))
+            .ok_or_else(|| format!("Read {} failed", key));
+    }
+    tab.inner_text(&found)
+}
+
+pub async fn attribute(tab: &dyn Driver, key: &str, name: &str) -> Result<Option<String>, String> {
+    let found = wait_for(tab, key).await?;
+    if is_xpath(&found) {
+        let expression = format!("node.getAttribute({})", serde_json::json!(name));
+        return Ok(
+            eval_on_xpath(tab, &found, &expression).and_then(|v| v.as_str().map(str::to_string))
+        );
+    }
+    tab.attribute(&found, name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectorStatus {
+    /// 第一个候选可用
+    Ok,
+    /// 只有后备候选可用，需要尽快更新
+    Fallback,
+    /// 所有候选都找不到
+    Broken,
+    /// 没有打开对应页面，未检查
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorHealth {
+    pub key: String,
+    pub status: SelectorStatus,
+    /// 页面上找到的候选
+    pub matched: Option<String>,
+    pub message: Option<String>,
+}
+
+/// 根据每个候选匹配到的元素数量判断状态，返回状态和可用候选的序号
+pub fn classify(counts: &[usize]) -> (SelectorStatus, Option<usize>) {
+    match counts.iter().position(|count| *count > 0) {
+        Some(0) => (SelectorStatus::Ok, Some(0)),
+        Some(index) => (SelectorStatus::Fallback, Some(index)),
+        None => (SelectorStatus::Broken, None),
+    }
+}
+
+fn check_page(tab: &dyn Driver, registry: &SelectorRegistry, page: &str) -> Vec<SelectorHealth> {
+    registry
+        .selectors
+        .iter()
+        .filter(|(_, entry)| entry.page.as_deref() == Some(page))
+        .map(|(key, entry)| {
+            let counts: Vec<usize> = entry
+                .candidates
+                .iter()
+                .map(|c| match_count(tab, c))
+                .collect();
+            let (status, index) = classify(&counts);
+            SelectorHealth {
+                key: key.clone(),
+                status,
+                matched: index.map(|i| entry.candidates[i].clone()),
+                message: None,
+            }
+        })
+        .collect()
+}
+
+fn skip_page(registry: &SelectorRegistry, page: Option<&str>, reason: &str) -> Vec<SelectorHealth> {
+    registry
+        .selectors
+        .iter()
+        .filter(|(_, entry)| entry.page.as_deref() == page)
+        .map(|(key, _)| SelectorHealth {
+            key: key.clone(),
+            status: SelectorStatus::Skipped,
+            matched: None,
+            message: Some(reason.to_string()),
+        })
+        .collect()
+}
+
+/// 在真实页面上逐个检查选择器
+///
+/// 提供 `sample_image` 时会上传到发布页以检查编辑页的元素，不会发布。
+#[tauri::command]
+pub async fn selector_health_check(
+    phone: String,
+    sample_image: Option<String>,
+) -> Result<Vec<SelectorHealth>, String> {
+    trace::scope(
+        RunKind::HealthCheck,
+        &phone,
+        check_selectors(&phone, sample_image),
+    )
+    .await
+}
+
+async fn check_selectors(
+    phone: &str,
+    sample_image: Option<String>,
+) -> Result<Vec<SelectorHealth>, String> {
+    let registry = active();
+    let session = super::session::acquire(phone).await?;
+    let tab = session.new_tab().await?;
+    trace::attach(&tab);
+    let tab = tab.as_ref();
+    let mut report = Vec::new();
+
+    trace::step("login_page");
+    tab.navigate(&site::creator_url("/login"))?;
+    wait::settle(tab).await;
+    if is_login_page(&tab.current_url()) {
+        report.extend(check_page(tab, &registry, "login"));
+    } else {
+        report.extend(skip_page(
+            &registry,
+            Some("login"),
+            "账号已登录，登录页会自动跳转",
+        ));
+    }
+
+    trace::step("publish_page");
+    tab.navigate(&site::creator_url(
+        "/publish/publish?from=homepage&target=image",
+    ))?;
+    wait::settle(tab).await;
+    if is_login_page(&tab.current_url()) {
+        report.extend(skip_page(&registry, Some("publish"), "账号未登录"));
+        report.extend(skip_page(&registry, Some("editor"), "账号未登录"));
+    } else {
+        report.extend(check_page(tab, &registry, "publish"));
+        match sample_image {
+            Some(image) => {
+                trace::step("editor_page");
+                set_files(tab, "publish.upload_input", &[&image]).await?;
+                // 编辑页出现后等请求结束，让按钮等元素渲染完
+                wait::for_selector(tab, "publish.editor", Step::CoverUpload).await?;
+                wait::settle(tab).await;
+                report.extend(check_page(tab, &registry, "editor"));
+            }
+            None => report.extend(skip_page(
+                &registry,
+                Some("editor"),
+                "需要提供示例图片才能进入编辑页",
+            )),
+        }
+    }
+    report.extend(skip_page(&registry, None, "只在发布过程中出现"));
+
+    for health in report
+        .iter()
+        .filter(|h| matches!(h.status, SelectorStatus::Broken | SelectorStatus::Fallback))
+    {
+        log::info!("Selector {} is {:?}", health.key, health.status);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_selectors() -> Result<SelectorSettings, String> {
+    Ok(SelectorSettings {
+        registry: active(),
+        overrides: load_overrides().await?,
+        warnings: LOAD_WARNINGS.read().unwrap().clone(),
+    })
+}
+
+/// 重新读取选择器文件和覆盖项
+#[tauri::command]
+pub async fn reload_selectors() -> Result<LoadedSelectors, String> {
+    load().await
+}
+
+/// 覆盖单个选择器的候选，传入空列表时取消覆盖
+#[tauri::command]
+pub async fn set_selector_override(
+    key: String,
+    candidates: Vec<String>,
+) -> Result<LoadedSelectors, String> {
+    let candidates: Vec<String> = candidates
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    if !active().selectors.contains_key(&key) {
+        return Err(format!("未知的选择器: {}", key));
+    }
+
+    let mut overrides = load_overrides().await?;
+    if candidates.is_empty() {
+        overrides.remove(&key);
+    } else {
+        overrides.insert(key, candidates);
+    }
+    let pool = crate::storage::pool().await?;
+    ConfigRepo::new(pool)
+        .set(
+            OVERRIDES_KEY,
+            &serde_json::to_string(&overrides).map_err(|e| e.to_string())?,
+        )
+        .await?;
+    load().await
+}
+
+/// 导入新版选择器文件，保存到数据目录后立即生效
+#[tauri::command]
+pub async fn import_selector_registry(content: String) -> Result<LoadedSelectors, String> {
+    let registry = parse_registry(&content)?;
+    let current = builtin().version;
+    if registry.version < current {
+        return Err(format!(
+            "选择器文件版本 {} 低于内置版本 {}",
+            registry.version, current
+        ));
+    }
+    std::fs::write(registry_path(), content).map_err(|e| e.to_string())?;
+    load().await
+}
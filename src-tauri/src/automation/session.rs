@@ -0,0 +1,236 @@
+use super::driver::Driver;
+use super::socks::SocksForwarder;
+use crate::model::{ProxyConfig, ProxyScheme};
+use crate::storage::get_browser_data_dir;
+use headless_chrome::browser::default_executable;
+use headless_chrome::{Browser, LaunchOptions, Tab};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedMutexGuard;
+
+/// 浏览器空闲多久后关闭
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// 等待同一账号上其他任务结束的最长时间
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// 检查空闲浏览器的间隔
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 一个账号的浏览器，同一时间只允许一个任务使用
+struct AccountSlot {
+    lock: Arc<tokio::sync::Mutex<()>>,
+    browser: Mutex<Option<Browser>>,
+    /// 浏览器启动时使用的代理，设置变化后需要重启
+    proxy: Mutex<Option<ProxyConfig>>,
+    /// 带认证的 SOCKS5 代理的本地转发，随浏览器一起关闭
+    forwarder: Mutex<Option<SocksForwarder>>,
+    last_used: Mutex<Instant>,
+}
+
+impl AccountSlot {
+    fn new() -> Self {
+        Self {
+            lock: Arc::new(tokio::sync::Mutex::new(())),
+            browser: Mutex::new(None),
+            proxy: Mutex::new(None),
+            forwarder: Mutex::new(None),
+            last_used: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn shutdown(&self) {
+        if let Some(browser) = self.browser.lock().unwrap().take() {
+            crate::util::utils::kill_browser_process(&browser);
+        }
+        self.forwarder.lock().unwrap().take();
+    }
+}
+
+lazy_static! {
+    static ref SLOTS: Mutex<HashMap<String, Arc<AccountSlot>>> = Mutex::new(HashMap::new());
+}
+
+/// 独占一个账号的浏览器，释放时关闭本次打开的标签页，浏览器留给下一个任务复用
+pub struct BrowserSession {
+    phone: String,
+    slot: Arc<AccountSlot>,
+    tabs: Mutex<Vec<Arc<Tab>>>,
+    _guard: OwnedMutexGuard<()>,
+}
+
+/// 获取账号的浏览器会话，同一账号上已有任务时排队等待
+pub async fn acquire(phone: &str) -> Result<BrowserSession, String> {
+    let slot = SLOTS
+        .lock()
+        .unwrap()
+        .entry(phone.to_string())
+        .or_insert_with(|| Arc::new(AccountSlot::new()))
+        .clone();
+    let guard = tokio::time::timeout(ACQUIRE_TIMEOUT, slot.lock.clone().lock_owned())
+        .await
+        .map_err(|_| format!("账号 {} 正在被其他任务使用，请稍后再试", phone))?;
+    Ok(BrowserSession {
+        phone: phone.to_string(),
+        slot,
+        tabs: Mutex::new(Vec::new()),
+        _guard: guard,
+    })
+}
+
+impl BrowserSession {
+    pub fn phone(&self) -> &str {
+        &self.phone
+    }
+
+    /// 返回账号的浏览器，没有启动、已经退出或代理有变化时重新启动
+    pub async fn browser(&self) -> Result<Browser, String> {
+        let proxy = super::proxy::account_proxy(&self.phone).await?;
+        let existing = self.slot.browser.lock().unwrap().clone();
+        if let Some(browser) = existing {
+            if *self.slot.proxy.lock().unwrap() != proxy {
+                log::info!("Proxy for {} changed, relaunching", self.phone);
+            } else if browser.get_version().is_ok() {
+                return Ok(browser);
+            } else {
+                log::info!("Browser for {} is gone, relaunching", self.phone);
+            }
+            self.slot.shutdown();
+        }
+
+        let headless = crate::ai::get_headless_mode().await;
+        // Chrome 不支持带认证的 SOCKS5，改为连接本地转发
+        let forwarder = match proxy
+            .as_ref()
+            .filter(|p| p.scheme == ProxyScheme::Socks5 && p.has_credentials())
+        {
+            Some(p) => Some(SocksForwarder::start(p.clone()).await?),
+            None => None,
+        };
+        let server = match &forwarder {
+            Some(forwarder) => Some(forwarder.server()),
+            None => proxy.as_ref().map(ProxyConfig::server),
+        };
+        let browser = launch(&self.phone, headless, server.as_deref())?;
+        *self.slot.forwarder.lock().unwrap() = forwarder;
+        *self.slot.browser.lock().unwrap() = Some(browser.clone());
+        *self.slot.proxy.lock().unwrap() = proxy;
+        Ok(browser)
+    }
+
+    pub async fn new_tab(&self) -> Result<Arc<dyn Driver>, String> {
+        let tab = self
+            .browser()
+            .await?
+            .new_tab()
+            .map_err(|e| format!("New tab failed: {}", e))?;
+        // HTTP 代理要求认证时由标签页应答，Chrome 的启动参数不能带用户名密码
+        let proxy = self.slot.proxy.lock().unwrap().clone();
+        let http_auth = proxy.filter(|p| p.scheme == ProxyScheme::Http && p.has_credentials());
+        if let Some(proxy) = http_auth {
+            tab.authenticate(proxy.username, proxy.password)
+                .and_then(|tab| tab.enable_fetch(None, Some(true)))
+                .map_err(|e| format!("Proxy auth failed: {}", e))?;
+        }
+        self.tabs.lock().unwrap().push(tab.clone());
+        Ok(tab)
+    }
+}
+
+impl Drop for BrowserSession {
+    fn drop(&mut self) {
+        for tab in self.tabs.lock().unwrap().drain(..) {
+            let _ = tab.close(false);
+        }
+        *self.slot.last_used.lock().unwrap() = Instant::now();
+    }
+}
+
+/// 用账号自己的数据目录和代理启动浏览器，`proxy_server` 不含用户名密码
+fn launch(phone: &str, headless: bool, proxy_server: Option<&str>) -> Result<Browser, String> {
+    log::info!("Starting browser for {}", phone);
+    // 启动前清理可能的 SingletonLock 锁文件，防止进程卡死
+    crate::storage::clear_browser_lock(phone);
+
+    let executable = default_executable().map_err(|e| format!("找不到 Chrome: {}", e))?;
+    let mut args = vec![
+        "--disable-extensions".to_string(),
+        "--disable-blink-features=AutomationControlled".to_string(),
+        "--no-first-run".to_string(),
+        "--no-default-browser-check".to_string(),
+    ];
+    if let Some(server) = proxy_server {
+        args.push(format!("--proxy-server={}", server));
+        // 不让 WebRTC 绕过代理暴露本机 IP
+        args.push("--force-webrtc-ip-handling-policy=disable_non_proxied_udp".to_string());
+    }
+    Browser::new(
+        LaunchOptions::default_builder()
+            .headless(headless)
+            .user_data_dir(Some(get_browser_data_dir(phone)))
+            .path(Some(executable))
+            .window_size(Some((1920, 1080)))
+            .enable_gpu(false)
+            // 浏览器会在任务之间保持打开，连接不能因为没有事件而断开
+            .idle_browser_timeout(IDLE_TIMEOUT + REAP_INTERVAL)
+            .args(args.iter().map(std::ffi::OsStr::new).collect())
+            .build()
+            .map_err(|e| format!("Browser build failed: {}", e))?,
+    )
+    .map_err(|e| format!("Browser init failed: {}", e))
+}
+
+/// 当前有浏览器会话记录的账号
+pub fn active_accounts() -> Vec<String> {
+    let mut phones: Vec<String> = SLOTS.lock().unwrap().keys().cloned().collect();
+    phones.sort();
+    phones
+}
+
+/// 关闭空闲超过 `timeout` 且没有任务在使用的浏览器，返回被关闭的账号
+pub fn evict_idle(timeout: Duration) -> Vec<String> {
+    let mut slots = SLOTS.lock().unwrap();
+    let idle: Vec<String> = slots
+        .iter()
+        // 只有这里持有引用时才说明没有任务在使用或等待
+        .filter(|(_, slot)| {
+            Arc::strong_count(slot) == 1 && slot.last_used.lock().unwrap().elapsed() >= timeout
+        })
+        .map(|(phone, _)| phone.clone())
+        .collect();
+    for phone in &idle {
+        if let Some(slot) = slots.remove(phone) {
+            slot.shutdown();
+        }
+    }
+    idle
+}
+
+/// 关闭账号的浏览器，用于退出登录前删除数据目录
+pub fn close_account(phone: &str) {
+    if let Some(slot) = SLOTS.lock().unwrap().remove(phone) {
+        slot.shutdown();
+    }
+}
+
+/// 在后台定期关闭空闲的浏览器和过期的登录会话
+pub fn start_reaper() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            tokio::time::sleep(REAP_INTERVAL).await;
+            crate::auth::evict_stale_logins(IDLE_TIMEOUT);
+            for phone in evict_idle(IDLE_TIMEOUT) {
+                log::info!("Closed idle browser for {}", phone);
+            }
+        }
+    });
+}
+
+/// 应用退出时关闭所有浏览器，不等待正在进行的任务
+pub fn shutdown_all() {
+    crate::auth::BROWSER_SESSIONS.lock().unwrap().clear();
+    let slots: Vec<Arc<AccountSlot>> = SLOTS.lock().unwrap().drain().map(|(_, s)| s).collect();
+    for slot in slots {
+        slot.shutdown();
+    }
+}
@@ -0,0 +1,29 @@
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+
+/// 创作者中心地址，可以用这个环境变量指向本地模拟站点
+pub const CREATOR_BASE_URL_ENV: &str = "XHS_CREATOR_BASE_URL";
+pub const DEFAULT_CREATOR_BASE_URL: &str = "https://creator.xiaohongshu.com";
+
+lazy_static! {
+    static ref CREATOR_BASE_URL: RwLock<String> = RwLock::new(
+        std::env::var(CREATOR_BASE_URL_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_CREATOR_BASE_URL.to_string())
+    );
+}
+
+pub fn creator_base_url() -> String {
+    CREATOR_BASE_URL.read().unwrap().clone()
+}
+
+/// 修改创作者中心地址，测试时指向本地模拟站点
+pub fn set_creator_base_url(url: &str) {
+    *CREATOR_BASE_URL.write().unwrap() = url.trim_end_matches('/').to_string();
+}
+
+/// 创作者中心页面的完整地址，`path` 以 `/` 开头
+pub fn creator_url(path: &str) -> String {
+    format!("{}{}", creator_base_url(), path)
+}
@@ -0,0 +1,194 @@
+use crate::model::ProxyConfig;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+const VERSION: u8 = 5;
+const NO_AUTH: u8 = 0;
+const USER_PASS: u8 = 2;
+const NO_ACCEPTABLE: u8 = 0xff;
+const CMD_CONNECT: u8 = 1;
+/// 返回给浏览器的「不支持的命令」
+const REPLY_UNSUPPORTED: [u8; 10] = [VERSION, 7, 0, 1, 0, 0, 0, 0, 0, 0];
+/// 返回给浏览器的「代理服务器故障」
+const REPLY_FAILURE: [u8; 10] = [VERSION, 1, 0, 1, 0, 0, 0, 0, 0, 0];
+
+/// 本地 SOCKS5 转发，释放时停止监听
+///
+/// Chrome 的 `--proxy-server` 不支持 SOCKS5 用户名密码认证。浏览器改为连接本机上
+/// 无需认证的端口，这里再用账号的用户名密码连接上游代理，之后原样转发数据。
+pub struct SocksForwarder {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl SocksForwarder {
+    /// 在 127.0.0.1 的随机端口上开始转发到 `upstream`，`upstream.password` 需要是明文
+    pub async fn start(upstream: ProxyConfig) -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("启动本地代理转发失败: {}", e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let task = tokio::spawn(async move {
+            while let Ok((client, _)) = listener.accept().await {
+                let upstream = upstream.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = forward(client, &upstream).await {
+                        log::warn!("SOCKS5 forward failed: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(Self { addr, task })
+    }
+
+    /// 给浏览器使用的代理地址
+    pub fn server(&self) -> String {
+        format!("socks5://{}", self.addr)
+    }
+}
+
+impl Drop for SocksForwarder {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn forward(mut client: TcpStream, upstream: &ProxyConfig) -> Result<(), String> {
+    // 浏览器的握手：版本、方法数、方法列表，这里只接受无认证
+    let mut head = [0u8; 2];
+    client
+        .read_exact(&mut head)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut methods = vec![0u8; head[1] as usize];
+    client
+        .read_exact(&mut methods)
+        .await
+        .map_err(|e| e.to_string())?;
+    if head[0] != VERSION || !methods.contains(&NO_AUTH) {
+        let _ = client.write_all(&[VERSION, NO_ACCEPTABLE]).await;
+        return Err("浏览器的 SOCKS5 握手不正确".to_string());
+    }
+    client
+        .write_all(&[VERSION, NO_AUTH])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 连接请求原样转给上游
+    let mut request = [0u8; 4];
+    client
+        .read_exact(&mut request)
+        .await
+        .map_err(|e| e.to_string())?;
+    if request[1] != CMD_CONNECT {
+        let _ = client.write_all(&REPLY_UNSUPPORTED).await;
+        return Err(format!("不支持的 SOCKS5 命令 {}", request[1]));
+    }
+    let target = read_address(&mut client, request[3]).await?;
+
+    let mut server = match connect_upstream(upstream).await {
+        Ok(server) => server,
+        Err(e) => {
+            let _ = client.write_all(&REPLY_FAILURE).await;
+            return Err(e);
+        }
+    };
+    server
+        .write_all(&request)
+        .await
+        .map_err(|e| e.to_string())?;
+    server.write_all(&target).await.map_err(|e| e.to_string())?;
+
+    let mut reply = [0u8; 4];
+    server
+        .read_exact(&mut reply)
+        .await
+        .map_err(|e| e.to_string())?;
+    let bound = read_address(&mut server, reply[3]).await?;
+    client.write_all(&reply).await.map_err(|e| e.to_string())?;
+    client.write_all(&bound).await.map_err(|e| e.to_string())?;
+    if reply[1] != 0 {
+        return Err(format!("上游代理拒绝连接，错误码 {}", reply[1]));
+    }
+
+    tokio::io::copy_bidirectional(&mut client, &mut server)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 连接上游代理并用用户名密码完成认证
+async fn connect_upstream(upstream: &ProxyConfig) -> Result<TcpStream, String> {
+    let host = upstream
+        .host
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let mut server = TcpStream::connect((host, upstream.port))
+        .await
+        .map_err(|e| format!("连接代理 {} 失败: {}", upstream.server(), e))?;
+
+    server
+        .write_all(&[VERSION, 1, USER_PASS])
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut choice = [0u8; 2];
+    server
+        .read_exact(&mut choice)
+        .await
+        .map_err(|e| e.to_string())?;
+    if choice != [VERSION, USER_PASS] {
+        return Err("上游代理不接受用户名密码认证".to_string());
+    }
+
+    let username = upstream.username.as_deref().unwrap_or_default().as_bytes();
+    let password = upstream.password.as_deref().unwrap_or_default().as_bytes();
+    if username.len() > 255 || password.len() > 255 {
+        return Err("SOCKS5 用户名和密码不能超过 255 字节".to_string());
+    }
+    let mut auth = vec![1, username.len() as u8];
+    auth.extend_from_slice(username);
+    auth.push(password.len() as u8);
+    auth.extend_from_slice(password);
+    server.write_all(&auth).await.map_err(|e| e.to_string())?;
+    let mut status = [0u8; 2];
+    server
+        .read_exact(&mut status)
+        .await
+        .map_err(|e| e.to_string())?;
+    if status[1] != 0 {
+        return Err("代理用户名或密码错误".to_string());
+    }
+    Ok(server)
+}
+
+/// 按地址类型读出地址和端口的原始字节
+async fn read_address(stream: &mut TcpStream, kind: u8) -> Result<Vec<u8>, String> {
+    let len = match kind {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0u8; 1];
+            stream
+                .read_exact(&mut len)
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut address = vec![0u8; 1 + len[0] as usize + 2];
+            address[0] = len[0];
+            stream
+                .read_exact(&mut address[1..])
+                .await
+                .map_err(|e| e.to_string())?;
+            return Ok(address);
+        }
+        _ => return Err(format!("未知的 SOCKS5 地址类型 {}", kind)),
+    };
+    let mut address = vec![0u8; len + 2];
+    stream
+        .read_exact(&mut address)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(address)
+}
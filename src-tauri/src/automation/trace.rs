@@ -0,0 +1,497 @@
+use super::driver::Driver;
+use crate::storage::repository::ConfigRepo;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 保存在 config 表中的保留策略
+pub const RETENTION_KEY: &str = "trace_retention";
+/// 每次运行目录中的步骤记录
+pub const TRACE_FILE: &str = "trace.json";
+const CONSOLE_FILE: &str = "console.json";
+const FAILURE_HTML_FILE: &str = "failure.html";
+/// 一次运行最多保留的控制台输出条数
+const CONSOLE_LIMIT: usize = 2000;
+
+/// 记录页面控制台输出，导航后由新文档脚本重新安装
+const CONSOLE_HOOK: &str = r#"(() => {
+    if (window.__helperConsole) return;
+    const logs = window.__helperConsole = [];
+    const push = (level, args) => {
+        try {
+            const text = args.map(a => {
+                if (typeof a === 'string') return a;
+                try { return JSON.stringify(a); } catch (e) { return String(a); }
+            }).join(' ');
+            logs.push({ level, time: Date.now(), url: location.href, text });
+            if (logs.length > 500) logs.shift();
+        } catch (e) {}
+    };
+    for (const level of ['log', 'info', 'warn', 'error', 'debug']) {
+        const original = console[level];
+        console[level] = function (...args) {
+            push(level, args);
+            return original.apply(this, args);
+        };
+    }
+    window.addEventListener('error', e => push('error', [e.message]));
+    window.addEventListener('unhandledrejection', e => push('error', [String(e.reason)]));
+})()"#;
+
+/// 自动化运行的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+    Publish,
+    Login,
+    Validate,
+    Analytics,
+    HealthCheck,
+}
+
+impl RunKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunKind::Publish => "publish",
+            RunKind::Login => "login",
+            RunKind::Validate => "validate",
+            RunKind::Analytics => "analytics",
+            RunKind::HealthCheck => "health_check",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// 运行中的一个步骤，`offset_ms` 为距运行开始的时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub name: String,
+    pub offset_ms: u64,
+    pub duration_ms: Option<u64>,
+    /// 步骤中保存的截图等文件，相对于运行目录
+    #[serde(default)]
+    pub files: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// 写入运行目录 trace.json 的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunTrace {
+    pub id: String,
+    pub kind: RunKind,
+    pub phone: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub status: RunStatus,
+    pub error: Option<String>,
+    pub steps: Vec<TraceStep>,
+}
+
+/// 一次自动化运行，步骤和文件都写到自己的目录中
+pub struct Run {
+    dir: PathBuf,
+    started: Instant,
+    trace: Mutex<RunTrace>,
+    tab: Mutex<Option<Arc<dyn Driver>>>,
+    console: Mutex<Vec<serde_json::Value>>,
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<Run>;
+}
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 生成按时间排序的运行 id，例如 `20260101-093000-123-publish-1`
+fn new_run_id(kind: RunKind) -> String {
+    format!(
+        "{}-{}-{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f"),
+        kind.as_str(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+impl Run {
+    fn create(kind: RunKind, phone: &str) -> Result<Self, String> {
+        let id = new_run_id(kind);
+        let dir = crate::storage::get_runs_dir().join(&id);
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let run = Self {
+            dir,
+            started: Instant::now(),
+            trace: Mutex::new(RunTrace {
+                id,
+                kind,
+                phone: phone.to_string(),
+                started_at: chrono::Local::now().to_rfc3339(),
+                finished_at: None,
+                duration_ms: None,
+                status: RunStatus::Running,
+                error: None,
+                steps: Vec::new(),
+            }),
+            tab: Mutex::new(None),
+            console: Mutex::new(Vec::new()),
+        };
+        run.save();
+        Ok(run)
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn save(&self) {
+        let trace = self.trace.lock().unwrap();
+        let result = serde_json::to_vec_pretty(&*trace)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                std::fs::write(self.dir.join(TRACE_FILE), data).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to write trace {}: {}", trace.id, e);
+        }
+    }
+
+    fn close_step(trace: &mut RunTrace, now_ms: u64) {
+        if let Some(last) = trace.steps.last_mut() {
+            if last.duration_ms.is_none() {
+                last.duration_ms = Some(now_ms.saturating_sub(last.offset_ms));
+            }
+        }
+    }
+
+    fn step(&self, name: &str) {
+        self.drain_console();
+        let now = self.elapsed_ms();
+        {
+            let mut trace = self.trace.lock().unwrap();
+            Self::close_step(&mut trace, now);
+            trace.steps.push(TraceStep {
+                name: name.to_string(),
+                offset_ms: now,
+                duration_ms: None,
+                files: Vec::new(),
+                error: None,
+            });
+        }
+        self.save();
+    }
+
+    /// 运行目录中下一个文件的路径，文件名带序号避免覆盖
+    fn file_path(&self, name: &str) -> PathBuf {
+        let trace = self.trace.lock().unwrap();
+        let count: usize = trace.steps.iter().map(|s| s.files.len()).sum();
+        self.dir.join(format!("{:02}_{}", count + 1, name))
+    }
+
+    fn add_file(&self, path: &Path) {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            return;
+        };
+        {
+            let mut trace = self.trace.lock().unwrap();
+            if trace.steps.is_empty() {
+                trace.steps.push(TraceStep {
+                    name: "start".to_string(),
+                    offset_ms: 0,
+                    duration_ms: None,
+                    files: Vec::new(),
+                    error: None,
+                });
+            }
+            trace.steps.last_mut().unwrap().files.push(name);
+        }
+        self.save();
+    }
+
+    fn attach(&self, tab: Arc<dyn Driver>) {
+        if let Err(e) = tab.add_init_script(CONSOLE_HOOK) {
+            log::warn!("Failed to install console hook: {}", e);
+        }
+        let _ = tab.evaluate(CONSOLE_HOOK, false);
+        *self.tab.lock().unwrap() = Some(tab);
+    }
+
+    /// 取出页面上记录的控制台输出，页面跳转前取出的部分不会丢失
+    fn drain_console(&self) {
+        let Some(tab) = self.tab.lock().unwrap().clone() else {
+            return;
+        };
+        let logs: Vec<serde_json::Value> = tab
+            .evaluate(
+                "JSON.stringify(window.__helperConsole ? window.__helperConsole.splice(0) : [])",
+                false,
+            )
+            .ok()
+            .flatten()
+            .and_then(|v| v.as_str().and_then(|s| serde_json::from_str(s).ok()))
+            .unwrap_or_default();
+        let mut console = self.console.lock().unwrap();
+        console.extend(logs);
+        let overflow = console.len().saturating_sub(CONSOLE_LIMIT);
+        console.drain(..overflow);
+    }
+
+    fn finish(&self, error: Option<String>) {
+        self.drain_console();
+        let tab = self.tab.lock().unwrap().take();
+        if let (Some(_), Some(tab)) = (&error, &tab) {
+            let html = tab
+                .evaluate("document.documentElement.outerHTML", false)
+                .ok()
+                .flatten()
+                .and_then(|v| v.as_str().map(str::to_string));
+            if let Some(html) = html {
+                let path = self.dir.join(FAILURE_HTML_FILE);
+                if std::fs::write(&path, html).is_ok() {
+                    self.add_file(&path);
+                }
+            }
+        }
+
+        let console = std::mem::take(&mut *self.console.lock().unwrap());
+        if !console.is_empty() {
+            let path = self.dir.join(CONSOLE_FILE);
+            let data = serde_json::to_vec_pretty(&console).unwrap_or_default();
+            if std::fs::write(&path, data).is_ok() {
+                self.add_file(&path);
+            }
+        }
+
+        let now = self.elapsed_ms();
+        {
+            let mut trace = self.trace.lock().unwrap();
+            Self::close_step(&mut trace, now);
+            if let (Some(error), Some(last)) = (&error, trace.steps.last_mut()) {
+                last.error = Some(error.clone());
+            }
+            trace.finished_at = Some(chrono::Local::now().to_rfc3339());
+            trace.duration_ms = Some(now);
+            trace.status = if error.is_some() {
+                RunStatus::Failed
+            } else {
+                RunStatus::Succeeded
+            };
+            trace.error = error;
+        }
+        self.save();
+    }
+}
+
+/// 在一次记录中执行 `fut`，结束时按结果写入成功或失败
+///
+/// 记录目录创建失败时照常执行，只是不保存记录。
+pub async fn scope<T, E: Display>(
+    kind: RunKind,
+    phone: &str,
+    fut: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let retention = load_retention().await.unwrap_or_default();
+    if let Err(e) = prune(&crate::storage::get_runs_dir(), &retention) {
+        log::warn!("Failed to prune automation runs: {}", e);
+    }
+    let run = match Run::create(kind, phone) {
+        Ok(run) => Arc::new(run),
+        Err(e) => {
+            log::warn!("Failed to create automation run: {}", e);
+            return fut.await;
+        }
+    };
+    let result = CURRENT.scope(run.clone(), fut).await;
+    run.finish(result.as_ref().err().map(|e| e.to_string()));
+    result
+}
+
+fn current() -> Option<Arc<Run>> {
+    CURRENT.try_with(|run| run.clone()).ok()
+}
+
+/// 当前运行的 id，不在记录中时为 None
+pub fn current_id() -> Option<String> {
+    current().map(|run| run.trace.lock().unwrap().id.clone())
+}
+
+/// 开始一个新步骤，上一个步骤在此结束
+pub fn step(name: &str) {
+    if let Some(run) = current() {
+        run.step(name);
+    }
+}
+
+/// 记录使用的页面，用于读取控制台输出和失败时的 HTML
+pub fn attach(tab: &Arc<dyn Driver>) {
+    if let Some(run) = current() {
+        run.attach(tab.clone());
+    }
+}
+
+/// 截图等文件的保存路径，不在记录中时返回 None
+pub fn file_path(name: &str) -> Option<PathBuf> {
+    current().map(|run| run.file_path(name))
+}
+
+/// 把已保存的文件记到当前步骤
+pub fn add_file(path: &Path) {
+    if let Some(run) = current() {
+        run.add_file(path);
+    }
+}
+
+/// 运行记录的保留策略，超出任意一项的旧记录会被删除
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub max_runs: usize,
+    pub max_days: u64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_runs: 200,
+            max_days: 14,
+        }
+    }
+}
+
+async fn load_retention() -> Result<RetentionPolicy, String> {
+    let pool = crate::storage::pool().await?;
+    Ok(ConfigRepo::new(pool)
+        .get(RETENTION_KEY)
+        .await?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+/// 按保留策略删除旧的运行目录，返回删除的数量
+///
+/// 运行 id 以时间开头，按名称排序即按时间排序。
+pub fn prune(root: &Path, policy: &RetentionPolicy) -> Result<usize, String> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(root)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join(TRACE_FILE).exists())
+        .collect();
+    dirs.sort();
+    dirs.reverse();
+
+    let max_age = Duration::from_secs(policy.max_days * 24 * 60 * 60);
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for (index, dir) in dirs.iter().enumerate() {
+        let expired = std::fs::metadata(dir)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+        if (index >= policy.max_runs || expired) && std::fs::remove_dir_all(dir).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 读取运行目录中的记录
+pub fn read_run(root: &Path, id: &str) -> Result<RunTrace, String> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("无效的运行 id: {}", id));
+    }
+    let data = std::fs::read(root.join(id).join(TRACE_FILE))
+        .map_err(|_| format!("运行记录不存在: {}", id))?;
+    serde_json::from_slice(&data).map_err(|e| e.to_string())
+}
+
+/// 按时间倒序列出运行记录
+pub fn list_runs(root: &Path) -> Vec<RunTrace> {
+    let mut ids: Vec<String> = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids.reverse();
+    ids.iter()
+        .filter_map(|id| read_run(root, id).ok())
+        .collect()
+}
+
+/// 列出最近的运行记录，可按类型、账号和状态筛选
+#[tauri::command]
+pub async fn list_automation_runs(
+    kind: Option<RunKind>,
+    phone: Option<String>,
+    status: Option<RunStatus>,
+    limit: Option<usize>,
+) -> Result<Vec<RunTrace>, String> {
+    Ok(list_runs(&crate::storage::get_runs_dir())
+        .into_iter()
+        .filter(|run| kind.is_none_or(|k| run.kind == k))
+        .filter(|run| phone.as_ref().is_none_or(|p| &run.phone == p))
+        .filter(|run| status.is_none_or(|s| run.status == s))
+        .take(limit.unwrap_or(50))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_automation_run(id: String) -> Result<RunTrace, String> {
+    read_run(&crate::storage::get_runs_dir(), &id)
+}
+
+/// 在文件管理器中打开运行目录
+#[tauri::command]
+pub async fn open_automation_run(id: String) -> Result<(), String> {
+    let root = crate::storage::get_runs_dir();
+    let run = read_run(&root, &id)?;
+    tauri_plugin_opener::open_path(
+        root.join(run.id).to_string_lossy().to_string(),
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_trace_retention() -> Result<RetentionPolicy, String> {
+    load_retention().await
+}
+
+#[tauri::command]
+pub async fn set_trace_retention(policy: RetentionPolicy) -> Result<usize, String> {
+    if policy.max_runs == 0 || policy.max_days == 0 {
+        return Err("保留数量和天数都需要大于 0".to_string());
+    }
+    let pool = crate::storage::pool().await?;
+    ConfigRepo::new(pool)
+        .set(
+            RETENTION_KEY,
+            &serde_json::to_string(&policy).map_err(|e| e.to_string())?,
+        )
+        .await?;
+    prune(&crate::storage::get_runs_dir(), &policy)
+}
+
+/// 立即按当前策略清理旧记录，返回删除的数量
+#[tauri::command]
+pub async fn prune_automation_runs() -> Result<usize, String> {
+    let policy = load_retention().await?;
+    prune(&crate::storage::get_runs_dir(), &policy)
+}
@@ -0,0 +1,285 @@
+use crate::model::{MediaType, WordHit};
+use crate::storage::repository::PostEdit;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 标题最多 20 个字，emoji 按 2 个字计算
+pub const MAX_TITLE_LENGTH: usize = 20;
+pub const MAX_CONTENT_LENGTH: usize = 1000;
+pub const MAX_IMAGES: usize = 18;
+pub const MAX_TOPICS: usize = 10;
+/// 单张图片大小上限
+pub const MAX_IMAGE_BYTES: u64 = 32 * 1024 * 1024;
+pub const MAX_VIDEO_BYTES: u64 = 20 * 1024 * 1024 * 1024;
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov"];
+/// 宽高比超出这个范围无法上传
+const ASPECT_LIMITS: (f64, f64) = (1.0 / 3.0, 3.0);
+/// 宽高比超出这个范围会被平台裁剪
+const ASPECT_RECOMMENDED: (f64, f64) = (3.0 / 4.0, 4.0 / 3.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueLevel {
+    /// 发布一定会失败，必须修改
+    Error,
+    /// 可以发布，但效果可能不理想
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ValidationIssue {
+    pub level: IssueLevel,
+    /// 出问题的字段，例如 title、images[2]
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ValidationIssue {
+            level: IssueLevel::Error,
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ValidationIssue {
+            level: IssueLevel::Warning,
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// 敏感词命中作为警告加入报告
+    pub fn add_word_hits(&mut self, hits: &[WordHit]) {
+        for hit in hits {
+            let suggestion = hit
+                .replacement
+                .as_ref()
+                .map(|r| format!("，建议改为「{}」", r))
+                .unwrap_or_default();
+            self.warning(
+                hit.field.clone(),
+                format!(
+                    "第 {} 个字起包含{}「{}」{}",
+                    hit.start + 1,
+                    hit.category,
+                    hit.word,
+                    suggestion
+                ),
+            );
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.level == IssueLevel::Error)
+    }
+
+    /// 所有错误合并成一句话，用作发布失败原因
+    pub fn error_summary(&self) -> String {
+        self.issues
+            .iter()
+            .filter(|i| i.level == IssueLevel::Error)
+            .map(|i| format!("{}: {}", i.field, i.message))
+            .collect::<Vec<_>>()
+            .join("；")
+    }
+}
+
+/// 按平台规则计算标题长度：emoji 计 2，组合用的零宽连接符、变体选择符和肤色修饰不计
+pub fn title_length(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x200D | 0xFE0E | 0xFE0F | 0x1F3FB..=0x1F3FF => 0,
+            0x2600..=0x27BF | 0x1F000..=0x1FAFF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 从文件头读取 PNG、JPEG、WebP 图片的宽高
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| Some(u16::from_be_bytes([*bytes.get(i)?, *bytes.get(i + 1)?]) as u32);
+    let le16 = |i: usize| Some(u16::from_le_bytes([*bytes.get(i)?, *bytes.get(i + 1)?]) as u32);
+    let le24 = |i: usize| {
+        Some(u32::from_le_bytes([*bytes.get(i)?, *bytes.get(i + 1)?, *bytes.get(i + 2)?, 0]))
+    };
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let w = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+        let h = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+        return Some((w, h));
+    }
+
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3FFF, le16(28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        };
+    }
+
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        // 依次跳过各个段，直到遇到带尺寸的 SOF 段
+        let mut i = 2;
+        while i + 9 < bytes.len() {
+            if bytes[i] != 0xFF {
+                return None;
+            }
+            let marker = bytes[i + 1];
+            let len = be16(i + 2)? as usize;
+            if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                return Some((be16(i + 7)?, be16(i + 5)?));
+            }
+            i += 2 + len;
+        }
+    }
+    None
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn check_image(report: &mut ValidationReport, field: &str, path: &str) {
+    let ext = extension(path);
+    if !IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        report.error(
+            field,
+            format!("不支持的图片格式 {}，支持 {}", ext, IMAGE_EXTENSIONS.join("/")),
+        );
+        return;
+    }
+    let size = match std::fs::metadata(path) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => {
+            report.error(field, format!("图片不存在: {}", path));
+            return;
+        }
+    };
+    if size > MAX_IMAGE_BYTES {
+        report.error(
+            field,
+            format!("图片超过 {}MB", MAX_IMAGE_BYTES / 1024 / 1024),
+        );
+    }
+
+    // 尺寸信息在文件开头，读前 64KB 足够
+    let head = std::fs::File::open(path).and_then(|f| {
+        use std::io::Read;
+        let mut buf = Vec::new();
+        f.take(64 * 1024).read_to_end(&mut buf).map(|_| buf)
+    });
+    let Some((w, h)) = head.ok().and_then(|b| image_dimensions(&b)) else {
+        report.warning(field, "无法读取图片尺寸");
+        return;
+    };
+    if w == 0 || h == 0 {
+        report.error(field, "图片尺寸无效");
+        return;
+    }
+    let ratio = w as f64 / h as f64;
+    if ratio < ASPECT_LIMITS.0 || ratio > ASPECT_LIMITS.1 {
+        report.error(field, format!("图片宽高比 {}x{} 超出平台限制", w, h));
+    } else if ratio < ASPECT_RECOMMENDED.0 - 0.01 || ratio > ASPECT_RECOMMENDED.1 + 0.01 {
+        report.warning(
+            field,
+            format!("图片宽高比 {}x{} 不在 3:4 到 4:3 之间，会被裁剪", w, h),
+        );
+    }
+}
+
+/// 发布前按平台规则检查笔记，不需要启动浏览器
+pub fn validate_post(post: &PostEdit) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let title_len = title_length(post.title.trim());
+    if title_len == 0 {
+        report.error("title", "标题不能为空");
+    } else if title_len > MAX_TITLE_LENGTH {
+        report.error(
+            "title",
+            format!("标题 {} 字，超过 {} 字上限", title_len, MAX_TITLE_LENGTH),
+        );
+    }
+
+    let content_len = post.content.chars().count();
+    if content_len > MAX_CONTENT_LENGTH {
+        report.error(
+            "content",
+            format!("正文 {} 字，超过 {} 字上限", content_len, MAX_CONTENT_LENGTH),
+        );
+    } else if content_len == 0 {
+        report.warning("content", "正文为空");
+    }
+
+    if post.topics.len() > MAX_TOPICS {
+        report.warning(
+            "topics",
+            format!("话题超过 {} 个，多余的可能不会生效", MAX_TOPICS),
+        );
+    }
+
+    match post.media_type {
+        MediaType::Image => {
+            if post.images.is_empty() {
+                report.error("images", "至少需要一张图片");
+            } else if post.images.len() > MAX_IMAGES {
+                report.error("images", format!("图片最多 {} 张", MAX_IMAGES));
+            }
+            for (i, image) in post.images.iter().enumerate() {
+                check_image(&mut report, &format!("images[{}]", i), image);
+            }
+            if let Some(cover) = &post.cover_image {
+                if !post.images.contains(cover) {
+                    report.error("cover_image", "封面必须是笔记中的一张图片");
+                }
+            }
+        }
+        MediaType::Video => {
+            match post.video_path.as_deref() {
+                None => report.error("video_path", "视频笔记缺少视频文件"),
+                Some(path) => {
+                    let ext = extension(path);
+                    if !VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+                        report.error(
+                            "video_path",
+                            format!("不支持的视频格式 {}，支持 {}", ext, VIDEO_EXTENSIONS.join("/")),
+                        );
+                    }
+                    match std::fs::metadata(path) {
+                        Ok(meta) if meta.is_file() => {
+                            if meta.len() > MAX_VIDEO_BYTES {
+                                report.error("video_path", "视频超过 20GB");
+                            }
+                        }
+                        _ => report.error("video_path", format!("视频不存在: {}", path)),
+                    }
+                }
+            }
+            if let Some(cover) = &post.cover_image {
+                check_image(&mut report, "cover_image", cover);
+            }
+            if post.cover_time.is_some_and(|t| t < 0.0) {
+                report.error("cover_time", "封面时间不能为负数");
+            }
+        }
+    }
+
+    report
+}
@@ -0,0 +1,228 @@
+use super::driver::Driver;
+use super::outcome::{PublishErrorKind, PublishFailure};
+use super::selectors;
+use super::take_screenshot;
+use super::trace;
+use super::wait::{self, Step};
+use base64::Engine;
+use std::path::PathBuf;
+
+/// 发布页上视频上传区域的状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoUploadState {
+    /// 正在上传，附带页面显示的进度
+    Uploading(Option<u8>),
+    /// 上传完成，平台正在转码
+    Processing,
+    Done,
+    Failed(String),
+}
+
+/// 根据上传区域的文字判断视频状态
+pub fn parse_upload_state(text: &str) -> VideoUploadState {
+    let text = text.trim();
+    if text.contains("失败") || text.contains("不支持") || text.contains("重新上传") {
+        return VideoUploadState::Failed(text.to_string());
+    }
+    if text.contains("转码") || text.contains("处理中") || text.contains("检测中") {
+        return VideoUploadState::Processing;
+    }
+    if text.contains("上传成功") || text.contains("上传完成") {
+        return VideoUploadState::Done;
+    }
+    if text.contains("上传中") || text.contains('%') {
+        let percent = text
+            .split('%')
+            .next()
+            .and_then(|s| {
+                let digits: String = s
+                    .chars()
+                    .rev()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                digits.chars().rev().collect::<String>().parse::<u8>().ok()
+            })
+            .filter(|p| *p <= 100);
+        return VideoUploadState::Uploading(percent);
+    }
+    VideoUploadState::Uploading(None)
+}
+
+/// 在视频发布页上传视频文件
+pub async fn upload_video(tab: &dyn Driver, video_path: &str) -> Result<(), PublishFailure> {
+    if !std::path::Path::new(video_path).is_file() {
+        return Err(PublishFailure::new(
+            PublishErrorKind::UploadFailed,
+            format!("视频文件不存在: {}", video_path),
+        ));
+    }
+
+    println!("Waiting for video upload input...");
+    selectors::wait_for(tab, "publish.upload_input")
+        .await
+        .inspect_err(|_| {
+            take_screenshot(tab, "error_wait_video_input");
+        })?;
+
+    println!("Uploading video: {}", video_path);
+    selectors::set_files(tab, "publish.upload_input", &[video_path])
+        .await
+        .map_err(|e| {
+            PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                format!("Failed to set video file: {}", e),
+            )
+        })?;
+    Ok(())
+}
+
+/// 轮询上传区域，直到视频上传并转码完成
+pub async fn wait_for_video_ready(tab: &dyn Driver) -> Result<(), PublishFailure> {
+    let script = format!(
+        r#"Array.from(document.querySelectorAll({})).map(e => e.innerText.trim()).filter(Boolean).join('\n')"#,
+        serde_json::json!(selectors::css("video.upload_status"))
+    );
+    let mut last_state = None;
+
+    wait::until(Step::VideoProcess, "视频上传和转码完成", || {
+        let text = tab
+            .evaluate(&script, false)
+            .ok()
+            .flatten()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+
+        // 转码完成后上传区域会被预览替换，只剩下 video 元素
+        let state = if text.is_empty() && has_video_preview(tab) {
+            VideoUploadState::Done
+        } else {
+            parse_upload_state(&text)
+        };
+
+        if last_state.as_ref() != Some(&state) {
+            println!("Video upload state: {:?}", state);
+            last_state = Some(state.clone());
+        }
+        match state {
+            VideoUploadState::Done => Some(Ok(())),
+            VideoUploadState::Failed(message) => Some(Err(PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                message,
+            ))),
+            _ => None,
+        }
+    })
+    .await
+    .map_err(|e| {
+        PublishFailure::new(
+            PublishErrorKind::Timeout,
+            format!("{}，请检查网络或视频大小", e),
+        )
+    })?
+}
+
+fn has_video_preview(tab: &dyn Driver) -> bool {
+    tab.evaluate("!!document.querySelector('video')", false)
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// 从视频截取的封面图
+///
+/// 在运行记录中时保存到该次运行的目录，随记录一起清理；否则保存到调试目录，用完即删。
+pub struct CapturedFrame {
+    pub path: PathBuf,
+    temporary: bool,
+}
+
+impl Drop for CapturedFrame {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 截取预览视频在 `seconds` 处的画面，保存为 PNG
+pub fn capture_frame(tab: &dyn Driver, seconds: f64) -> Result<CapturedFrame, String> {
+    let script = format!(
+        r#"(async () => {{
+            const v = document.querySelector('video');
+            if (!v) return '';
+            if (v.readyState < 1) await new Promise(r => v.addEventListener('loadedmetadata', r, {{ once: true }}));
+            const target = Math.min({seconds}, Math.max((v.duration || 0) - 0.1, 0));
+            v.pause();
+            await new Promise(r => {{ v.addEventListener('seeked', r, {{ once: true }}); v.currentTime = target; }});
+            const c = document.createElement('canvas');
+            c.width = v.videoWidth;
+            c.height = v.videoHeight;
+            c.getContext('2d').drawImage(v, 0, 0);
+            return c.toDataURL('image/png');
+        }})()"#,
+        seconds = seconds.max(0.0)
+    );
+    let data_url = tab
+        .evaluate(&script, true)
+        .map_err(|e| format!("截取视频帧失败: {}", e))?
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let encoded = data_url
+        .strip_prefix("data:image/png;base64,")
+        .ok_or_else(|| "页面上没有可截取的视频预览".to_string())?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| e.to_string())?;
+
+    let filename = format!(
+        "cover_frame_{}.png",
+        chrono::Local::now().format("%Y%m%d_%H%M%S%3f")
+    );
+    let frame = match trace::file_path(&filename) {
+        Some(path) => CapturedFrame {
+            path,
+            temporary: false,
+        },
+        None => CapturedFrame {
+            path: crate::storage::get_debug_dir().join(&filename),
+            temporary: true,
+        },
+    };
+    std::fs::write(&frame.path, bytes).map_err(|e| e.to_string())?;
+    if !frame.temporary {
+        trace::add_file(&frame.path);
+    }
+    Ok(frame)
+}
+
+/// 打开封面设置弹窗，上传自定义封面图
+pub async fn set_video_cover(tab: &dyn Driver, cover_image: &str) -> Result<(), PublishFailure> {
+    println!("Setting video cover: {}", cover_image);
+    selectors::click(tab, "video.cover_button")
+        .await
+        .map_err(|e| format!("Click cover setting failed: {}", e))?;
+
+    selectors::wait_for(tab, "video.cover_input")
+        .await
+        .inspect_err(|_| {
+            take_screenshot(tab, "error_wait_cover_input");
+        })?;
+    selectors::set_files(tab, "video.cover_input", &[cover_image])
+        .await
+        .map_err(|e| {
+            PublishFailure::new(
+                PublishErrorKind::UploadFailed,
+                format!("Failed to set video cover: {}", e),
+            )
+        })?;
+    wait::settle(tab).await;
+
+    // 弹窗里的按钮在页面最后，点击最后一个匹配项
+    selectors::click_last(tab, "video.cover_confirm")
+        .await
+        .map_err(|e| format!("Click cover confirm failed: {}", e))?;
+    wait::settle(tab).await;
+    take_screenshot(tab, "video_cover_set");
+    Ok(())
+}
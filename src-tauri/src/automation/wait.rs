@@ -0,0 +1,294 @@
+use super::driver::Driver;
+use super::outcome::{PublishErrorKind, PublishFailure};
+use super::selectors;
+use crate::storage::repository::ConfigRepo;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// 保存在 config 表中的各步骤超时，值为 `{ 步骤: 秒数 }`
+pub const TIMEOUTS_KEY: &str = "step_timeouts";
+/// 检查条件的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+/// 请求数量保持不变多久算作网络空闲
+const NETWORK_QUIET: Duration = Duration::from_millis(800);
+/// 单个步骤允许设置的最长超时
+pub const MAX_TIMEOUT_SECS: u64 = 3600;
+
+/// 自动化流程中需要等待的步骤，每一步的超时可以单独设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    PageLoad,
+    Element,
+    Login,
+    CoverUpload,
+    ImageUpload,
+    VideoProcess,
+    Suggestions,
+    NetworkIdle,
+    PublishResult,
+    DraftSave,
+}
+
+impl Step {
+    pub const ALL: [Step; 10] = [
+        Step::PageLoad,
+        Step::Element,
+        Step::Login,
+        Step::CoverUpload,
+        Step::ImageUpload,
+        Step::VideoProcess,
+        Step::Suggestions,
+        Step::NetworkIdle,
+        Step::PublishResult,
+        Step::DraftSave,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Step::PageLoad => "page_load",
+            Step::Element => "element",
+            Step::Login => "login",
+            Step::CoverUpload => "cover_upload",
+            Step::ImageUpload => "image_upload",
+            Step::VideoProcess => "video_process",
+            Step::Suggestions => "suggestions",
+            Step::NetworkIdle => "network_idle",
+            Step::PublishResult => "publish_result",
+            Step::DraftSave => "draft_save",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Step> {
+        Step::ALL.into_iter().find(|step| step.as_str() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Step::PageLoad => "页面加载",
+            Step::Element => "等待页面元素",
+            Step::Login => "登录后跳转",
+            Step::CoverUpload => "上传封面进入编辑页",
+            Step::ImageUpload => "单张图片上传",
+            Step::VideoProcess => "视频上传和转码",
+            Step::Suggestions => "话题和用户候选",
+            Step::NetworkIdle => "页面请求结束",
+            Step::PublishResult => "发布结果",
+            Step::DraftSave => "暂存结果",
+        }
+    }
+
+    pub fn default_timeout(self) -> Duration {
+        Duration::from_secs(match self {
+            Step::PageLoad => 30,
+            Step::Element => 20,
+            Step::Login => 60,
+            Step::CoverUpload => 60,
+            Step::ImageUpload => 60,
+            Step::VideoProcess => 600,
+            Step::Suggestions => 4,
+            Step::NetworkIdle => 15,
+            Step::PublishResult => 30,
+            Step::DraftSave => 15,
+        })
+    }
+}
+
+lazy_static! {
+    static ref TIMEOUTS: RwLock<HashMap<Step, Duration>> = RwLock::new(HashMap::new());
+}
+
+/// 步骤当前的超时，没有设置时使用默认值
+pub fn timeout(step: Step) -> Duration {
+    TIMEOUTS
+        .read()
+        .unwrap()
+        .get(&step)
+        .copied()
+        .unwrap_or_else(|| step.default_timeout())
+}
+
+/// 解析 config 中保存的超时，忽略未知步骤和超出范围的值
+pub fn parse_timeouts(value: &str) -> HashMap<Step, Duration> {
+    let raw: HashMap<String, u64> = serde_json::from_str(value).unwrap_or_default();
+    raw.into_iter()
+        .filter(|(_, secs)| (1..=MAX_TIMEOUT_SECS).contains(secs))
+        .filter_map(|(name, secs)| Some((Step::parse(&name)?, Duration::from_secs(secs))))
+        .collect()
+}
+
+async fn load_raw() -> Result<HashMap<String, u64>, String> {
+    let pool = crate::storage::pool().await?;
+    Ok(ConfigRepo::new(pool)
+        .get(TIMEOUTS_KEY)
+        .await?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+/// 从 config 表读取各步骤超时，启动时和修改后调用
+pub async fn load_timeouts() -> Result<(), String> {
+    let raw = serde_json::to_string(&load_raw().await?).map_err(|e| e.to_string())?;
+    *TIMEOUTS.write().unwrap() = parse_timeouts(&raw);
+    Ok(())
+}
+
+/// 等待超时，错误信息说明在等什么、等了多久
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    pub step: Step,
+    pub what: String,
+    pub after: Duration,
+}
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "等待{}超时（{} 秒，步骤「{}」{}，可在设置中调整）",
+            self.what,
+            self.after.as_secs(),
+            self.step.label(),
+            self.step.as_str()
+        )
+    }
+}
+
+impl From<Timeout> for String {
+    fn from(timeout: Timeout) -> Self {
+        timeout.to_string()
+    }
+}
+
+impl From<Timeout> for PublishFailure {
+    fn from(timeout: Timeout) -> Self {
+        PublishFailure::new(PublishErrorKind::Timeout, timeout.to_string())
+    }
+}
+
+/// 反复检查直到 `check` 返回结果，超过步骤超时后返回 [`Timeout`]
+pub async fn until<T>(
+    step: Step,
+    what: &str,
+    mut check: impl FnMut() -> Option<T>,
+) -> Result<T, Timeout> {
+    let limit = timeout(step);
+    let started = Instant::now();
+    loop {
+        if let Some(value) = check() {
+            return Ok(value);
+        }
+        if started.elapsed() >= limit {
+            return Err(Timeout {
+                step,
+                what: what.to_string(),
+                after: limit,
+            });
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// 等待注册表中的元素出现，返回找到的候选
+pub async fn for_selector(tab: &dyn Driver, key: &str, step: Step) -> Result<String, Timeout> {
+    let what = format!(
+        "页面元素 {}（已尝试 {}）",
+        key,
+        selectors::candidates(key).join(" | ")
+    );
+    until(step, &what, || selectors::find(tab, key)).await
+}
+
+/// 等待页面跳转到满足条件的地址，返回当前地址
+pub async fn for_url(
+    tab: &dyn Driver,
+    what: &str,
+    step: Step,
+    accept: impl Fn(&str) -> bool,
+) -> Result<String, Timeout> {
+    until(step, what, || {
+        let url = tab.current_url();
+        accept(&url).then_some(url)
+    })
+    .await
+}
+
+/// 等待页面加载完成且一段时间内没有新的请求完成
+pub async fn for_network_idle(tab: &dyn Driver, step: Step) -> Result<(), Timeout> {
+    let script =
+        "document.readyState === 'complete' ? performance.getEntriesByType('resource').length : -1";
+    let mut last: Option<(i64, Instant)> = None;
+    until(step, "页面请求结束", || {
+        let count = tab
+            .evaluate(script, false)
+            .ok()
+            .flatten()
+            .and_then(|v| v.as_i64())
+            .unwrap_or(-1);
+        match last {
+            Some((previous, since)) if previous == count && count >= 0 => {
+                (since.elapsed() >= NETWORK_QUIET).then_some(())
+            }
+            _ => {
+                last = Some((count, Instant::now()));
+                None
+            }
+        }
+    })
+    .await
+}
+
+/// 尽量等页面请求结束，超时只记录日志，用于不影响结果的等待
+pub async fn settle(tab: &dyn Driver) {
+    if let Err(e) = for_network_idle(tab, Step::NetworkIdle).await {
+        log::warn!("{}", e);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepTimeout {
+    pub step: Step,
+    pub label: String,
+    pub seconds: u64,
+    pub default_seconds: u64,
+}
+
+#[tauri::command]
+pub async fn get_step_timeouts() -> Result<Vec<StepTimeout>, String> {
+    Ok(Step::ALL
+        .into_iter()
+        .map(|step| StepTimeout {
+            step,
+            label: step.label().to_string(),
+            seconds: timeout(step).as_secs(),
+            default_seconds: step.default_timeout().as_secs(),
+        })
+        .collect())
+}
+
+/// 设置步骤超时，`seconds` 为空时恢复默认值
+#[tauri::command]
+pub async fn set_step_timeout(step: String, seconds: Option<u64>) -> Result<(), String> {
+    let parsed = Step::parse(&step).ok_or_else(|| format!("未知的步骤: {}", step))?;
+    let mut raw = load_raw().await?;
+    match seconds {
+        Some(secs) if (1..=MAX_TIMEOUT_SECS).contains(&secs) => {
+            raw.insert(parsed.as_str().to_string(), secs);
+        }
+        Some(_) => return Err(format!("超时需要在 1 到 {} 秒之间", MAX_TIMEOUT_SECS)),
+        None => {
+            raw.remove(parsed.as_str());
+        }
+    }
+    let pool = crate::storage::pool().await?;
+    ConfigRepo::new(pool)
+        .set(
+            TIMEOUTS_KEY,
+            &serde_json::to_string(&raw).map_err(|e| e.to_string())?,
+        )
+        .await?;
+    load_timeouts().await
+}
@@ -5,10 +5,13 @@ pub mod auth;
 pub mod automation;
 pub mod mcp;
 pub mod model;
+pub mod scheduler;
+pub mod sensitive;
 pub mod storage;
 pub mod util;
 
 use std::sync::Arc;
+use tauri::Manager;
 use tauri_plugin_sql::Builder as SqlPluginBuilder;
 use tokio::sync::RwLock;
 
@@ -52,14 +55,76 @@ pub fn run() {
     let migrations = storage::sqlite::get_migrations();
     let db_path = storage::get_db_path();
 
-    // 初始化数据库
-    tauri::async_runtime::block_on(async {
-        if let Err(e) = storage::sqlite::initialize_database().await {
-            eprintln!("Failed to initialize database: {}", e);
+    // 启动参数或环境变量指定的工作区无效时直接退出，不能打开其他工作区的数据
+    if let Err(e) = storage::workspace::init_data_location() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    // 自动化过程的日志写到数据目录的 app.log
+    util::logging::enable_logging(Some(&storage::get_app_dir().join(util::logging::LOG_FILE)));
+
+    // 完成上次未完成的备份恢复，必须在打开数据库之前执行
+    storage::backup::apply_pending_restore();
+
+    // 初始化数据库，连接池在整个应用内共享
+    let db_pool = tauri::async_runtime::block_on(async {
+        match storage::sqlite::initialize_database().await {
+            Ok(pool) => {
+                // 加载主密钥，并把旧版本遗留的明文密钥加密保存
+                if let Err(e) =
+                    storage::secrets::recover_pending_change(&storage::get_app_dir(), pool).await
+                {
+                    eprintln!("Failed to recover master key change: {}", e);
+                }
+                match storage::secrets::init(&storage::get_app_dir()) {
+                    Ok(status) if status.unlocked => {
+                        if let Err(e) = storage::secrets::encrypt_plaintext_secrets(pool).await {
+                            eprintln!("Failed to encrypt stored secrets: {}", e);
+                        }
+                    }
+                    Ok(_) => println!("密钥已锁定，请输入主密码解锁"),
+                    Err(e) => eprintln!("Failed to initialize secrets: {}", e),
+                }
+                // 选择器文件和 config 中的覆盖项，加载时的问题通过 get_selectors 显示在界面上
+                if let Err(e) = automation::selectors::load().await {
+                    eprintln!("Failed to load selectors: {}", e);
+                }
+                // 各步骤的等待超时
+                if let Err(e) = automation::wait::load_timeouts().await {
+                    eprintln!("Failed to load step timeouts: {}", e);
+                }
+                // 模拟真人输入的设置
+                if let Err(e) = automation::human::load().await {
+                    eprintln!("Failed to load human input settings: {}", e);
+                }
+                // 上次异常退出时正在发布的笔记
+                match storage::repository::PostRepo::new(pool)
+                    .recover_interrupted()
+                    .await
+                {
+                    Ok(0) => {}
+                    Ok(n) => println!("{} 篇笔记在发布过程中中断，已标记为失败", n),
+                    Err(e) => eprintln!("Failed to recover interrupted posts: {}", e),
+                }
+                Some(pool.clone())
+            }
+            Err(e) => {
+                eprintln!("Failed to initialize database: {}", e);
+                None
+            }
         }
     });
 
     tauri::Builder::default()
+        .setup(move |app| {
+            if let Some(pool) = db_pool {
+                app.manage(pool);
+                scheduler::start();
+            }
+            automation::session::start_reaper();
+            Ok(())
+        })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
@@ -93,10 +158,47 @@ pub fn run() {
             ai::test_model_structured_output,
             ai::analyze_local_image,
             auth::save_post,
+            auth::update_post,
+            auth::list_post_revisions,
+            auth::diff_post_revisions,
+            auth::restore_post_revision,
             auth::get_posts,
             auth::delete_post,
             automation::publish_post,
+            automation::publish_post_by_id,
+            automation::set_post_publish_options,
+            automation::get_post_publish_options,
+            automation::multi_account::publish_to_accounts,
+            automation::set_post_status,
+            automation::get_post_status_history,
+            automation::validate_saved_post,
+            sensitive::check_sensitive_words,
+            sensitive::list_sensitive_words,
+            sensitive::save_sensitive_word,
+            sensitive::delete_sensitive_word,
+            sensitive::import_sensitive_words,
+            scheduler::schedule_post,
+            scheduler::list_publish_jobs,
+            scheduler::cancel_publish_job,
             automation::validate_login_status,
+            automation::selectors::get_selectors,
+            automation::selectors::reload_selectors,
+            automation::selectors::set_selector_override,
+            automation::selectors::import_selector_registry,
+            automation::selectors::selector_health_check,
+            automation::wait::get_step_timeouts,
+            automation::wait::set_step_timeout,
+            automation::human::get_human_input,
+            automation::human::set_human_input,
+            automation::proxy::get_account_proxy,
+            automation::proxy::set_account_proxy,
+            automation::proxy::test_account_proxy,
+            automation::trace::list_automation_runs,
+            automation::trace::get_automation_run,
+            automation::trace::open_automation_run,
+            automation::trace::get_trace_retention,
+            automation::trace::set_trace_retention,
+            automation::trace::prune_automation_runs,
             analytics::fetch_user_analytics,
             get_trends,
             mcp::start_mcp_server,
@@ -107,10 +209,26 @@ pub fn run() {
             get_api_status,
             generate_api_key,
             save_api_key,
-            get_api_key
+            get_api_key,
+            storage::workspace::get_data_location,
+            storage::workspace::list_workspaces,
+            storage::workspace::create_workspace,
+            storage::workspace::switch_workspace,
+            storage::backup::export_backup,
+            storage::backup::inspect_backup,
+            storage::backup::restore_backup,
+            storage::secrets::get_secrets_status,
+            storage::secrets::unlock_secrets,
+            storage::secrets::set_master_passphrase
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            // 退出时关闭所有账号的浏览器，避免残留进程占用数据目录
+            if let tauri::RunEvent::Exit = event {
+                automation::session::shutdown_all();
+            }
+        });
 }
 
 // ============ API 服务器管理命令 ============
@@ -132,16 +250,15 @@ async fn start_api_server(port: u16) -> Result<(), String> {
     }
 
     // 获取 API Key
-    let api_key = match ai::get_config_value("api_key".to_string()).await {
+    let api_key = match ai::get_secret_config("api_key").await {
         Ok(Some(key)) => key,
-        _ => {
+        Ok(None) => {
             // 如果没有 API Key，生成一个新的
             let new_key = uuid::Uuid::new_v4().to_string();
-            ai::save_config("api_key".to_string(), new_key.clone())
-                .await
-                .ok();
+            ai::save_secret_config("api_key", &new_key).await?;
             new_key
         }
+        Err(e) => return Err(e),
     };
 
     let is_running = Arc::new(RwLock::new(true));
@@ -217,16 +334,17 @@ async fn get_api_status() -> Result<serde_json::Value, String> {
 #[tauri::command]
 async fn generate_api_key() -> Result<String, String> {
     let new_key = uuid::Uuid::new_v4().to_string();
-    ai::save_config("api_key".to_string(), new_key.clone()).await?;
+    ai::save_secret_config("api_key", &new_key).await?;
     Ok(new_key)
 }
 
 #[tauri::command]
 async fn save_api_key(key: String) -> Result<(), String> {
-    ai::save_config("api_key".to_string(), key).await
+    ai::save_secret_config("api_key", &key).await
 }
 
+/// 返回解密后的 API Key，供 API 设置页复制使用
 #[tauri::command]
 async fn get_api_key() -> Result<Option<String>, String> {
-    ai::get_config_value("api_key".to_string()).await
+    ai::get_secret_config("api_key").await
 }
@@ -1,6 +1,6 @@
 use crate::auth;
 use crate::automation;
-use crate::model::{AIProvider, User};
+use crate::model::{AIProvider, User, WordHit};
 use axum::extract::State;
 use axum::http::HeaderMap;
 use axum::middleware::{self, Next};
@@ -139,8 +139,21 @@ pub struct PublishPostArgs {
     pub phone: String,
     pub title: String,
     pub content: String,
+    /// 图片列表，每项可以是本地路径、http(s) 链接、`asset:素材文件名` 或 base64 数据
+    #[serde(default)]
     pub images: Vec<String>,
+    /// 封面图片，格式同 images
     pub cover_image: Option<String>,
+    /// 本地视频路径，填写时发布视频笔记
+    pub video_path: Option<String>,
+    /// 用视频第几秒的画面作封面
+    pub cover_time: Option<f64>,
+    /// 话题名称，不需要带 #
+    pub topics: Option<Vec<String>>,
+    /// 要 @ 的用户昵称
+    pub mentions: Option<Vec<String>>,
+    /// 可见范围、定时发布、地点、原创声明、合集等发布设置
+    pub options: Option<automation::PublishOptions>,
 }
 
 #[derive(Serialize, Deserialize, schemars::JsonSchema)]
@@ -183,6 +196,18 @@ pub struct UsersOutput {
     pub users: Vec<User>,
 }
 
+#[derive(Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CheckWordsArgs {
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WordHitsOutput {
+    /// 命中的敏感词，位置按字符计算
+    pub hits: Vec<WordHit>,
+}
+
 #[derive(Serialize, Deserialize, schemars::JsonSchema)]
 pub struct AddAIProviderArgs {
     pub provider: AIProvider,
@@ -275,26 +300,62 @@ impl XhsMcpTools {
 
     #[tool(
         name = "publish_post",
-        description = "发布笔记到小红书. 需要手机号, 标题, 内容, 和图片列表."
+        description = "发布笔记到小红书. 需要手机号, 标题, 内容, 以及图片列表或视频路径. 图片可以是本地路径、网络链接、asset:素材文件名或 base64 数据. options.mode 可设为 platform_draft 保存到平台草稿箱, 或 dry_run 只填写并返回截图."
     )]
     async fn publish_post(
         &self,
         params: Parameters<PublishPostArgs>,
     ) -> Result<Json<StringOutput>, ErrorData> {
         let args = params.0;
-        automation::publish_post(
+        let note = automation::publish_post(
             args.phone,
             args.title,
             args.content,
             args.images,
             args.cover_image,
+            args.video_path,
+            args.cover_time,
+            args.topics,
+            args.mentions,
+            args.options,
         )
         .await
-        .map_err(|e| ErrorData::internal_error(e, None))?;
+        .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+
+        let mut result = match note.note_url {
+            Some(url) => format!("{}: {}", note.mode.done_message(), url),
+            None => note.mode.done_message().to_string(),
+        };
+        if !note.screenshots.is_empty() {
+            result.push_str(&format!("，截图: {}", note.screenshots.join("、")));
+        }
+        if !note.unresolved_topics.is_empty() {
+            result.push_str(&format!(
+                "，以下话题未找到，按普通文字发布: {}",
+                note.unresolved_topics.join("、")
+            ));
+        }
+        if !note.unresolved_mentions.is_empty() {
+            result.push_str(&format!(
+                "，以下用户未找到: {}",
+                note.unresolved_mentions.join("、")
+            ));
+        }
+        Ok(Json(StringOutput { result }))
+    }
 
-        Ok(Json(StringOutput {
-            result: "发布任务已提交".to_string(),
-        }))
+    #[tool(
+        name = "check_sensitive_words",
+        description = "用本地词库检查标题和正文中的极限词、医疗用语、站外引流词等, 返回位置、分类和替换建议"
+    )]
+    async fn check_sensitive_words(
+        &self,
+        params: Parameters<CheckWordsArgs>,
+    ) -> Result<Json<WordHitsOutput>, ErrorData> {
+        let hits = crate::sensitive::check_with_dictionary(&params.0.title, &params.0.content)
+            .await
+            .map_err(|e| ErrorData::internal_error(e, None))?;
+        Ok(Json(WordHitsOutput { hits }))
     }
 
     #[tool(
@@ -512,13 +573,9 @@ impl XhsMcpTools {
         &self,
         params: Parameters<AnalyzeLocalImageArgs>,
     ) -> Result<Json<StringOutput>, ErrorData> {
-        let providers = crate::ai::get_ai_providers()
+        let provider = crate::ai::get_ai_provider(params.0.provider_id)
             .await
-            .map_err(|e| ErrorData::internal_error(e, None))?;
-
-        let provider = providers
-            .into_iter()
-            .find(|p| p.id == Some(params.0.provider_id))
+            .map_err(|e| ErrorData::internal_error(e, None))?
             .ok_or_else(|| ErrorData::invalid_request("未找到指定的 AI 提供者", None))?;
 
         let result = crate::ai::analyze_local_image(
@@ -591,8 +648,14 @@ pub async fn start_mcp_server(port: u16, token: Option<String>) -> Result<(), St
     let cancel_token = CancellationToken::new();
     let cancel_token_for_shutdown = cancel_token.clone();
 
-    // 生成或使用提供的 token
-    let auth_token = token.unwrap_or_else(|| Uuid::new_v4().to_string());
+    // 使用提供的 token，否则沿用上次保存的 token 或生成新的，加密保存在配置中
+    let auth_token = match token.filter(|t| !t.is_empty()) {
+        Some(t) => t,
+        None => crate::ai::get_secret_config("mcp_token")
+            .await?
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+    };
+    crate::ai::save_secret_config("mcp_token", &auth_token).await?;
     let token_store = Arc::new(TokenStore {
         valid_token: Some(auth_token.clone()),
     });
@@ -37,13 +37,393 @@ pub struct AIProvider {
     pub models: Vec<AIModel>,
 }
 
-#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
 pub struct Post {
     pub id: i64,
     pub user_id: i64,
     pub title: String,
     pub content: String,
     pub images: Vec<String>,
-    pub status: String, // draft, publishing, published, failed
+    pub cover_image: Option<String>,
+    pub media_type: MediaType,
+    /// 视频笔记的本地视频文件
+    pub video_path: Option<String>,
+    /// 以视频某一帧作封面时的时间点（秒），设置了 cover_image 时以图片为准
+    pub cover_time: Option<f64>,
+    /// 发布时通过编辑器插入的话题，不带 #
+    pub topics: Vec<String>,
+    /// 发布时 @ 的用户昵称，不带 @
+    pub mentions: Vec<String>,
+    pub status: String, // draft, queued, publishing, published, failed
+    pub created_at: String,
+    /// 每次保存递增，用于乐观锁
+    pub version: i64,
+    pub updated_at: Option<String>,
+    pub status_updated_at: Option<String>,
+    /// 最近一次发布失败的原因
+    pub last_error: Option<String>,
+    /// 最近一次发布失败时的页面截图
+    pub failure_screenshot: Option<String>,
+    /// 最近一次发布失败的类型，例如 session_expired、content_rejected
+    pub failure_kind: Option<String>,
+    /// 发布成功后平台返回的笔记 id
+    pub note_id: Option<String>,
+    pub note_url: Option<String>,
+    /// 本次操作的提示，不保存，例如已经发布但记录状态失败
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// 笔记类型：图文或视频
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    #[default]
+    Image,
+    Video,
+}
+
+impl MediaType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Image => "image",
+            MediaType::Video => "video",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "image" => Some(MediaType::Image),
+            "video" => Some(MediaType::Video),
+            _ => None,
+        }
+    }
+
+    /// 有视频文件的是视频笔记，否则是图文笔记
+    pub fn from_video_path(video_path: Option<&str>) -> Self {
+        match video_path {
+            Some(path) if !path.trim().is_empty() => MediaType::Video,
+            _ => MediaType::Image,
+        }
+    }
+}
+
+/// 笔记的发布状态
+///
+/// 合法的流转：draft → queued → publishing → published | failed，
+/// queued 可以撤回为 draft，failed 可以重新排队或退回草稿。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    Queued,
+    Publishing,
+    Published,
+    Failed,
+}
+
+impl PostStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Queued => "queued",
+            PostStatus::Publishing => "publishing",
+            PostStatus::Published => "published",
+            PostStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(PostStatus::Draft),
+            "queued" => Some(PostStatus::Queued),
+            "publishing" => Some(PostStatus::Publishing),
+            "published" => Some(PostStatus::Published),
+            "failed" => Some(PostStatus::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn can_transition_to(&self, next: PostStatus) -> bool {
+        use PostStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Queued)
+                | (Queued, Publishing)
+                | (Queued, Draft)
+                | (Publishing, Published)
+                | (Publishing, Failed)
+                | (Failed, Queued)
+                | (Failed, Draft)
+        )
+    }
+
+    /// 排队或发布中的笔记不允许编辑
+    pub fn is_editable(&self) -> bool {
+        !matches!(self, PostStatus::Queued | PostStatus::Publishing)
+    }
+}
+
+/// 一次状态流转记录
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct PostStatusChange {
+    pub id: i64,
+    pub post_id: i64,
+    pub from_status: String,
+    pub to_status: String,
+    pub error: Option<String>,
+    pub screenshot: Option<String>,
+    pub created_at: String,
+}
+
+/// 草稿的一次保存记录
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct PostRevision {
+    pub id: i64,
+    pub post_id: i64,
+    pub version: i64,
+    pub title: String,
+    pub content: String,
+    pub images: Vec<String>,
+    pub cover_image: Option<String>,
+    pub media_type: MediaType,
+    pub video_path: Option<String>,
+    pub cover_time: Option<f64>,
+    pub topics: Vec<String>,
+    pub mentions: Vec<String>,
+    pub source: Option<String>, // manual, ai, restore
+    pub created_at: String,
+}
+
+/// 两个版本之间某个字段的变化
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+impl PostRevision {
+    /// 逐字段比较两个版本，只返回有变化的字段
+    pub fn diff(&self, other: &PostRevision) -> Vec<FieldChange> {
+        let fields = [
+            ("title", serde_json::json!(self.title), serde_json::json!(other.title)),
+            ("content", serde_json::json!(self.content), serde_json::json!(other.content)),
+            ("images", serde_json::json!(self.images), serde_json::json!(other.images)),
+            (
+                "cover_image",
+                serde_json::json!(self.cover_image),
+                serde_json::json!(other.cover_image),
+            ),
+            (
+                "media_type",
+                serde_json::json!(self.media_type),
+                serde_json::json!(other.media_type),
+            ),
+            (
+                "video_path",
+                serde_json::json!(self.video_path),
+                serde_json::json!(other.video_path),
+            ),
+            (
+                "cover_time",
+                serde_json::json!(self.cover_time),
+                serde_json::json!(other.cover_time),
+            ),
+            ("topics", serde_json::json!(self.topics), serde_json::json!(other.topics)),
+            (
+                "mentions",
+                serde_json::json!(self.mentions),
+                serde_json::json!(other.mentions),
+            ),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(field, before, after)| FieldChange {
+                field: field.to_string(),
+                before,
+                after,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishJobStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// 错过发布时间且按补发策略放弃
+    Skipped,
+}
+
+impl PublishJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublishJobStatus::Pending => "pending",
+            PublishJobStatus::Running => "running",
+            PublishJobStatus::Succeeded => "succeeded",
+            PublishJobStatus::Failed => "failed",
+            PublishJobStatus::Cancelled => "cancelled",
+            PublishJobStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(PublishJobStatus::Pending),
+            "running" => Some(PublishJobStatus::Running),
+            "succeeded" => Some(PublishJobStatus::Succeeded),
+            "failed" => Some(PublishJobStatus::Failed),
+            "cancelled" => Some(PublishJobStatus::Cancelled),
+            "skipped" => Some(PublishJobStatus::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// 失败重试策略，第 n 次重试等待 `backoff_seconds * 2^(n-1)` 秒
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RetryPolicy {
+    /// 包含首次执行在内的最大尝试次数
+    pub max_attempts: i64,
+    pub backoff_seconds: i64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_seconds: 300,
+        }
+    }
+}
+
+/// 应用未运行或休眠导致错过发布时间时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// 无论错过多久都立即补发
+    RunLate,
+    /// 跳过，笔记退回草稿
+    Skip,
+    /// 错过不超过指定分钟数时补发，否则跳过
+    Window { minutes: i64 },
+}
+
+impl Default for CatchUpPolicy {
+    fn default() -> Self {
+        CatchUpPolicy::Window { minutes: 60 }
+    }
+}
+
+/// 定时发布任务
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PublishJob {
+    pub id: i64,
+    pub post_id: i64,
+    /// 用户选择的本地时间，例如 2026-10-20T20:00
+    pub local_time: String,
+    /// IANA 时区名，例如 Asia/Shanghai
+    pub timezone: String,
+    /// 计划发布时间（UTC）
+    pub scheduled_at: String,
+    /// 下一次执行时间（UTC），重试时会推迟
+    pub next_run_at: String,
+    pub status: PublishJobStatus,
+    pub attempts: i64,
+    pub retry: RetryPolicy,
+    pub catch_up: CatchUpPolicy,
+    pub last_error: Option<String>,
     pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 敏感词词库中的一个词
+#[derive(Debug, Serialize, Deserialize, Clone, schemars::JsonSchema)]
+pub struct SensitiveWord {
+    pub id: Option<i64>,
+    pub word: String,
+    /// 分类，例如 极限词、医疗用语、站外引流、竞品
+    pub category: String,
+    /// 建议替换成的词
+    pub replacement: Option<String>,
+    pub enabled: bool,
+}
+
+/// 文本中命中的一个敏感词，位置按字符计算
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema)]
+pub struct WordHit {
+    /// title 或 content
+    pub field: String,
+    pub word: String,
+    pub category: String,
+    pub start: usize,
+    pub end: usize,
+    pub replacement: Option<String>,
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyScheme {
+    #[default]
+    Http,
+    Socks5,
+}
+
+impl ProxyScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Socks5 => "socks5",
+        }
+    }
+}
+
+/// 账号的浏览器代理，保存在 `users` 表中
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ProxyConfig {
+    #[serde(default)]
+    pub scheme: ProxyScheme,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    /// 数据库中是密文，返回给前端时是脱敏占位符
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let host = self.host.trim();
+        if host.is_empty() || host.contains(['/', ' ', '@']) {
+            return Err("代理地址格式不正确".to_string());
+        }
+        if self.port == 0 {
+            return Err("代理端口不能为 0".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        self.username.as_deref().is_some_and(|u| !u.is_empty())
+    }
+
+    /// 不含用户名密码的代理地址，例如 `http://127.0.0.1:7890`
+    pub fn server(&self) -> String {
+        let host = self.host.trim();
+        // IPv6 地址需要加方括号
+        if host.contains(':') && !host.starts_with('[') {
+            format!("{}://[{}]:{}", self.scheme.as_str(), host, self.port)
+        } else {
+            format!("{}://{}:{}", self.scheme.as_str(), host, self.port)
+        }
+    }
 }
@@ -0,0 +1,254 @@
+use crate::model::{CatchUpPolicy, PostStatus, PublishJob, PublishJobStatus, RetryPolicy};
+use crate::storage::repository::{PostRepo, PublishJobRepo};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeDelta, TimeZone, Utc};
+use sqlx::sqlite::SqlitePool;
+use std::time::Duration;
+
+/// 检查到期任务的间隔
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// 超过计划时间多久算作错过，需要按补发策略处理
+const MISSED_GRACE_SECONDS: i64 = 120;
+/// 单次重试等待的上限
+const MAX_RETRY_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// 统一的 UTC 时间格式，保证数据库里可以按字符串比较先后
+pub fn format_utc(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_utc(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("时间格式错误 {}: {}", value, e))
+}
+
+fn parse_local_time(value: &str) -> Result<NaiveDateTime, String> {
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value.trim(), fmt).ok())
+        .ok_or_else(|| format!("无法解析发布时间 {}，格式应为 2026-10-20T20:00", value))
+}
+
+/// 把本地时间换算成 UTC；夏令时重叠时取较早的时刻，跳过的时刻视为无效
+pub fn local_to_utc<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> Result<DateTime<Utc>, String> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| format!("{} 在该时区不存在（夏令时切换）", local))
+}
+
+/// 把用户在指定 IANA 时区（例如 Asia/Shanghai）选择的时间换算成 UTC
+pub fn schedule_to_utc(local_time: &str, timezone: &str) -> Result<DateTime<Utc>, String> {
+    let tz: chrono_tz::Tz = timezone
+        .parse()
+        .map_err(|_| format!("未知的时区 {}", timezone))?;
+    local_to_utc(&tz, parse_local_time(local_time)?)
+}
+
+/// 第 `attempts` 次失败后到下次重试的等待时间
+pub fn retry_delay(policy: &RetryPolicy, attempts: i64) -> TimeDelta {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    let seconds = policy
+        .backoff_seconds
+        .max(0)
+        .saturating_mul(1 << exponent)
+        .min(MAX_RETRY_DELAY_SECONDS);
+    TimeDelta::seconds(seconds)
+}
+
+/// 到期任务是否应该执行；错过太久时由补发策略决定
+pub fn should_run(policy: &CatchUpPolicy, next_run_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    let late = now - next_run_at;
+    if late <= TimeDelta::seconds(MISSED_GRACE_SECONDS) {
+        return true;
+    }
+    match policy {
+        CatchUpPolicy::RunLate => true,
+        CatchUpPolicy::Skip => false,
+        CatchUpPolicy::Window { minutes } => late <= TimeDelta::minutes(*minutes),
+    }
+}
+
+/// 在后台启动调度器，应用运行期间每隔 30 秒检查一次到期任务
+pub fn start() {
+    tauri::async_runtime::spawn(async {
+        match crate::storage::pool().await {
+            Ok(pool) => match PublishJobRepo::new(pool).fail_interrupted().await {
+                Ok(0) => {}
+                Ok(n) => println!("{} 个定时任务在执行中中断，任务和笔记已标记为失败", n),
+                Err(e) => eprintln!("Failed to recover publish jobs: {}", e),
+            },
+            Err(e) => eprintln!("定时发布调度器启动失败: {}", e),
+        }
+
+        loop {
+            if let Err(e) = tick().await {
+                eprintln!("定时发布调度失败: {}", e);
+            }
+            tokio::time::sleep(TICK_INTERVAL).await;
+        }
+    });
+}
+
+/// 执行所有到期任务，同一时间只发布一篇，避免多个浏览器抢占同一账号
+pub async fn tick() -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    let jobs = PublishJobRepo::new(pool);
+    let now = Utc::now();
+
+    for job in jobs.due(&format_utc(now)).await? {
+        if !should_run(&job.catch_up, parse_utc(&job.next_run_at)?, now) {
+            println!("定时任务 {} 错过发布时间，按补发策略跳过", job.id);
+            jobs.set_status(
+                job.id,
+                PublishJobStatus::Skipped,
+                Some("错过发布时间，按补发策略跳过"),
+                None,
+            )
+            .await?;
+            release_post(pool, job.post_id).await;
+            continue;
+        }
+
+        if !jobs.claim(job.id).await? {
+            continue;
+        }
+        // 单个任务出错不影响其他到期任务
+        if let Err(e) = run_job(pool, &job).await {
+            eprintln!("定时任务 {} 执行失败: {}", job.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_job(pool: &SqlitePool, job: &PublishJob) -> Result<(), String> {
+    let jobs = PublishJobRepo::new(pool);
+    let attempts = job.attempts + 1;
+    println!(
+        "执行定时任务 {}（笔记 {}，第 {} 次）",
+        job.id, job.post_id, attempts
+    );
+
+    match crate::automation::run_queued_post(job.post_id).await {
+        Ok(_) => {
+            jobs.set_status(job.id, PublishJobStatus::Succeeded, None, None)
+                .await
+        }
+        Err(failure) if failure.kind.is_retryable() && attempts < job.retry.max_attempts => {
+            // 发布失败的笔记重新排队，等待下次重试
+            let posts = PostRepo::new(pool);
+            if let Some(post) = posts.get(job.post_id).await? {
+                if post.status == PostStatus::Failed.as_str() {
+                    posts
+                        .transition(job.post_id, PostStatus::Queued, None, None)
+                        .await?;
+                }
+            }
+            let next_run_at = Utc::now() + retry_delay(&job.retry, attempts);
+            println!("定时任务 {} 失败，将于 {} 重试: {}", job.id, next_run_at, failure);
+            jobs.set_status(
+                job.id,
+                PublishJobStatus::Pending,
+                Some(&failure.to_string()),
+                Some(&format_utc(next_run_at)),
+            )
+            .await
+        }
+        // 登录失效、内容被拒等无法通过重试解决的失败直接结束
+        Err(failure) => {
+            jobs.set_status(
+                job.id,
+                PublishJobStatus::Failed,
+                Some(&failure.to_string()),
+                None,
+            )
+            .await?;
+            release_post(pool, job.post_id).await;
+            Ok(())
+        }
+    }
+}
+
+/// 任务不再执行时，把仍在排队的笔记退回草稿
+async fn release_post(pool: &SqlitePool, post_id: i64) {
+    let posts = PostRepo::new(pool);
+    if let Ok(Some(post)) = posts.get(post_id).await {
+        if post.status == PostStatus::Queued.as_str() {
+            if let Err(e) = posts
+                .transition(post_id, PostStatus::Draft, None, None)
+                .await
+            {
+                eprintln!("Failed to release post {}: {}", post_id, e);
+            }
+        }
+    }
+}
+
+/// 定时发布草稿
+///
+/// `local_time` 是用户在 `timezone` 时区选择的时间，例如 `2026-10-20T20:00` 和 `Asia/Shanghai`。
+#[tauri::command]
+pub async fn schedule_post(
+    post_id: i64,
+    local_time: String,
+    timezone: String,
+    retry: Option<RetryPolicy>,
+    catch_up: Option<CatchUpPolicy>,
+) -> Result<PublishJob, String> {
+    let scheduled_at = schedule_to_utc(&local_time, &timezone)?;
+    if scheduled_at <= Utc::now() {
+        return Err("发布时间必须晚于当前时间".to_string());
+    }
+    let retry = retry.unwrap_or_default();
+    if retry.max_attempts < 1 || !(0..=MAX_RETRY_DELAY_SECONDS).contains(&retry.backoff_seconds) {
+        return Err("重试策略无效".to_string());
+    }
+
+    let pool = crate::storage::pool().await?;
+    let posts = PostRepo::new(pool);
+    posts
+        .transition(post_id, PostStatus::Queued, None, None)
+        .await?;
+
+    let job = PublishJobRepo::new(pool)
+        .create(
+            post_id,
+            &local_time,
+            &timezone,
+            &format_utc(scheduled_at),
+            retry,
+            catch_up.unwrap_or_default(),
+        )
+        .await;
+    if job.is_err() {
+        let _ = posts
+            .transition(post_id, PostStatus::Draft, None, None)
+            .await;
+    }
+    job
+}
+
+#[tauri::command]
+pub async fn list_publish_jobs(
+    status: Option<PublishJobStatus>,
+) -> Result<Vec<PublishJob>, String> {
+    let pool = crate::storage::pool().await?;
+    PublishJobRepo::new(pool).list(status).await
+}
+
+/// 取消尚未执行的定时任务，笔记退回草稿
+#[tauri::command]
+pub async fn cancel_publish_job(job_id: i64) -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    let jobs = PublishJobRepo::new(pool);
+    let job = jobs
+        .get(job_id)
+        .await?
+        .ok_or_else(|| "定时任务不存在".to_string())?;
+    if !jobs.cancel(job_id).await? {
+        return Err("任务已开始执行或已结束，无法取消".to_string());
+    }
+    release_post(pool, job.post_id).await;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::model::{SensitiveWord, WordHit};
+use crate::storage::repository::SensitiveWordRepo;
+
+/// 解析词库文本：每行一个词，可以用逗号写替换词，例如 `最好,很好`；`#` 开头的行是注释
+pub fn parse_dictionary(text: &str) -> Vec<(String, Option<String>)> {
+    let mut entries: Vec<(String, Option<String>)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(2, [',', '，', '\t']);
+        let word = parts.next().unwrap_or_default().trim().to_string();
+        let replacement = parts
+            .next()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty());
+        if !word.is_empty() && !entries.iter().any(|(w, _)| *w == word) {
+            entries.push((word, replacement));
+        }
+    }
+    entries
+}
+
+/// 在一段文本中查找所有命中的词，英文不区分大小写
+///
+/// 同一位置命中多个词时保留最长的，被更长的词完整覆盖的短词不再单独报告。
+pub fn find_hits(words: &[SensitiveWord], field: &str, text: &str) -> Vec<WordHit> {
+    let chars: Vec<char> = text.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let mut hits: Vec<WordHit> = Vec::new();
+
+    for word in words.iter().filter(|w| w.enabled) {
+        let pattern: Vec<char> = word.word.chars().map(|c| c.to_ascii_lowercase()).collect();
+        if pattern.is_empty() || pattern.len() > chars.len() {
+            continue;
+        }
+        for start in 0..=chars.len() - pattern.len() {
+            if chars[start..start + pattern.len()] == pattern[..] {
+                hits.push(WordHit {
+                    field: field.to_string(),
+                    word: word.word.clone(),
+                    category: word.category.clone(),
+                    start,
+                    end: start + pattern.len(),
+                    replacement: word.replacement.clone(),
+                });
+            }
+        }
+    }
+
+    hits.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    let mut kept: Vec<WordHit> = Vec::new();
+    for hit in hits {
+        let covered = kept
+            .iter()
+            .any(|k| k.start <= hit.start && hit.end <= k.end && k.word != hit.word);
+        let duplicate = kept
+            .iter()
+            .any(|k| k.start == hit.start && k.end == hit.end && k.word == hit.word);
+        if !covered && !duplicate {
+            kept.push(hit);
+        }
+    }
+    kept
+}
+
+pub fn check_post(words: &[SensitiveWord], title: &str, content: &str) -> Vec<WordHit> {
+    let mut hits = find_hits(words, "title", title);
+    hits.extend(find_hits(words, "content", content));
+    hits
+}
+
+/// 用当前启用的词库检查标题和正文
+pub async fn check_with_dictionary(title: &str, content: &str) -> Result<Vec<WordHit>, String> {
+    let pool = crate::storage::pool().await?;
+    let words = SensitiveWordRepo::new(pool).list_enabled().await?;
+    Ok(check_post(&words, title, content))
+}
+
+#[tauri::command]
+pub async fn check_sensitive_words(title: String, content: String) -> Result<Vec<WordHit>, String> {
+    check_with_dictionary(&title, &content).await
+}
+
+#[tauri::command]
+pub async fn list_sensitive_words() -> Result<Vec<SensitiveWord>, String> {
+    let pool = crate::storage::pool().await?;
+    SensitiveWordRepo::new(pool).list().await
+}
+
+#[tauri::command]
+pub async fn save_sensitive_word(word: SensitiveWord) -> Result<i64, String> {
+    let pool = crate::storage::pool().await?;
+    SensitiveWordRepo::new(pool).save(&word).await
+}
+
+#[tauri::command]
+pub async fn delete_sensitive_word(id: i64) -> Result<(), String> {
+    let pool = crate::storage::pool().await?;
+    SensitiveWordRepo::new(pool).delete(id).await
+}
+
+/// 从文本文件导入词库，返回新增的词数
+#[tauri::command]
+pub async fn import_sensitive_words(path: String, category: String) -> Result<usize, String> {
+    let category = category.trim();
+    if category.is_empty() {
+        return Err("请填写词库分类".to_string());
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("读取 {} 失败: {}", path, e))?;
+    let entries = parse_dictionary(&text);
+    let pool = crate::storage::pool().await?;
+    SensitiveWordRepo::new(pool).import(category, &entries).await
+}
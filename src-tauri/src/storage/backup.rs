@@ -0,0 +1,551 @@
+use crate::storage::sqlite::{current_schema_version, latest_schema_version, run_migrations};
+use crate::storage::secrets;
+use crate::storage::{get_app_dir, pool};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 备份包格式版本，格式不兼容时递增
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "database/app.db";
+/// 随数据库一起备份的密钥配置，只有盐值和校验值，不含密钥本身
+const SECRETS_ENTRY: &str = "database/secrets.json";
+const KEY_ENTRY: &str = "secrets/master.key";
+const PENDING_RESTORE_DIR: &str = "restore-pending";
+const PENDING_RESTORE_FILE: &str = "pending.json";
+
+/// Chrome 的缓存目录和运行时锁文件，备份时跳过
+const PROFILE_SKIP: &[&str] = &[
+    "Cache",
+    "Code Cache",
+    "GPUCache",
+    "ShaderCache",
+    "GrShaderCache",
+    "CacheStorage",
+    "SingletonLock",
+    "SingletonCookie",
+    "SingletonSocket",
+    "DevToolsActivePort",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupPart {
+    /// app.db：账号、草稿、AI 配置和设置
+    Database,
+    /// images/ 素材库
+    Images,
+    /// profiles/ 浏览器登录状态
+    Profiles,
+    /// master.key，拿到备份的人可以解密其中的 API Key，需要单独勾选
+    #[serde(rename = "master_key")]
+    MasterKey,
+}
+
+impl BackupPart {
+    fn prefix(&self) -> &'static str {
+        match self {
+            BackupPart::Database => "database/",
+            BackupPart::Images => "images/",
+            BackupPart::Profiles => "profiles/",
+            BackupPart::MasterKey => "secrets/",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFileEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub schema_version: i64,
+    pub created_at: String,
+    pub workspace: String,
+    pub parts: Vec<BackupPart>,
+    pub files: Vec<BackupFileEntry>,
+    /// 导出时需要提醒用户的事项，例如备份中包含主密钥
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingRestore {
+    parts: Vec<BackupPart>,
+    /// 已经开始替换，再次启动时是在继续上次中断的恢复
+    #[serde(default)]
+    started: bool,
+}
+
+/// 边写边计算 SHA-256 和大小
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            size: 0,
+        }
+    }
+
+    fn finish(self) -> (String, u64) {
+        let digest = self.hasher.finalize();
+        (
+            digest.iter().map(|b| format!("{:02x}", b)).collect(),
+            self.size,
+        )
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 列出目录下所有文件，返回 (绝对路径, 以 `/` 分隔的相对路径)
+fn collect_files(dir: &Path, skip: &[&str]) -> Vec<(PathBuf, String)> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| {
+            e.file_name()
+                .to_str()
+                .map(|name| !skip.contains(&name))
+                .unwrap_or(true)
+        })
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(dir).ok()?;
+            let rel = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((e.path().to_path_buf(), rel))
+        })
+        .collect()
+}
+
+/// 把工作区数据写入备份包
+///
+/// `db_snapshot` 是通过 `VACUUM INTO` 得到的一致性快照，而不是正在使用的 app.db。
+pub fn write_backup_archive(
+    app_dir: &Path,
+    db_snapshot: Option<&Path>,
+    dest: &Path,
+    parts: &[BackupPart],
+    schema_version: i64,
+    workspace: &str,
+) -> Result<BackupManifest, String> {
+    let key_file = secrets::key_file(app_dir);
+    let mut warnings = Vec::new();
+    let mut sources: Vec<(PathBuf, String)> = Vec::new();
+    for part in parts {
+        match part {
+            BackupPart::Database => {
+                let snapshot = db_snapshot.ok_or("缺少数据库快照")?;
+                sources.push((snapshot.to_path_buf(), DATABASE_ENTRY.to_string()));
+                sources.push((
+                    app_dir.join(secrets::SECRETS_FILE),
+                    SECRETS_ENTRY.to_string(),
+                ));
+                if !parts.contains(&BackupPart::MasterKey) && key_file.exists() {
+                    warnings.push(format!(
+                        "备份不含主密钥，恢复到其他电脑时需要另外复制 {:?}，否则 API Key 无法解密",
+                        key_file
+                    ));
+                }
+            }
+            BackupPart::MasterKey => {
+                sources.push((key_file.clone(), KEY_ENTRY.to_string()));
+                warnings.push(
+                    "备份中包含主密钥，拿到备份的人可以解密其中的 API Key 和代理密码，请妥善保管"
+                        .to_string(),
+                );
+            }
+            BackupPart::Images => {
+                for (path, rel) in collect_files(&app_dir.join("images"), &[]) {
+                    sources.push((path, format!("images/{}", rel)));
+                }
+            }
+            BackupPart::Profiles => {
+                for (path, rel) in collect_files(&app_dir.join("profiles"), PROFILE_SKIP) {
+                    sources.push((path, format!("profiles/{}", rel)));
+                }
+            }
+        }
+    }
+
+    let file = File::create(dest).map_err(|e| format!("无法创建备份文件 {:?}: {}", dest, e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    let mut files = Vec::with_capacity(sources.len());
+    for (path, entry) in sources {
+        let mut source = match File::open(&path) {
+            Ok(f) => f,
+            // 浏览器运行时可能删除临时文件，跳过即可
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("读取 {:?} 失败: {}", path, e)),
+        };
+        zip.start_file(entry.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        let mut writer = HashingWriter::new(&mut zip);
+        io::copy(&mut source, &mut writer).map_err(|e| format!("写入 {} 失败: {}", entry, e))?;
+        let (sha256, size) = writer.finish();
+        files.push(BackupFileEntry {
+            path: entry,
+            size,
+            sha256,
+        });
+    }
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        created_at: chrono::Local::now().to_rfc3339(),
+        workspace: workspace.to_string(),
+        parts: parts.to_vec(),
+        files,
+        warnings,
+    };
+
+    zip.start_file(MANIFEST_NAME, options)
+        .map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(manifest)
+}
+
+pub fn read_backup_manifest(archive_path: &Path) -> Result<BackupManifest, String> {
+    let file = File::open(archive_path).map_err(|e| format!("无法打开备份文件: {}", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("备份文件格式错误: {}", e))?;
+    let mut entry = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| "备份文件缺少 manifest.json".to_string())?;
+    let mut json = String::new();
+    entry
+        .read_to_string(&mut json)
+        .map_err(|e| e.to_string())?;
+    let manifest: BackupManifest =
+        serde_json::from_str(&json).map_err(|e| format!("manifest.json 解析失败: {}", e))?;
+
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(format!(
+            "备份格式 v{} 高于当前应用支持的 v{}，请升级应用",
+            manifest.format_version, BACKUP_FORMAT_VERSION
+        ));
+    }
+    if manifest.schema_version > latest_schema_version() {
+        return Err(format!(
+            "备份的数据库版本 v{} 高于当前应用支持的 v{}，请升级应用",
+            manifest.schema_version,
+            latest_schema_version()
+        ));
+    }
+    Ok(manifest)
+}
+
+/// 解压并校验备份包到暂存目录，校验失败时清理暂存目录
+pub fn stage_backup(
+    archive_path: &Path,
+    staging: &Path,
+    parts: &[BackupPart],
+) -> Result<BackupManifest, String> {
+    let manifest = read_backup_manifest(archive_path)?;
+    for part in parts {
+        if !manifest.parts.contains(part) {
+            return Err(format!("备份中不包含 {:?}", part));
+        }
+    }
+
+    if staging.exists() {
+        fs::remove_dir_all(staging).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(staging).map_err(|e| e.to_string())?;
+
+    let result = extract_verified(archive_path, staging, &manifest, parts).and_then(|_| {
+        // 备份中的目录为空时也留下暂存目录，恢复时据此替换为空目录
+        for part in parts {
+            if matches!(part, BackupPart::Images | BackupPart::Profiles) {
+                let dir = staging.join(part.prefix().trim_end_matches('/'));
+                fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(staging);
+    }
+    result.map(|_| manifest)
+}
+
+fn extract_verified(
+    archive_path: &Path,
+    staging: &Path,
+    manifest: &BackupManifest,
+    parts: &[BackupPart],
+) -> Result<(), String> {
+    let file = File::open(archive_path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    for expected in &manifest.files {
+        if !parts.iter().any(|p| expected.path.starts_with(p.prefix())) {
+            continue;
+        }
+
+        let mut entry = archive
+            .by_name(&expected.path)
+            .map_err(|_| format!("备份文件缺少 {}", expected.path))?;
+        let rel = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("备份中包含非法路径 {}", expected.path))?;
+        let dest = staging.join(rel);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        let out = File::create(&dest).map_err(|e| e.to_string())?;
+        let mut writer = HashingWriter::new(out);
+        io::copy(&mut entry, &mut writer).map_err(|e| e.to_string())?;
+        let (sha256, size) = writer.finish();
+
+        if sha256 != expected.sha256 || size != expected.size {
+            return Err(format!("{} 校验失败，备份文件可能已损坏", expected.path));
+        }
+    }
+
+    Ok(())
+}
+
+/// 将暂存区内容替换到工作区，被替换的内容保留为 `*.before-restore`
+///
+/// 可以重复执行：上次中断时已经替换完成的项没有暂存内容，直接跳过；已经存在的
+/// `*.before-restore` 是替换前的原始内容，不会被覆盖。全部完成后才删除暂存区和标记文件。
+pub fn apply_staged_restore(app_dir: &Path) -> Result<bool, String> {
+    let staging = app_dir.join(PENDING_RESTORE_DIR);
+    let marker = staging.join(PENDING_RESTORE_FILE);
+    let mut pending: PendingRestore = match fs::read_to_string(&marker) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| e.to_string())?,
+        Err(_) => return Ok(false),
+    };
+
+    let entries: Vec<(PathBuf, PathBuf)> = pending
+        .parts
+        .iter()
+        .flat_map(|part| restore_entries(*part, &staging, app_dir))
+        .collect();
+
+    if !pending.started {
+        // 清理更早一次恢复留下的原始内容，之后中断再继续时不再清理
+        for (_, target) in &entries {
+            remove_path(&before_restore_path(target))?;
+        }
+        pending.started = true;
+        let json = serde_json::to_string(&pending).map_err(|e| e.to_string())?;
+        fs::write(&marker, json).map_err(|e| e.to_string())?;
+    }
+
+    for (staged, target) in &entries {
+        if !staged.exists() {
+            continue;
+        }
+        if target == &app_dir.join("app.db") {
+            for suffix in ["app.db-wal", "app.db-shm"] {
+                let _ = fs::remove_file(app_dir.join(suffix));
+            }
+        }
+        let previous = before_restore_path(target);
+        if previous.exists() {
+            // 上次已经保留了原始内容，现在的是中断后新生成的
+            remove_path(target)?;
+        } else if target.exists() {
+            fs::rename(target, &previous).map_err(|e| e.to_string())?;
+        }
+        fs::rename(staged, target).map_err(|e| e.to_string())?;
+    }
+
+    fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 恢复某一项时暂存区中的文件或目录和它在工作区中的位置
+fn restore_entries(part: BackupPart, staging: &Path, app_dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    match part {
+        BackupPart::Database => vec![
+            (staging.join(DATABASE_ENTRY), app_dir.join("app.db")),
+            (
+                staging.join(SECRETS_ENTRY),
+                app_dir.join(secrets::SECRETS_FILE),
+            ),
+        ],
+        BackupPart::MasterKey => vec![(staging.join(KEY_ENTRY), secrets::key_file(app_dir))],
+        BackupPart::Images | BackupPart::Profiles => {
+            let name = part.prefix().trim_end_matches('/');
+            vec![(staging.join(name), app_dir.join(name))]
+        }
+    }
+}
+
+fn before_restore_path(target: &Path) -> PathBuf {
+    let mut previous = target.as_os_str().to_owned();
+    previous.push(".before-restore");
+    PathBuf::from(previous)
+}
+
+fn remove_path(path: &Path) -> Result<(), String> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// 启动时在打开数据库之前调用，完成上次 `restore_backup` 留下的替换
+pub fn apply_pending_restore() {
+    match apply_staged_restore(&get_app_dir()) {
+        Ok(true) => println!("已从备份恢复数据"),
+        Ok(false) => {}
+        Err(e) => eprintln!("恢复备份失败: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn export_backup(path: String, parts: Vec<BackupPart>) -> Result<BackupManifest, String> {
+    if parts.is_empty() {
+        return Err("请至少选择一项备份内容".to_string());
+    }
+
+    let app_dir = get_app_dir();
+    let workspace = crate::storage::workspace::data_location().workspace.clone();
+    let pool = pool().await?;
+    let schema_version = current_schema_version(pool).await?;
+
+    // VACUUM INTO 生成包含 WAL 内容的一致性快照，不影响正在使用的连接
+    let snapshot = if parts.contains(&BackupPart::Database) {
+        let snapshot = app_dir.join(format!("backup-{}.db", uuid::Uuid::new_v4()));
+        sqlx::query("VACUUM INTO ?")
+            .bind(snapshot.to_string_lossy().to_string())
+            .execute(pool)
+            .await
+            .map_err(|e| format!("生成数据库快照失败: {}", e))?;
+        Some(snapshot)
+    } else {
+        None
+    };
+
+    let dest = PathBuf::from(path);
+    let snapshot_for_task = snapshot.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        write_backup_archive(
+            &app_dir,
+            snapshot_for_task.as_deref(),
+            &dest,
+            &parts,
+            schema_version,
+            &workspace,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some(snapshot) = snapshot {
+        let _ = fs::remove_file(snapshot);
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn inspect_backup(path: String) -> Result<BackupManifest, String> {
+    read_backup_manifest(Path::new(&path))
+}
+
+/// 从备份恢复，校验通过后重启应用完成替换
+///
+/// `parts` 为空时恢复备份中包含的全部内容。数据库会先在暂存区执行迁移，
+/// 失败则不会影响当前数据。
+#[tauri::command]
+pub async fn restore_backup(
+    app: tauri::AppHandle,
+    path: String,
+    parts: Option<Vec<BackupPart>>,
+) -> Result<(), String> {
+    let app_dir = get_app_dir();
+    let staging = app_dir.join(PENDING_RESTORE_DIR);
+    let archive = PathBuf::from(path);
+
+    let manifest = read_backup_manifest(&archive)?;
+    let parts = parts
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| manifest.parts.clone());
+
+    let staging_for_task = staging.clone();
+    let parts_for_task = parts.clone();
+    tokio::task::spawn_blocking(move || stage_backup(&archive, &staging_for_task, &parts_for_task))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    if parts.contains(&BackupPart::Database) {
+        let staged_db = staging.join(DATABASE_ENTRY);
+        let migrated = async {
+            let staged_pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect(&format!("sqlite://{}", staged_db.to_string_lossy()))
+                .await
+                .map_err(|e| e.to_string())?;
+            let result = run_migrations(&staged_pool).await;
+            staged_pool.close().await;
+            result
+        }
+        .await;
+
+        if let Err(e) = migrated {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(format!("备份数据库迁移失败: {}", e));
+        }
+    }
+
+    let pending = serde_json::to_string(&PendingRestore {
+        parts,
+        started: false,
+    })
+    .map_err(|e| e.to_string())?;
+    fs::write(staging.join(PENDING_RESTORE_FILE), pending).map_err(|e| e.to_string())?;
+
+    println!("备份校验通过，正在重启应用以完成恢复");
+    app.restart();
+}
@@ -1,11 +1,23 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::OnceCell;
 
+pub mod backup;
+pub mod repository;
+pub mod secrets;
 pub mod sqlite;
+pub mod workspace;
 
+static DB_POOL: OnceCell<SqlitePool> = OnceCell::const_new();
+
+/// 当前工作区的数据目录，包含 app.db、profiles/ 和 images/
+///
+/// 根目录由 `--data-dir`、`XHS_HELPER_DATA_DIR` 或便携模式决定，默认为 `~/xiaohongshu-helper-data`
 pub fn get_app_dir() -> PathBuf {
-    // 优先使用用户目录下的 xiaohongshu-helper-data
-    let mut path = dirs::home_dir().expect("Could not find home directory");
-    path.push("xiaohongshu-helper-data");
+    let location = workspace::data_location();
+    let path = workspace::workspace_dir(&location.root, &location.workspace);
 
     if !path.exists() {
         std::fs::create_dir_all(&path).expect("Could not create app directory");
@@ -30,6 +42,28 @@ pub fn get_db_path() -> String {
     format!("sqlite://{}?mode=rwc", path.to_str().expect("Invalid path"))
 }
 
+/// 获取全局共享的数据库连接池
+///
+/// 首次调用时建立连接，之后 Tauri 命令、API 服务器和 MCP 服务器共用同一个池。
+/// 开启 WAL 和 busy_timeout，避免并发写入时出现 "database is locked"。
+pub async fn pool() -> Result<&'static SqlitePool, String> {
+    DB_POOL
+        .get_or_try_init(|| async {
+            let options = SqliteConnectOptions::from_str(&get_db_path())
+                .map_err(|e| e.to_string())?
+                .create_if_missing(true)
+                .journal_mode(SqliteJournalMode::Wal)
+                .busy_timeout(Duration::from_secs(10));
+
+            SqlitePoolOptions::new()
+                .max_connections(5)
+                .connect_with(options)
+                .await
+                .map_err(|e| e.to_string())
+        })
+        .await
+}
+
 pub fn get_images_dir() -> PathBuf {
     let mut path = get_app_dir();
     path.push("images");
@@ -39,6 +73,26 @@ pub fn get_images_dir() -> PathBuf {
     path
 }
 
+/// 自动化调试截图目录
+pub fn get_debug_dir() -> PathBuf {
+    let mut path = get_app_dir();
+    path.push("debug");
+    if !path.exists() {
+        std::fs::create_dir_all(&path).expect("Could not create debug directory");
+    }
+    path
+}
+
+/// 自动化运行记录目录，每次运行一个子目录
+pub fn get_runs_dir() -> PathBuf {
+    let mut path = get_app_dir();
+    path.push("runs");
+    if !path.exists() {
+        std::fs::create_dir_all(&path).expect("Could not create runs directory");
+    }
+    path
+}
+
 pub fn clear_browser_lock(user_id: &str) {
     let base_path = get_browser_data_dir(user_id);
 
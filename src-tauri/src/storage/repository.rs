@@ -0,0 +1,1220 @@
+use crate::model::{
+    AIModel, AIModelType, AIProvider, CatchUpPolicy, MediaType, Post, PostRevision, PostStatus,
+    PostStatusChange, ProxyConfig, PublishJob, PublishJobStatus, RetryPolicy, SensitiveWord, User,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::Row;
+
+fn row_to_user(row: &SqliteRow) -> User {
+    User {
+        id: row.get("id"),
+        nickname: row.get("nickname"),
+        phone: row.get("phone"),
+        avatar: row.get("avatar"),
+        created_at: row.get::<Option<String>, _>("created_at").unwrap_or_default(),
+    }
+}
+
+fn row_to_post(row: &SqliteRow) -> Post {
+    let images_str: Option<String> = row.get("images");
+    Post {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        title: row.get("title"),
+        content: row.get::<Option<String>, _>("content").unwrap_or_default(),
+        images: images_str
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        cover_image: row.get("cover_image"),
+        media_type: row_media_type(row),
+        video_path: row.get("video_path"),
+        cover_time: row.get("cover_time"),
+        topics: row_string_list(row, "topics"),
+        mentions: row_string_list(row, "mentions"),
+        status: row
+            .get::<Option<String>, _>("status")
+            .unwrap_or_else(|| "draft".to_string()),
+        created_at: row.get::<Option<String>, _>("created_at").unwrap_or_default(),
+        version: row.get("version"),
+        updated_at: row.get("updated_at"),
+        status_updated_at: row.get("status_updated_at"),
+        last_error: row.get("last_error"),
+        failure_screenshot: row.get("failure_screenshot"),
+        failure_kind: row.get("failure_kind"),
+        note_id: row.get("note_id"),
+        note_url: row.get("note_url"),
+        warning: None,
+    }
+}
+
+fn row_media_type(row: &SqliteRow) -> MediaType {
+    row.get::<Option<String>, _>("media_type")
+        .and_then(|s| MediaType::parse(&s))
+        .unwrap_or_default()
+}
+
+fn row_string_list(row: &SqliteRow, column: &str) -> Vec<String> {
+    row.get::<Option<String>, _>(column)
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn row_to_revision(row: &SqliteRow) -> PostRevision {
+    let images_str: Option<String> = row.get("images");
+    PostRevision {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        version: row.get("version"),
+        title: row.get("title"),
+        content: row.get::<Option<String>, _>("content").unwrap_or_default(),
+        images: images_str
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        cover_image: row.get("cover_image"),
+        media_type: row_media_type(row),
+        video_path: row.get("video_path"),
+        cover_time: row.get("cover_time"),
+        topics: row_string_list(row, "topics"),
+        mentions: row_string_list(row, "mentions"),
+        source: row.get("source"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn row_to_status_change(row: &SqliteRow) -> PostStatusChange {
+    PostStatusChange {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        from_status: row.get("from_status"),
+        to_status: row.get("to_status"),
+        error: row.get("error"),
+        screenshot: row.get("screenshot"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn row_to_job(row: &SqliteRow) -> PublishJob {
+    let status: String = row.get("status");
+    let catch_up: String = row.get("catch_up");
+    PublishJob {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        local_time: row.get("local_time"),
+        timezone: row.get("timezone"),
+        scheduled_at: row.get("scheduled_at"),
+        next_run_at: row.get("next_run_at"),
+        status: PublishJobStatus::parse(&status).unwrap_or(PublishJobStatus::Failed),
+        attempts: row.get("attempts"),
+        retry: RetryPolicy {
+            max_attempts: row.get("max_attempts"),
+            backoff_seconds: row.get("backoff_seconds"),
+        },
+        catch_up: serde_json::from_str(&catch_up).unwrap_or_default(),
+        last_error: row.get("last_error"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+const JOB_COLUMNS: &str = "id, post_id, local_time, timezone, scheduled_at, next_run_at, status, attempts, max_attempts, backoff_seconds, catch_up, last_error, created_at, updated_at";
+
+const REVISION_COLUMNS: &str = "id, post_id, version, title, content, images, cover_image, media_type, video_path, cover_time, topics, mentions, source, created_at";
+
+const POST_COLUMNS: &str = "id, user_id, title, content, images, cover_image, media_type, video_path, cover_time, topics, mentions, status, created_at, version, updated_at, status_updated_at, last_error, failure_screenshot, failure_kind, note_id, note_url";
+
+// ============ 用户 ============
+
+pub struct UserRepo<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> UserRepo<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<User>, String> {
+        let rows = sqlx::query("SELECT id, nickname, phone, avatar, created_at FROM users")
+            .fetch_all(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_user).collect())
+    }
+
+    pub async fn find_by_phone(&self, phone: &str) -> Result<Option<User>, String> {
+        let row = sqlx::query(
+            "SELECT id, nickname, phone, avatar, created_at FROM users WHERE phone = ?",
+        )
+        .bind(phone)
+        .fetch_optional(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(row.as_ref().map(row_to_user))
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<User>, String> {
+        let row = sqlx::query("SELECT id, nickname, phone, avatar, created_at FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(row.as_ref().map(row_to_user))
+    }
+
+    /// 按手机号或昵称查找用户
+    pub async fn find(&self, query: &str) -> Result<Option<User>, String> {
+        let row = sqlx::query(
+            "SELECT id, nickname, phone, avatar, created_at FROM users WHERE phone = ? OR nickname = ?",
+        )
+        .bind(query)
+        .bind(query)
+        .fetch_optional(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(row.as_ref().map(row_to_user))
+    }
+
+    /// 按手机号插入或更新用户，保留已有记录的 id 和 created_at
+    pub async fn upsert(
+        &self,
+        nickname: &str,
+        phone: &str,
+        avatar: Option<&str>,
+    ) -> Result<User, String> {
+        sqlx::query(
+            "INSERT INTO users (nickname, phone, avatar, created_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(phone) DO UPDATE SET nickname = excluded.nickname, avatar = excluded.avatar",
+        )
+        .bind(nickname)
+        .bind(phone)
+        .bind(avatar)
+        .bind(chrono::Local::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        self.find_by_phone(phone)
+            .await?
+            .ok_or_else(|| format!("保存用户 {} 后未能读取记录", phone))
+    }
+
+    /// 读取账号的代理设置，密码保持数据库中的密文
+    pub async fn get_proxy(&self, phone: &str) -> Result<Option<ProxyConfig>, String> {
+        let row = sqlx::query("SELECT proxy, proxy_password FROM users WHERE phone = ?")
+            .bind(phone)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let Some(json) = row.get::<Option<String>, _>("proxy") else {
+            return Ok(None);
+        };
+        let mut proxy: ProxyConfig = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        proxy.password = row.get("proxy_password");
+        Ok(Some(proxy))
+    }
+
+    /// 保存账号的代理设置，`proxy.password` 需要已经加密；None 表示直连
+    pub async fn set_proxy(&self, phone: &str, proxy: Option<&ProxyConfig>) -> Result<(), String> {
+        let json = proxy
+            .map(|p| {
+                serde_json::to_string(&ProxyConfig {
+                    password: None,
+                    ..p.clone()
+                })
+            })
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let result = sqlx::query("UPDATE users SET proxy = ?, proxy_password = ? WHERE phone = ?")
+            .bind(json)
+            .bind(proxy.and_then(|p| p.password.as_deref()))
+            .bind(phone)
+            .execute(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if result.rows_affected() == 0 {
+            return Err(format!("账号 {} 不存在", phone));
+        }
+        Ok(())
+    }
+
+    /// 删除用户及其所有草稿
+    pub async fn delete_by_phone(&self, phone: &str) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let user_row = sqlx::query("SELECT id FROM users WHERE phone = ?")
+            .bind(phone)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Some(row) = user_row {
+            let user_id: i64 = row.get(0);
+
+            // 删除该用户的所有帖子 (处理外键约束)
+            for table in ["post_revisions", "post_status_history", "publish_jobs"] {
+                sqlx::query(&format!(
+                    "DELETE FROM {} WHERE post_id IN (SELECT id FROM posts WHERE user_id = ?)",
+                    table
+                ))
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+
+            sqlx::query("DELETE FROM posts WHERE user_id = ?")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            sqlx::query("DELETE FROM users WHERE id = ?")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+}
+
+// ============ 草稿 ============
+
+/// 草稿中可编辑的字段
+#[derive(Debug, Clone, Default)]
+pub struct PostEdit {
+    pub title: String,
+    pub content: String,
+    pub images: Vec<String>,
+    pub cover_image: Option<String>,
+    pub media_type: MediaType,
+    pub video_path: Option<String>,
+    pub cover_time: Option<f64>,
+    pub topics: Vec<String>,
+    pub mentions: Vec<String>,
+}
+
+impl From<Post> for PostEdit {
+    fn from(post: Post) -> Self {
+        Self {
+            title: post.title,
+            content: post.content,
+            images: post.images,
+            cover_image: post.cover_image,
+            media_type: post.media_type,
+            video_path: post.video_path,
+            cover_time: post.cover_time,
+            topics: post.topics,
+            mentions: post.mentions,
+        }
+    }
+}
+
+impl From<PostRevision> for PostEdit {
+    fn from(revision: PostRevision) -> Self {
+        Self {
+            title: revision.title,
+            content: revision.content,
+            images: revision.images,
+            cover_image: revision.cover_image,
+            media_type: revision.media_type,
+            video_path: revision.video_path,
+            cover_time: revision.cover_time,
+            topics: revision.topics,
+            mentions: revision.mentions,
+        }
+    }
+}
+
+impl PostEdit {
+    fn lists_json(&self) -> Result<(String, String, String), String> {
+        let json = |v: &Vec<String>| serde_json::to_string(v).map_err(|e| e.to_string());
+        Ok((json(&self.images)?, json(&self.topics)?, json(&self.mentions)?))
+    }
+}
+
+pub struct PostRepo<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> PostRepo<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, user_id: i64, edit: &PostEdit) -> Result<i64, String> {
+        let (images_json, topics_json, mentions_json) = edit.lists_json()?;
+        let now = chrono::Local::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let res = sqlx::query(
+            "INSERT INTO posts (user_id, title, content, images, cover_image, media_type, video_path, cover_time, topics, mentions, status, created_at, version) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, 1)",
+        )
+        .bind(user_id)
+        .bind(&edit.title)
+        .bind(&edit.content)
+        .bind(&images_json)
+        .bind(&edit.cover_image)
+        .bind(edit.media_type.as_str())
+        .bind(&edit.video_path)
+        .bind(edit.cover_time)
+        .bind(&topics_json)
+        .bind(&mentions_json)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        let post_id = res.last_insert_rowid();
+
+        sqlx::query(
+            "INSERT INTO post_revisions (post_id, version, title, content, images, cover_image, media_type, video_path, cover_time, topics, mentions, source, created_at) VALUES (?, 1, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'manual', ?)",
+        )
+        .bind(post_id)
+        .bind(&edit.title)
+        .bind(&edit.content)
+        .bind(&images_json)
+        .bind(&edit.cover_image)
+        .bind(edit.media_type.as_str())
+        .bind(&edit.video_path)
+        .bind(edit.cover_time)
+        .bind(&topics_json)
+        .bind(&mentions_json)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(post_id)
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<Post>, String> {
+        let row = sqlx::query(&format!("SELECT {} FROM posts WHERE id = ?", POST_COLUMNS))
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(row.as_ref().map(row_to_post))
+    }
+
+    pub async fn list_by_user(&self, user_id: i64) -> Result<Vec<Post>, String> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM posts WHERE user_id = ? ORDER BY created_at DESC",
+            POST_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_post).collect())
+    }
+
+    /// 保存草稿的新版本
+    ///
+    /// `expected_version` 必须等于数据库中的当前版本，否则说明草稿已在别处被修改，
+    /// 返回冲突错误而不是覆盖对方的修改。每次保存都会写入一条修订记录。
+    pub async fn update(
+        &self,
+        id: i64,
+        expected_version: i64,
+        edit: &PostEdit,
+        source: &str,
+    ) -> Result<Post, String> {
+        let (images_json, topics_json, mentions_json) = edit.lists_json()?;
+        let now = chrono::Local::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let res = sqlx::query(
+            "UPDATE posts SET title = ?, content = ?, images = ?, cover_image = ?, media_type = ?, video_path = ?, cover_time = ?, topics = ?, mentions = ?, version = version + 1, updated_at = ? WHERE id = ? AND version = ? AND status NOT IN ('queued', 'publishing')",
+        )
+        .bind(&edit.title)
+        .bind(&edit.content)
+        .bind(&images_json)
+        .bind(&edit.cover_image)
+        .bind(edit.media_type.as_str())
+        .bind(&edit.video_path)
+        .bind(edit.cover_time)
+        .bind(&topics_json)
+        .bind(&mentions_json)
+        .bind(&now)
+        .bind(id)
+        .bind(expected_version)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if res.rows_affected() == 0 {
+            let current = sqlx::query("SELECT version, status FROM posts WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+            return Err(match current {
+                Some(row) if row.get::<i64, _>(0) == expected_version => format!(
+                    "笔记处于 {} 状态，不能编辑",
+                    row.get::<String, _>(1)
+                ),
+                Some(row) => format!(
+                    "草稿已被修改（当前版本 v{}，提交的版本 v{}），请刷新后重试",
+                    row.get::<i64, _>(0),
+                    expected_version
+                ),
+                None => "草稿不存在".to_string(),
+            });
+        }
+
+        sqlx::query(
+            "INSERT INTO post_revisions (post_id, version, title, content, images, cover_image, media_type, video_path, cover_time, topics, mentions, source, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(expected_version + 1)
+        .bind(&edit.title)
+        .bind(&edit.content)
+        .bind(&images_json)
+        .bind(&edit.cover_image)
+        .bind(edit.media_type.as_str())
+        .bind(&edit.video_path)
+        .bind(edit.cover_time)
+        .bind(&topics_json)
+        .bind(&mentions_json)
+        .bind(source)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let row = sqlx::query(&format!("SELECT {} FROM posts WHERE id = ?", POST_COLUMNS))
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(row_to_post(&row))
+    }
+
+    /// 按版本从新到旧列出修订记录
+    pub async fn list_revisions(&self, post_id: i64) -> Result<Vec<PostRevision>, String> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM post_revisions WHERE post_id = ? ORDER BY version DESC",
+            REVISION_COLUMNS
+        ))
+        .bind(post_id)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_revision).collect())
+    }
+
+    pub async fn get_revision(
+        &self,
+        post_id: i64,
+        version: i64,
+    ) -> Result<Option<PostRevision>, String> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM post_revisions WHERE post_id = ? AND version = ?",
+            REVISION_COLUMNS
+        ))
+        .bind(post_id)
+        .bind(version)
+        .fetch_optional(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(row.as_ref().map(row_to_revision))
+    }
+
+    /// 把草稿恢复到某个历史版本，恢复本身也会生成一个新版本
+    pub async fn restore_revision(
+        &self,
+        post_id: i64,
+        version: i64,
+        expected_version: i64,
+    ) -> Result<Post, String> {
+        let revision = self
+            .get_revision(post_id, version)
+            .await?
+            .ok_or_else(|| format!("版本 v{} 不存在", version))?;
+        self.update(post_id, expected_version, &revision.into(), "restore")
+            .await
+    }
+
+    /// 按状态机流转笔记状态并记录历史
+    ///
+    /// 使用 `WHERE status = 当前状态` 做比较交换，同一篇笔记不会被两个任务同时发布。
+    pub async fn transition(
+        &self,
+        id: i64,
+        to: PostStatus,
+        error: Option<&str>,
+        screenshot: Option<&str>,
+    ) -> Result<Post, String> {
+        let now = chrono::Local::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let row = sqlx::query("SELECT status FROM posts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "笔记不存在".to_string())?;
+        let current: String = row
+            .get::<Option<String>, _>(0)
+            .unwrap_or_else(|| "draft".to_string());
+        let from = PostStatus::parse(&current)
+            .ok_or_else(|| format!("未知的笔记状态 {}", current))?;
+        if !from.can_transition_to(to) {
+            return Err(format!(
+                "笔记状态不能从 {} 变为 {}",
+                from.as_str(),
+                to.as_str()
+            ));
+        }
+
+        // 失败信息只在进入 failed 时写入，重新排队时清空
+        let res = sqlx::query(
+            "UPDATE posts SET status = ?, status_updated_at = ?, last_error = ?, failure_screenshot = ?, failure_kind = NULL WHERE id = ? AND status = ?",
+        )
+        .bind(to.as_str())
+        .bind(&now)
+        .bind(error)
+        .bind(screenshot)
+        .bind(id)
+        .bind(&current)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if res.rows_affected() == 0 {
+            return Err("笔记状态已被其他任务修改，请重试".to_string());
+        }
+
+        sqlx::query(
+            "INSERT INTO post_status_history (post_id, from_status, to_status, error, screenshot, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(from.as_str())
+        .bind(to.as_str())
+        .bind(error)
+        .bind(screenshot)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let row = sqlx::query(&format!("SELECT {} FROM posts WHERE id = ?", POST_COLUMNS))
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(row_to_post(&row))
+    }
+
+    /// 记录发布结果：成功时的笔记 id 和链接，或失败类型
+    pub async fn set_publish_details(
+        &self,
+        id: i64,
+        note_id: Option<&str>,
+        note_url: Option<&str>,
+        failure_kind: Option<&str>,
+    ) -> Result<Post, String> {
+        sqlx::query(
+            "UPDATE posts SET note_id = COALESCE(?, note_id), note_url = COALESCE(?, note_url), failure_kind = ? WHERE id = ?",
+        )
+        .bind(note_id)
+        .bind(note_url)
+        .bind(failure_kind)
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        self.get(id)
+            .await?
+            .ok_or_else(|| "笔记不存在".to_string())
+    }
+
+    /// 读取笔记保存的发布设置，没有保存时为 None
+    pub async fn get_publish_options<T: DeserializeOwned>(
+        &self,
+        id: i64,
+    ) -> Result<Option<T>, String> {
+        let json: Option<String> =
+            sqlx::query_scalar("SELECT publish_options FROM posts WHERE id = ?")
+                .bind(id)
+                .fetch_optional(self.pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .flatten();
+        json.map(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    /// 保存笔记的发布设置，立即发布和定时任务都按它发布；None 表示使用平台默认
+    pub async fn set_publish_options<T: Serialize>(
+        &self,
+        id: i64,
+        options: Option<&T>,
+    ) -> Result<(), String> {
+        let json = options
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let result = sqlx::query(
+            "UPDATE posts SET publish_options = ? WHERE id = ? AND status != 'publishing'",
+        )
+        .bind(json)
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if result.rows_affected() == 0 {
+            return Err("笔记不存在或正在发布".to_string());
+        }
+        Ok(())
+    }
+
+    /// 把一篇笔记保存的发布设置复制到另一篇，例如为其他账号创建的副本
+    pub async fn copy_publish_options(&self, from: i64, to: i64) -> Result<(), String> {
+        let options: Option<serde_json::Value> = self.get_publish_options(from).await?;
+        self.set_publish_options(to, options.as_ref()).await
+    }
+
+    pub async fn list_status_history(&self, post_id: i64) -> Result<Vec<PostStatusChange>, String> {
+        let rows = sqlx::query(
+            "SELECT id, post_id, from_status, to_status, error, screenshot, created_at FROM post_status_history WHERE post_id = ? ORDER BY id",
+        )
+        .bind(post_id)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_status_change).collect())
+    }
+
+    /// 把上次异常退出时停留在 publishing 的笔记标记为失败，返回处理的数量
+    ///
+    /// 无法确定这些笔记是否已经发出，标记为 failed 由用户确认后重新排队，避免重复发布。
+    pub async fn recover_interrupted(&self) -> Result<usize, String> {
+        let rows = sqlx::query("SELECT id FROM posts WHERE status = 'publishing'")
+            .fetch_all(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        for row in &rows {
+            self.transition(
+                row.get(0),
+                PostStatus::Failed,
+                Some("发布过程中应用意外退出，请确认笔记是否已发出后再重试"),
+                None,
+            )
+            .await?;
+        }
+        Ok(rows.len())
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM publish_jobs WHERE post_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM post_status_history WHERE post_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM post_revisions WHERE post_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM posts WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+}
+
+// ============ 定时发布 ============
+
+pub struct PublishJobRepo<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> PublishJobRepo<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 创建定时任务，`scheduled_at` 为 UTC 时间
+    pub async fn create(
+        &self,
+        post_id: i64,
+        local_time: &str,
+        timezone: &str,
+        scheduled_at: &str,
+        retry: RetryPolicy,
+        catch_up: CatchUpPolicy,
+    ) -> Result<PublishJob, String> {
+        let now = chrono::Local::now().to_rfc3339();
+        let catch_up = serde_json::to_string(&catch_up).map_err(|e| e.to_string())?;
+        let res = sqlx::query(
+            "INSERT INTO publish_jobs (post_id, local_time, timezone, scheduled_at, next_run_at, status, attempts, max_attempts, backoff_seconds, catch_up, created_at, updated_at) VALUES (?, ?, ?, ?, ?, 'pending', 0, ?, ?, ?, ?, ?)",
+        )
+        .bind(post_id)
+        .bind(local_time)
+        .bind(timezone)
+        .bind(scheduled_at)
+        .bind(scheduled_at)
+        .bind(retry.max_attempts)
+        .bind(retry.backoff_seconds)
+        .bind(catch_up)
+        .bind(&now)
+        .bind(&now)
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        self.get(res.last_insert_rowid())
+            .await?
+            .ok_or_else(|| "创建定时任务失败".to_string())
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<PublishJob>, String> {
+        let row = sqlx::query(&format!("SELECT {} FROM publish_jobs WHERE id = ?", JOB_COLUMNS))
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(row.as_ref().map(row_to_job))
+    }
+
+    pub async fn list(&self, status: Option<PublishJobStatus>) -> Result<Vec<PublishJob>, String> {
+        let rows = match status {
+            Some(status) => sqlx::query(&format!(
+                "SELECT {} FROM publish_jobs WHERE status = ? ORDER BY next_run_at",
+                JOB_COLUMNS
+            ))
+            .bind(status.as_str())
+            .fetch_all(self.pool)
+            .await,
+            None => sqlx::query(&format!(
+                "SELECT {} FROM publish_jobs ORDER BY next_run_at DESC",
+                JOB_COLUMNS
+            ))
+            .fetch_all(self.pool)
+            .await,
+        }
+        .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_job).collect())
+    }
+
+    /// 到期的待执行任务，按执行时间排序
+    pub async fn due(&self, now: &str) -> Result<Vec<PublishJob>, String> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM publish_jobs WHERE status = 'pending' AND next_run_at <= ? ORDER BY next_run_at",
+            JOB_COLUMNS
+        ))
+        .bind(now)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_job).collect())
+    }
+
+    /// 领取任务并累加尝试次数，任务已被取消或领取时返回 false
+    pub async fn claim(&self, id: i64) -> Result<bool, String> {
+        let res = sqlx::query(
+            "UPDATE publish_jobs SET status = 'running', attempts = attempts + 1, updated_at = ? WHERE id = ? AND status = 'pending'",
+        )
+        .bind(chrono::Local::now().to_rfc3339())
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    /// 取消尚未执行的任务，任务已开始或已结束时返回 false
+    pub async fn cancel(&self, id: i64) -> Result<bool, String> {
+        let res = sqlx::query(
+            "UPDATE publish_jobs SET status = 'cancelled', updated_at = ? WHERE id = ? AND status = 'pending'",
+        )
+        .bind(chrono::Local::now().to_rfc3339())
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    /// 更新任务状态；`next_run_at` 不为 None 时同时推迟下次执行时间
+    pub async fn set_status(
+        &self,
+        id: i64,
+        status: PublishJobStatus,
+        error: Option<&str>,
+        next_run_at: Option<&str>,
+    ) -> Result<(), String> {
+        sqlx::query(
+            "UPDATE publish_jobs SET status = ?, last_error = ?, next_run_at = COALESCE(?, next_run_at), updated_at = ? WHERE id = ?",
+        )
+        .bind(status.as_str())
+        .bind(error)
+        .bind(next_run_at)
+        .bind(chrono::Local::now().to_rfc3339())
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 把上次异常退出时仍在执行的任务及其笔记标记为失败，返回处理的任务数量
+    pub async fn fail_interrupted(&self) -> Result<u64, String> {
+        const ERROR: &str = "发布过程中应用意外退出";
+        let now = chrono::Local::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        // 笔记可能停在 queued 或 publishing，不处理会一直无法编辑
+        let posts = sqlx::query(
+            "SELECT id, status FROM posts WHERE status IN ('queued', 'publishing')
+             AND id IN (SELECT post_id FROM publish_jobs WHERE status = 'running')",
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        for row in &posts {
+            let post_id: i64 = row.get(0);
+            let from: String = row.get(1);
+            sqlx::query(
+                "UPDATE posts SET status = 'failed', status_updated_at = ?, last_error = ?, failure_screenshot = NULL, failure_kind = NULL WHERE id = ?",
+            )
+            .bind(&now)
+            .bind(ERROR)
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+            sqlx::query(
+                "INSERT INTO post_status_history (post_id, from_status, to_status, error, screenshot, created_at) VALUES (?, ?, 'failed', ?, NULL, ?)",
+            )
+            .bind(post_id)
+            .bind(&from)
+            .bind(ERROR)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+
+        let res = sqlx::query(
+            "UPDATE publish_jobs SET status = 'failed', last_error = ?, updated_at = ? WHERE status = 'running'",
+        )
+        .bind(ERROR)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(res.rows_affected())
+    }
+}
+
+// ============ 敏感词 ============
+
+pub struct SensitiveWordRepo<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SensitiveWordRepo<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<SensitiveWord>, String> {
+        let rows = sqlx::query(
+            "SELECT id, word, category, replacement, enabled FROM sensitive_words ORDER BY category, word",
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows
+            .iter()
+            .map(|row| SensitiveWord {
+                id: row.get("id"),
+                word: row.get("word"),
+                category: row.get("category"),
+                replacement: row.get("replacement"),
+                enabled: row.get::<i64, _>("enabled") != 0,
+            })
+            .collect())
+    }
+
+    pub async fn list_enabled(&self) -> Result<Vec<SensitiveWord>, String> {
+        Ok(self.list().await?.into_iter().filter(|w| w.enabled).collect())
+    }
+
+    /// 新增或修改一个词，同一分类下的同一个词只保留一条
+    pub async fn save(&self, word: &SensitiveWord) -> Result<i64, String> {
+        let text = word.word.trim();
+        if text.is_empty() {
+            return Err("敏感词不能为空".to_string());
+        }
+        let enabled = if word.enabled { 1 } else { 0 };
+        if let Some(id) = word.id {
+            sqlx::query(
+                "UPDATE sensitive_words SET word = ?, category = ?, replacement = ?, enabled = ? WHERE id = ?",
+            )
+            .bind(text)
+            .bind(&word.category)
+            .bind(&word.replacement)
+            .bind(enabled)
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+            return Ok(id);
+        }
+
+        sqlx::query(
+            "INSERT INTO sensitive_words (word, category, replacement, enabled, created_at) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(word, category) DO UPDATE SET replacement = excluded.replacement, enabled = excluded.enabled",
+        )
+        .bind(text)
+        .bind(&word.category)
+        .bind(&word.replacement)
+        .bind(enabled)
+        .bind(chrono::Local::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        let row = sqlx::query("SELECT id FROM sensitive_words WHERE word = ? AND category = ?")
+            .bind(text)
+            .bind(&word.category)
+            .fetch_one(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(row.get(0))
+    }
+
+    /// 批量导入一个分类的词，返回新增的数量
+    pub async fn import(
+        &self,
+        category: &str,
+        entries: &[(String, Option<String>)],
+    ) -> Result<usize, String> {
+        let now = chrono::Local::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let mut added = 0;
+        for (word, replacement) in entries {
+            let res = sqlx::query(
+                "INSERT OR IGNORE INTO sensitive_words (word, category, replacement, enabled, created_at) VALUES (?, ?, ?, 1, ?)",
+            )
+            .bind(word)
+            .bind(category)
+            .bind(replacement)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+            added += res.rows_affected() as usize;
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(added)
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<(), String> {
+        sqlx::query("DELETE FROM sensitive_words WHERE id = ?")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+// ============ 配置 ============
+
+pub struct ConfigRepo<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ConfigRepo<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let row = sqlx::query("SELECT value FROM config WHERE key = ?")
+            .bind(key)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(row.and_then(|r| r.get(0)))
+    }
+
+    pub async fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        sqlx::query("INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)")
+            .bind(key)
+            .bind(value)
+            .execute(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+// ============ AI 提供商 ============
+
+pub struct ProviderRepo<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ProviderRepo<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn load_models(&self, provider_id: i64) -> Result<Vec<AIModel>, String> {
+        let rows = sqlx::query("SELECT id, name, model_type FROM ai_models WHERE provider_id = ?")
+            .bind(provider_id)
+            .fetch_all(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let m_type: String = row.get(2);
+                AIModel {
+                    id: Some(row.get(0)),
+                    provider_id: Some(provider_id),
+                    name: row.get(1),
+                    model_type: if m_type == "text" {
+                        AIModelType::Text
+                    } else {
+                        AIModelType::Image
+                    },
+                    supports_structured_output: None,
+                    test_status: None,
+                }
+            })
+            .collect())
+    }
+
+    async fn row_to_provider(&self, row: &SqliteRow) -> Result<AIProvider, String> {
+        let id: i64 = row.get(0);
+        Ok(AIProvider {
+            id: Some(id),
+            name: row.get(1),
+            api_key: row.get(2),
+            base_url: row.get(3),
+            models: self.load_models(id).await?,
+        })
+    }
+
+    pub async fn list(&self) -> Result<Vec<AIProvider>, String> {
+        let rows = sqlx::query("SELECT id, name, api_key, base_url FROM ai_providers")
+            .fetch_all(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut providers = Vec::with_capacity(rows.len());
+        for row in &rows {
+            providers.push(self.row_to_provider(row).await?);
+        }
+        Ok(providers)
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<AIProvider>, String> {
+        let row = sqlx::query("SELECT id, name, api_key, base_url FROM ai_providers WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_provider(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 新增或更新提供商，模型列表整体替换
+    pub async fn save(&self, provider: &AIProvider) -> Result<i64, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let provider_id = if let Some(id) = provider.id {
+            sqlx::query("UPDATE ai_providers SET name = ?, api_key = ?, base_url = ? WHERE id = ?")
+                .bind(&provider.name)
+                .bind(&provider.api_key)
+                .bind(&provider.base_url)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // Delete old models and re-insert (simpler than update logic)
+            sqlx::query("DELETE FROM ai_models WHERE provider_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            id
+        } else {
+            let res =
+                sqlx::query("INSERT INTO ai_providers (name, api_key, base_url) VALUES (?, ?, ?)")
+                    .bind(&provider.name)
+                    .bind(&provider.api_key)
+                    .bind(&provider.base_url)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            res.last_insert_rowid()
+        };
+
+        for model in &provider.models {
+            let m_type = match model.model_type {
+                AIModelType::Text => "text",
+                AIModelType::Image => "image",
+            };
+            sqlx::query("INSERT INTO ai_models (provider_id, name, model_type) VALUES (?, ?, ?)")
+                .bind(provider_id)
+                .bind(&model.name)
+                .bind(m_type)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(provider_id)
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        sqlx::query("DELETE FROM ai_models WHERE provider_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        sqlx::query("DELETE FROM ai_providers WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,464 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use lazy_static::lazy_static;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// 加密后的密文前缀，没有该前缀的值视为旧版本留下的明文
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+/// 返回给前端、REST 和 MCP 的脱敏占位符
+pub const MASKED_SECRET: &str = "********";
+/// 口令模式下用于非交互解锁（例如定时发布）的环境变量
+pub const PASSPHRASE_ENV: &str = "XHS_HELPER_MASTER_PASSPHRASE";
+/// 指定外部密钥文件路径的环境变量，例如放在 U 盘上
+pub const KEY_FILE_ENV: &str = "XHS_HELPER_KEY_FILE";
+
+/// 以加密形式保存在 config 表中的键
+pub const SECRET_CONFIG_KEYS: &[&str] = &["api_key", "mcp_token"];
+/// 以加密形式保存的表字段
+const SECRET_COLUMNS: &[(&str, &str)] = &[("ai_providers", "api_key"), ("users", "proxy_password")];
+
+pub const SECRETS_FILE: &str = "secrets.json";
+pub const KEY_FILE: &str = "master.key";
+
+/// 更换密钥时先写入的临时文件后缀，数据库提交后再改名生效
+const PENDING_SUFFIX: &str = ".pending";
+/// config 表中记录当前密钥校验值的键，用来判断未完成的密钥更换是否已经提交
+const CHECK_CONFIG_KEY: &str = "secrets_check";
+
+const PBKDF2_ROUNDS: u32 = 600_000;
+const CHECK_PLAINTEXT: &str = "xiaohongshu-helper";
+
+lazy_static! {
+    static ref MASTER_KEY: RwLock<Option<[u8; 32]>> = RwLock::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMode {
+    /// 随机生成的 32 字节密钥文件
+    KeyFile,
+    /// 由主口令派生密钥，每次启动需要解锁
+    Passphrase,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SecretsConfig {
+    mode: KeyMode,
+    /// 口令模式的 PBKDF2 盐
+    salt: Option<String>,
+    /// 用当前密钥加密的校验值，用于判断口令是否正确
+    check: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SecretsStatus {
+    pub mode: KeyMode,
+    pub unlocked: bool,
+}
+
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+pub fn encrypt_with(key: &[u8; 32], plain: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut nonce = [0u8; 12];
+    rand::rng().fill(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plain.as_bytes())
+        .map_err(|_| "加密失败".to_string())?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_PREFIX,
+        general_purpose::STANDARD.encode(payload)
+    ))
+}
+
+pub fn decrypt_with(key: &[u8; 32], stored: &str) -> Result<String, String> {
+    let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+        return Ok(stored.to_string());
+    };
+    let payload = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("密文格式错误: {}", e))?;
+    if payload.len() < 12 {
+        return Err("密文格式错误".to_string());
+    }
+    let (nonce, ciphertext) = payload.split_at(12);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "解密失败，主密钥不匹配".to_string())?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// 列表中展示的脱敏值，不需要解密
+pub fn mask_secret(stored: &str) -> String {
+    if stored.is_empty() {
+        String::new()
+    } else {
+        MASKED_SECRET.to_string()
+    }
+}
+
+/// 前端原样回传的脱敏值，表示“保持不变”
+pub fn is_masked(value: &str) -> bool {
+    value == MASKED_SECRET
+}
+
+fn current_key() -> Result<[u8; 32], String> {
+    MASTER_KEY
+        .read()
+        .unwrap()
+        .ok_or_else(|| "密钥未解锁，请先输入主口令".to_string())
+}
+
+pub fn encrypt_secret(plain: &str) -> Result<String, String> {
+    encrypt_with(&current_key()?, plain)
+}
+
+pub fn decrypt_secret(stored: &str) -> Result<String, String> {
+    if !is_encrypted(stored) {
+        return Ok(stored.to_string());
+    }
+    decrypt_with(&current_key()?, stored)
+}
+
+fn secrets_file(app_dir: &Path) -> PathBuf {
+    app_dir.join(SECRETS_FILE)
+}
+
+/// 密钥文件的位置，`XHS_HELPER_KEY_FILE` 优先
+pub fn key_file(app_dir: &Path) -> PathBuf {
+    std::env::var(KEY_FILE_ENV)
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| app_dir.join(KEY_FILE))
+}
+
+fn pending(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(PENDING_SUFFIX);
+    PathBuf::from(name)
+}
+
+fn read_config_file(path: &Path) -> Option<SecretsConfig> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn read_config(app_dir: &Path) -> Option<SecretsConfig> {
+    read_config_file(&secrets_file(app_dir))
+}
+
+fn write_config_file(path: &Path, config: &SecretsConfig) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+fn write_config(app_dir: &Path, config: &SecretsConfig) -> Result<(), String> {
+    write_config_file(&secrets_file(app_dir), config)
+}
+
+fn rename(from: &Path, to: &Path) -> Result<(), String> {
+    std::fs::rename(from, to).map_err(|e| format!("无法保存 {:?}: {}", to, e))
+}
+
+fn read_key_file(path: &Path) -> Result<[u8; 32], String> {
+    let encoded = std::fs::read_to_string(path)
+        .map_err(|e| format!("无法读取密钥文件 {:?}: {}", path, e))?;
+    let bytes = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("密钥文件格式错误: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|_| "密钥文件长度错误".to_string())
+}
+
+fn random_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    rand::rng().fill(&mut key);
+    key
+}
+
+fn write_key_file(path: &Path, key: &[u8; 32]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, general_purpose::STANDARD.encode(key)).map_err(|e| e.to_string())
+}
+
+fn verify(config: &SecretsConfig, key: &[u8; 32]) -> bool {
+    decrypt_with(key, &config.check)
+        .map(|s| s == CHECK_PLAINTEXT)
+        .unwrap_or(false)
+}
+
+/// 启动时加载主密钥
+///
+/// 首次运行生成密钥文件；口令模式下尝试使用环境变量解锁，否则保持锁定直到调用 `unlock_secrets`。
+pub fn init(app_dir: &Path) -> Result<SecretsStatus, String> {
+    let config = match read_config(app_dir) {
+        Some(config) => config,
+        None => {
+            let path = key_file(app_dir);
+            let key = if path.exists() {
+                read_key_file(&path)?
+            } else {
+                let key = random_key();
+                write_key_file(&path, &key)?;
+                key
+            };
+            let config = SecretsConfig {
+                mode: KeyMode::KeyFile,
+                salt: None,
+                check: encrypt_with(&key, CHECK_PLAINTEXT)?,
+            };
+            write_config(app_dir, &config)?;
+            config
+        }
+    };
+
+    let key = match config.mode {
+        KeyMode::KeyFile => Some(read_key_file(&key_file(app_dir))?),
+        KeyMode::Passphrase => std::env::var(PASSPHRASE_ENV)
+            .ok()
+            .and_then(|pass| unlock_with(&config, &pass).ok()),
+    };
+
+    if let Some(key) = key {
+        if !verify(&config, &key) {
+            return Err("主密钥与已加密数据不匹配".to_string());
+        }
+        *MASTER_KEY.write().unwrap() = Some(key);
+    }
+
+    Ok(SecretsStatus {
+        mode: config.mode,
+        unlocked: MASTER_KEY.read().unwrap().is_some(),
+    })
+}
+
+fn unlock_with(config: &SecretsConfig, passphrase: &str) -> Result<[u8; 32], String> {
+    let salt = config
+        .salt
+        .as_deref()
+        .ok_or("缺少口令盐值")
+        .and_then(|s| general_purpose::STANDARD.decode(s).map_err(|_| "口令盐值格式错误"))?;
+    let key = derive_key(passphrase, &salt);
+    if verify(config, &key) {
+        Ok(key)
+    } else {
+        Err("主口令错误".to_string())
+    }
+}
+
+/// 把旧版本留下的明文密钥加密保存
+pub async fn encrypt_plaintext_secrets(pool: &SqlitePool) -> Result<(), String> {
+    reencrypt_all(pool, None).await
+}
+
+/// 完成上次中断的密钥更换，需要在 `init` 之前调用
+///
+/// 数据库中的校验值与临时配置一致说明已经用新密钥重新加密，改名生效；
+/// 否则数据库仍使用旧密钥，丢弃临时文件。
+pub async fn recover_pending_change(app_dir: &Path, pool: &SqlitePool) -> Result<(), String> {
+    let pending_config = pending(&secrets_file(app_dir));
+    let Some(config) = read_config_file(&pending_config) else {
+        let _ = std::fs::remove_file(&pending_config);
+        let _ = std::fs::remove_file(pending(&key_file(app_dir)));
+        return Ok(());
+    };
+
+    let committed = sqlx::query("SELECT value FROM config WHERE key = ?")
+        .bind(CHECK_CONFIG_KEY)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|r| r.get::<Option<String>, _>(0))
+        .is_some_and(|check| check == config.check);
+    if committed {
+        println!("完成上次中断的主密钥更换");
+        finish_change(app_dir, &config)
+    } else {
+        let _ = std::fs::remove_file(&pending_config);
+        let _ = std::fs::remove_file(pending(&key_file(app_dir)));
+        Ok(())
+    }
+}
+
+/// 把临时的密钥文件和配置改名生效，旧密钥文件在改名成功前一直保留
+fn finish_change(app_dir: &Path, config: &SecretsConfig) -> Result<(), String> {
+    let key_path = key_file(app_dir);
+    if config.mode == KeyMode::KeyFile {
+        rename(&pending(&key_path), &key_path)?;
+    }
+    rename(&pending(&secrets_file(app_dir)), &secrets_file(app_dir))?;
+    // 口令模式不再需要密钥文件，包括 XHS_HELPER_KEY_FILE 指定的外部文件
+    if config.mode == KeyMode::Passphrase {
+        let _ = std::fs::remove_file(&key_path);
+    }
+    Ok(())
+}
+
+/// 用新密钥重新加密所有密钥并记录新的校验值；`new_key` 为 None 时只加密明文
+async fn reencrypt_all(
+    pool: &SqlitePool,
+    new_key: Option<(&[u8; 32], &str)>,
+) -> Result<(), String> {
+    let old_key = current_key()?;
+    let (new_key, new_check) = new_key.unzip();
+    let target_key = new_key.unwrap_or(&old_key);
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    for (table, column) in SECRET_COLUMNS {
+        let rows = sqlx::query(&format!(
+            "SELECT id, {} FROM {} WHERE {} IS NOT NULL",
+            column, table, column
+        ))
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        for row in rows {
+            let id: i64 = row.get(0);
+            let stored: String = row.get(1);
+            if new_key.is_none() && is_encrypted(&stored) {
+                continue;
+            }
+            let plain = decrypt_with(&old_key, &stored)?;
+            sqlx::query(&format!("UPDATE {} SET {} = ? WHERE id = ?", table, column))
+                .bind(encrypt_with(target_key, &plain)?)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    for key in SECRET_CONFIG_KEYS {
+        let row = sqlx::query("SELECT value FROM config WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(stored) = row.and_then(|r| r.get::<Option<String>, _>(0)) else {
+            continue;
+        };
+        if new_key.is_none() && is_encrypted(&stored) {
+            continue;
+        }
+        let plain = decrypt_with(&old_key, &stored)?;
+        sqlx::query("UPDATE config SET value = ? WHERE key = ?")
+            .bind(encrypt_with(target_key, &plain)?)
+            .bind(key)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(check) = new_check {
+        sqlx::query("INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)")
+            .bind(CHECK_CONFIG_KEY)
+            .bind(check)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_secrets_status() -> Result<SecretsStatus, String> {
+    let mode = read_config(&crate::storage::get_app_dir())
+        .map(|c| c.mode)
+        .unwrap_or(KeyMode::KeyFile);
+    Ok(SecretsStatus {
+        mode,
+        unlocked: MASTER_KEY.read().unwrap().is_some(),
+    })
+}
+
+#[tauri::command]
+pub async fn unlock_secrets(passphrase: String) -> Result<(), String> {
+    let app_dir = crate::storage::get_app_dir();
+    let config = read_config(&app_dir).ok_or("尚未初始化密钥")?;
+    if config.mode != KeyMode::Passphrase {
+        return Ok(());
+    }
+    let key = tokio::task::spawn_blocking(move || unlock_with(&config, &passphrase))
+        .await
+        .map_err(|e| e.to_string())??;
+    *MASTER_KEY.write().unwrap() = Some(key);
+
+    encrypt_plaintext_secrets(crate::storage::pool().await?).await
+}
+
+/// 设置或取消主口令，并用新密钥重新加密所有已保存的密钥
+///
+/// `passphrase` 为 None 时切换回密钥文件模式。
+#[tauri::command]
+pub async fn set_master_passphrase(passphrase: Option<String>) -> Result<(), String> {
+    let app_dir = crate::storage::get_app_dir();
+    let pool = crate::storage::pool().await?;
+
+    let (config, new_key) = match passphrase.filter(|p| !p.is_empty()) {
+        Some(pass) => {
+            let mut salt = [0u8; 16];
+            rand::rng().fill(&mut salt);
+            let key = tokio::task::spawn_blocking(move || derive_key(&pass, &salt))
+                .await
+                .map_err(|e| e.to_string())?;
+            let config = SecretsConfig {
+                mode: KeyMode::Passphrase,
+                salt: Some(general_purpose::STANDARD.encode(salt)),
+                check: encrypt_with(&key, CHECK_PLAINTEXT)?,
+            };
+            (config, key)
+        }
+        None => {
+            let key = random_key();
+            let config = SecretsConfig {
+                mode: KeyMode::KeyFile,
+                salt: None,
+                check: encrypt_with(&key, CHECK_PLAINTEXT)?,
+            };
+            (config, key)
+        }
+    };
+
+    // 先把新配置和密钥写到临时文件，重新加密提交后再改名生效。
+    // 中途失败或退出时，启动时由 recover_pending_change 根据数据库中的校验值收尾。
+    let pending_config = pending(&secrets_file(&app_dir));
+    let pending_key = pending(&key_file(&app_dir));
+    write_config_file(&pending_config, &config)?;
+    if config.mode == KeyMode::KeyFile {
+        write_key_file(&pending_key, &new_key)?;
+    }
+    if let Err(e) = reencrypt_all(pool, Some((&new_key, &config.check))).await {
+        let _ = std::fs::remove_file(&pending_config);
+        let _ = std::fs::remove_file(&pending_key);
+        return Err(e);
+    }
+    *MASTER_KEY.write().unwrap() = Some(new_key);
+    finish_change(&app_dir, &config).map_err(|e| format!("{}，重启应用后会自动完成更换", e))
+}
@@ -1,64 +1,36 @@
-use crate::storage::get_db_path;
-use sqlx::sqlite::SqlitePoolOptions;
+use crate::storage::{get_app_dir, pool};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
 use tauri_plugin_sql::{Migration, MigrationKind};
 
-pub async fn initialize_database() -> Result<(), String> {
-    let db_url = get_db_path();
-    let pool = SqlitePoolOptions::new()
-        .connect(&db_url)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    sqlx::query(
-        "
-        CREATE TABLE IF NOT EXISTS users (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            nickname TEXT NOT NULL,
-            phone TEXT NOT NULL UNIQUE,
-            avatar TEXT,
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-        );
-        CREATE TABLE IF NOT EXISTS posts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            title TEXT NOT NULL,
-            content TEXT,
-            images TEXT, -- JSON array
-            cover_image TEXT,
-            status TEXT DEFAULT 'draft',
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY(user_id) REFERENCES users(id)
-        );
-        CREATE TABLE IF NOT EXISTS config (
-            key TEXT PRIMARY KEY,
-            value TEXT
-        );
-        CREATE TABLE IF NOT EXISTS ai_providers (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            api_key TEXT NOT NULL,
-            base_url TEXT
-        );
-        CREATE TABLE IF NOT EXISTS ai_models (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            provider_id INTEGER NOT NULL,
-            name TEXT NOT NULL,
-            model_type TEXT NOT NULL, -- 'text' or 'image'
-            FOREIGN KEY(provider_id) REFERENCES ai_providers(id)
-        );
-    ",
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+/// 一条带版本号的数据库迁移
+///
+/// 由 Rust 端的 `run_migrations` 执行，`tauri_plugin_sql` 只注册 v1；
+/// 新增表结构变更时只能在列表末尾追加新版本，不能修改已发布的版本。
+pub struct SchemaMigration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
 
-    Ok(())
+impl SchemaMigration {
+    /// 迁移脚本的 SHA-256 校验和，用于检测已应用的迁移是否被篡改
+    pub fn checksum(&self) -> String {
+        let digest = Sha256::digest(self.sql.as_bytes());
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
 }
 
-pub fn get_migrations() -> Vec<Migration> {
-    vec![Migration {
+/// 全部迁移，按版本号递增
+///
+/// `run_migrations` 按 `;` 把脚本拆成单条语句执行，以便跳过旧版本已经加过的列。脚本的
+/// 字符串和注释中不能出现 `;`，也不能写触发器；需要时先把拆分改为整段执行。
+pub const MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration {
         version: 1,
         description: "create_initial_tables",
+        // 必须与旧版本注册给 tauri_plugin_sql 的脚本逐字节一致，否则插件的校验和会失败
         sql: "
                 CREATE TABLE IF NOT EXISTS users (
                     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -82,6 +54,351 @@ pub fn get_migrations() -> Vec<Migration> {
                     value TEXT
                 );
             ",
-        kind: MigrationKind::Up,
-    }]
+    },
+    SchemaMigration {
+        version: 2,
+        description: "add_posts_cover_image",
+        sql: "ALTER TABLE posts ADD COLUMN cover_image TEXT;",
+    },
+    SchemaMigration {
+        version: 3,
+        description: "create_ai_tables",
+        sql: "
+            CREATE TABLE IF NOT EXISTS ai_providers (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                api_key TEXT NOT NULL,
+                base_url TEXT
+            );
+            CREATE TABLE IF NOT EXISTS ai_models (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                provider_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                model_type TEXT NOT NULL, -- 'text' or 'image'
+                FOREIGN KEY(provider_id) REFERENCES ai_providers(id)
+            );
+        ",
+    },
+    SchemaMigration {
+        version: 4,
+        description: "add_post_revisions",
+        sql: "
+            ALTER TABLE posts ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE posts ADD COLUMN updated_at TEXT;
+            CREATE TABLE IF NOT EXISTS post_revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                post_id INTEGER NOT NULL,
+                version INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT,
+                images TEXT, -- JSON array
+                cover_image TEXT,
+                source TEXT, -- manual, ai, restore
+                created_at TEXT NOT NULL,
+                UNIQUE(post_id, version),
+                FOREIGN KEY(post_id) REFERENCES posts(id)
+            );
+            INSERT INTO post_revisions (post_id, version, title, content, images, cover_image, source, created_at)
+                SELECT id, 1, title, content, images, cover_image, 'manual', COALESCE(created_at, '') FROM posts;
+        ",
+    },
+    SchemaMigration {
+        version: 5,
+        description: "add_post_status_history",
+        sql: "
+            ALTER TABLE posts ADD COLUMN status_updated_at TEXT;
+            ALTER TABLE posts ADD COLUMN last_error TEXT;
+            ALTER TABLE posts ADD COLUMN failure_screenshot TEXT;
+            UPDATE posts SET status = 'draft' WHERE status IS NULL;
+            CREATE TABLE IF NOT EXISTS post_status_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                post_id INTEGER NOT NULL,
+                from_status TEXT NOT NULL,
+                to_status TEXT NOT NULL,
+                error TEXT,
+                screenshot TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY(post_id) REFERENCES posts(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_posts_status ON posts(status);
+        ",
+    },
+    SchemaMigration {
+        version: 6,
+        description: "create_publish_jobs",
+        sql: "
+            CREATE TABLE IF NOT EXISTS publish_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                post_id INTEGER NOT NULL,
+                local_time TEXT NOT NULL,
+                timezone TEXT NOT NULL,
+                scheduled_at TEXT NOT NULL, -- UTC
+                next_run_at TEXT NOT NULL, -- UTC
+                status TEXT NOT NULL DEFAULT 'pending',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                max_attempts INTEGER NOT NULL DEFAULT 3,
+                backoff_seconds INTEGER NOT NULL DEFAULT 300,
+                catch_up TEXT NOT NULL, -- JSON
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY(post_id) REFERENCES posts(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_publish_jobs_due ON publish_jobs(status, next_run_at);
+        ",
+    },
+    SchemaMigration {
+        version: 7,
+        description: "add_post_publish_result",
+        sql: "
+            ALTER TABLE posts ADD COLUMN note_id TEXT;
+            ALTER TABLE posts ADD COLUMN note_url TEXT;
+            ALTER TABLE posts ADD COLUMN failure_kind TEXT;
+        ",
+    },
+    SchemaMigration {
+        version: 8,
+        description: "add_post_media_type",
+        sql: "
+            ALTER TABLE posts ADD COLUMN media_type TEXT NOT NULL DEFAULT 'image';
+            ALTER TABLE posts ADD COLUMN video_path TEXT;
+            ALTER TABLE posts ADD COLUMN cover_time REAL;
+            ALTER TABLE post_revisions ADD COLUMN media_type TEXT NOT NULL DEFAULT 'image';
+            ALTER TABLE post_revisions ADD COLUMN video_path TEXT;
+            ALTER TABLE post_revisions ADD COLUMN cover_time REAL;
+        ",
+    },
+    SchemaMigration {
+        version: 9,
+        description: "add_post_topics_mentions",
+        sql: "
+            ALTER TABLE posts ADD COLUMN topics TEXT; -- JSON 数组
+            ALTER TABLE posts ADD COLUMN mentions TEXT; -- JSON 数组
+            ALTER TABLE post_revisions ADD COLUMN topics TEXT;
+            ALTER TABLE post_revisions ADD COLUMN mentions TEXT;
+        ",
+    },
+    SchemaMigration {
+        version: 10,
+        description: "create_sensitive_words",
+        sql: "
+            CREATE TABLE IF NOT EXISTS sensitive_words (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                word TEXT NOT NULL,
+                category TEXT NOT NULL,
+                replacement TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                UNIQUE(word, category)
+            );
+            INSERT OR IGNORE INTO sensitive_words (word, category, replacement, enabled, created_at) VALUES
+                ('最', '极限词', NULL, 1, datetime('now')),
+                ('第一', '极限词', NULL, 1, datetime('now')),
+                ('顶级', '极限词', NULL, 1, datetime('now')),
+                ('极致', '极限词', NULL, 1, datetime('now')),
+                ('国家级', '极限词', NULL, 1, datetime('now')),
+                ('全网最低', '极限词', NULL, 1, datetime('now')),
+                ('100%', '极限词', NULL, 1, datetime('now')),
+                ('绝对', '极限词', NULL, 1, datetime('now')),
+                ('史上', '极限词', NULL, 1, datetime('now')),
+                ('唯一', '极限词', NULL, 1, datetime('now')),
+                ('治疗', '医疗用语', '改善', 1, datetime('now')),
+                ('治愈', '医疗用语', NULL, 1, datetime('now')),
+                ('根治', '医疗用语', NULL, 1, datetime('now')),
+                ('药效', '医疗用语', NULL, 1, datetime('now')),
+                ('消炎', '医疗用语', NULL, 1, datetime('now')),
+                ('减肥', '医疗用语', '身材管理', 1, datetime('now')),
+                ('祛斑', '医疗用语', '淡化', 1, datetime('now')),
+                ('美白', '医疗用语', '提亮', 1, datetime('now')),
+                ('微信', '站外引流', '私信', 1, datetime('now')),
+                ('vx', '站外引流', NULL, 1, datetime('now')),
+                ('加V', '站外引流', NULL, 1, datetime('now')),
+                ('二维码', '站外引流', NULL, 1, datetime('now')),
+                ('淘宝', '站外引流', NULL, 1, datetime('now')),
+                ('拼多多', '站外引流', NULL, 1, datetime('now')),
+                ('链接', '站外引流', NULL, 1, datetime('now')),
+                ('电话', '站外引流', NULL, 1, datetime('now')),
+                ('抖音', '竞品', NULL, 1, datetime('now')),
+                ('快手', '竞品', NULL, 1, datetime('now')),
+                ('B站', '竞品', NULL, 1, datetime('now'));
+        ",
+    },
+    SchemaMigration {
+        version: 11,
+        description: "add_user_proxy",
+        sql: "
+            ALTER TABLE users ADD COLUMN proxy TEXT; -- JSON，不含密码
+            ALTER TABLE users ADD COLUMN proxy_password TEXT; -- 密文
+        ",
+    },
+    SchemaMigration {
+        version: 12,
+        description: "add_post_publish_options",
+        sql: "
+            ALTER TABLE posts ADD COLUMN publish_options TEXT; -- JSON，可见范围、定时发布等附加设置
+        ",
+    },
+];
+
+/// 当前应用支持的最新数据库版本
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub async fn initialize_database() -> Result<&'static SqlitePool, String> {
+    let db_file = get_app_dir().join("app.db");
+    let existed = db_file.metadata().map(|m| m.len() > 0).unwrap_or(false);
+
+    let pool = pool().await?;
+
+    // 升级已有数据库前先备份，迁移失败时可以手动回滚
+    if existed {
+        let current = current_schema_version(pool).await?;
+        if current < latest_schema_version() {
+            let backup = db_file.with_file_name(format!("app.db.v{}.bak", current));
+            // 连接池已经以 WAL 模式打开，直接复制 app.db 会漏掉 app.db-wal 中的内容
+            let _ = std::fs::remove_file(&backup);
+            sqlx::query("VACUUM INTO ?")
+                .bind(backup.to_string_lossy().to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| format!("备份数据库失败 {:?}: {}", backup, e))?;
+            log::info!("数据库升级前已备份到 {:?}", backup);
+        }
+    }
+
+    run_migrations(pool).await?;
+
+    Ok(pool)
+}
+
+async fn ensure_schema_version_table(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 读取数据库当前的 schema 版本，未迁移过的数据库返回 0
+pub async fn current_schema_version(pool: &SqlitePool) -> Result<i64, String> {
+    ensure_schema_version_table(pool).await?;
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.get(0))
+}
+
+/// 按版本顺序执行尚未应用的迁移
+///
+/// 已应用的迁移会校验 checksum；数据库版本高于当前应用时拒绝启动，
+/// 防止旧版本应用写坏新版本的数据。可重复调用。
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
+    ensure_schema_version_table(pool).await?;
+
+    let applied = sqlx::query("SELECT version, checksum FROM schema_version ORDER BY version")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for row in &applied {
+        let version: i64 = row.get(0);
+        let checksum: String = row.get(1);
+        match MIGRATIONS.iter().find(|m| m.version == version) {
+            Some(m) if m.checksum() != checksum => {
+                return Err(format!(
+                    "数据库迁移 v{} ({}) 校验失败，脚本已被修改",
+                    version, m.description
+                ));
+            }
+            Some(_) => {}
+            None => {
+                return Err(format!(
+                    "数据库版本 v{} 高于当前应用支持的 v{}，请升级应用",
+                    version,
+                    latest_schema_version()
+                ));
+            }
+        }
+    }
+
+    let applied_versions: Vec<i64> = applied.iter().map(|r| r.get(0)).collect();
+
+    for migration in MIGRATIONS {
+        if applied_versions.contains(&migration.version) {
+            continue;
+        }
+
+        log::info!(
+            "应用数据库迁移 v{}: {}",
+            migration.version,
+            migration.description
+        );
+
+        let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+        for statement in split_statements(migration.sql) {
+            if let Err(e) = sqlx::query(statement).execute(&mut *tx).await {
+                // 旧版 initialize_database 建的表可能已经有这一列，视为已应用
+                if is_duplicate_column(&e) {
+                    continue;
+                }
+                return Err(format!(
+                    "数据库迁移 v{} ({}) 失败: {}",
+                    migration.version, migration.description, e
+                ));
+            }
+        }
+
+        sqlx::query(
+            "INSERT INTO schema_version (version, description, checksum, applied_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(migration.checksum())
+        .bind(chrono::Local::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// 按 `;` 拆分迁移脚本，不识别引号和注释，限制见 `MIGRATIONS`
+fn split_statements(sql: &str) -> impl Iterator<Item = &str> {
+    sql.split(';').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn is_duplicate_column(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .map(|db| db.message().contains("duplicate column name"))
+        .unwrap_or(false)
+}
+
+/// 注册给 `tauri_plugin_sql` 的迁移，只包含 v1
+///
+/// 表结构由 `run_migrations` 统一维护。插件在自己的 `_sqlx_migrations` 表中记录版本，
+/// 不能容忍已存在的列，注册后续的 ALTER 迁移会让前端 `Database.load` 在已升级的数据库上失败。
+pub fn get_migrations() -> Vec<Migration> {
+    MIGRATIONS
+        .iter()
+        .take(1)
+        .map(|m| Migration {
+            version: m.version,
+            description: m.description,
+            sql: m.sql,
+            kind: MigrationKind::Up,
+        })
+        .collect()
 }
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 数据根目录的环境变量
+pub const DATA_DIR_ENV: &str = "XHS_HELPER_DATA_DIR";
+/// 启动时使用的工作区的环境变量
+pub const WORKSPACE_ENV: &str = "XHS_HELPER_WORKSPACE";
+/// 可执行文件旁存在该文件时进入便携模式，数据保存在可执行文件目录下
+pub const PORTABLE_MARKER: &str = "portable";
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+const DATA_DIR_NAME: &str = "xiaohongshu-helper-data";
+const WORKSPACES_DIR: &str = "workspaces";
+const WORKSPACE_STATE_FILE: &str = "workspace.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataDirSource {
+    Cli,
+    Env,
+    Portable,
+    Home,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataLocation {
+    /// 数据根目录，所有工作区都在它下面
+    pub root: PathBuf,
+    pub source: DataDirSource,
+    /// 当前进程使用的工作区
+    pub workspace: String,
+    /// 工作区由 `--workspace` 或环境变量指定，此时不能在应用内切换
+    pub workspace_pinned: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct WorkspaceState {
+    active: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub path: String,
+    pub is_active: bool,
+}
+
+static DATA_LOCATION: OnceLock<DataLocation> = OnceLock::new();
+
+/// 从命令行参数中读取 `--flag value` 或 `--flag=value`
+fn cli_value(args: &[String], flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == flag {
+            return iter.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// 解析数据根目录，优先级：`--data-dir` > 环境变量 > 便携模式 > 用户目录
+pub fn resolve_data_root(
+    args: &[String],
+    env_dir: Option<String>,
+    exe_dir: Option<&Path>,
+    home: &Path,
+) -> (PathBuf, DataDirSource) {
+    if let Some(dir) = cli_value(args, "--data-dir").filter(|d| !d.is_empty()) {
+        return (PathBuf::from(dir), DataDirSource::Cli);
+    }
+    if let Some(dir) = env_dir.filter(|d| !d.is_empty()) {
+        return (PathBuf::from(dir), DataDirSource::Env);
+    }
+    if let Some(exe_dir) = exe_dir {
+        if exe_dir.join(PORTABLE_MARKER).exists() {
+            return (exe_dir.join(DATA_DIR_NAME), DataDirSource::Portable);
+        }
+    }
+    (home.join(DATA_DIR_NAME), DataDirSource::Home)
+}
+
+/// 启动参数或环境变量指定的工作区，重启后仍然生效，优先于应用内的切换
+pub fn pinned_workspace(args: &[String], env_workspace: Option<String>) -> Option<String> {
+    cli_value(args, "--workspace")
+        .or(env_workspace)
+        .filter(|name| !name.is_empty())
+}
+
+/// 解析启动工作区，优先级：`--workspace` > 环境变量 > 上次切换保存的工作区 > default
+///
+/// 启动参数或环境变量指定的名称无效时返回错误，不能悄悄打开其他工作区的数据；
+/// 保存的工作区无效时回退到 default。
+pub fn resolve_workspace(
+    args: &[String],
+    env_workspace: Option<String>,
+    root: &Path,
+) -> Result<String, String> {
+    if let Some(name) = pinned_workspace(args, env_workspace) {
+        validate_workspace_name(&name)
+            .map_err(|e| format!("启动参数或环境变量指定的工作区 {} 无效: {}", name, e))?;
+        return Ok(name);
+    }
+    Ok(read_state(root)
+        .active
+        .filter(|name| validate_workspace_name(name).is_ok())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string()))
+}
+
+/// 解析当前进程的数据位置，启动时调用，工作区参数无效时返回错误
+pub fn init_data_location() -> Result<&'static DataLocation, String> {
+    if let Some(location) = DATA_LOCATION.get() {
+        return Ok(location);
+    }
+    let args: Vec<String> = std::env::args().collect();
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf));
+    let home = dirs::home_dir().expect("Could not find home directory");
+
+    let (root, source) = resolve_data_root(
+        &args,
+        std::env::var(DATA_DIR_ENV).ok(),
+        exe_dir.as_deref(),
+        &home,
+    );
+    let env_workspace = std::env::var(WORKSPACE_ENV).ok();
+    let workspace_pinned = pinned_workspace(&args, env_workspace.clone()).is_some();
+    let workspace = resolve_workspace(&args, env_workspace, &root)?;
+
+    println!("数据目录: {:?} ({:?}), 工作区: {}", root, source, workspace);
+    Ok(DATA_LOCATION.get_or_init(|| DataLocation {
+        root,
+        source,
+        workspace,
+        workspace_pinned,
+    }))
+}
+
+/// 当前进程的数据位置，首次调用时解析，之后不再变化
+///
+/// 工作区参数无效时 panic，应用启动时已经通过 `init_data_location` 报告并退出。
+pub fn data_location() -> &'static DataLocation {
+    init_data_location().unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn validate_workspace_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Err("工作区名称长度必须在 1 到 64 个字符之间".to_string());
+    }
+    if name.starts_with('.')
+        || name
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control())
+    {
+        return Err(format!("工作区名称 {} 包含非法字符", name));
+    }
+    Ok(())
+}
+
+/// 工作区目录。default 工作区就是根目录本身，兼容旧版本的数据布局
+pub fn workspace_dir(root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_WORKSPACE {
+        root.to_path_buf()
+    } else {
+        root.join(WORKSPACES_DIR).join(name)
+    }
+}
+
+fn read_state(root: &Path) -> WorkspaceState {
+    std::fs::read_to_string(root.join(WORKSPACE_STATE_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn write_state(root: &Path, state: &WorkspaceState) -> Result<(), String> {
+    std::fs::create_dir_all(root).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    std::fs::write(root.join(WORKSPACE_STATE_FILE), json).map_err(|e| e.to_string())
+}
+
+pub fn list_workspace_names(root: &Path) -> Vec<String> {
+    let mut names = vec![DEFAULT_WORKSPACE.to_string()];
+    if let Ok(entries) = std::fs::read_dir(root.join(WORKSPACES_DIR)) {
+        let mut others: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .filter(|n| n != DEFAULT_WORKSPACE && validate_workspace_name(n).is_ok())
+            .collect();
+        others.sort();
+        names.extend(others);
+    }
+    names
+}
+
+#[tauri::command]
+pub async fn get_data_location() -> Result<DataLocation, String> {
+    Ok(data_location().clone())
+}
+
+#[tauri::command]
+pub async fn list_workspaces() -> Result<Vec<WorkspaceInfo>, String> {
+    let location = data_location();
+    Ok(list_workspace_names(&location.root)
+        .into_iter()
+        .map(|name| WorkspaceInfo {
+            path: workspace_dir(&location.root, &name)
+                .to_string_lossy()
+                .to_string(),
+            is_active: name == location.workspace,
+            name,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn create_workspace(name: String) -> Result<WorkspaceInfo, String> {
+    validate_workspace_name(&name)?;
+    let name = name.trim().to_string();
+    let location = data_location();
+    let dir = workspace_dir(&location.root, &name);
+    if name != DEFAULT_WORKSPACE && dir.exists() {
+        return Err(format!("工作区 {} 已存在", name));
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    Ok(WorkspaceInfo {
+        path: dir.to_string_lossy().to_string(),
+        is_active: name == location.workspace,
+        name,
+    })
+}
+
+/// 切换工作区并重启应用
+///
+/// 数据库连接池、浏览器会话以及 API/MCP 服务都绑定在启动时的工作区上，
+/// 重启可以保证不同品牌的账号和素材不会在同一进程里混用。
+#[tauri::command]
+pub async fn switch_workspace(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    validate_workspace_name(&name)?;
+    let name = name.trim().to_string();
+    let location = data_location();
+    // 重启后启动参数和环境变量仍然生效，保存的选择不会被使用
+    if location.workspace_pinned {
+        return Err(format!(
+            "当前工作区由启动参数 --workspace 或环境变量 {} 指定，请去掉后重新启动再切换",
+            WORKSPACE_ENV
+        ));
+    }
+    if !list_workspace_names(&location.root).contains(&name) {
+        return Err(format!("工作区 {} 不存在", name));
+    }
+
+    write_state(
+        &location.root,
+        &WorkspaceState {
+            active: Some(name.clone()),
+        },
+    )?;
+    println!("已切换到工作区 {}，正在重启应用", name);
+    app.restart();
+}
@@ -1,9 +1,20 @@
 use env_logger::fmt;
 use log::*;
 use std::io::Write;
+use std::path::Path;
 
-pub fn enable_logging() {
-    let mut builder = env_logger::Builder::from_default_env();
+/// 数据目录中的日志文件
+pub const LOG_FILE: &str = "app.log";
+
+/// 启动日志，默认记录 info 及以上级别，可以用 RUST_LOG 调整
+///
+/// 传入 `file` 时写入该文件，每次启动重新创建；打包后的应用看不到标准输出。
+pub fn enable_logging(file: Option<&Path>) {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(LevelFilter::Info).parse_default_env();
+    if let Some(file) = file.and_then(|path| std::fs::File::create(path).ok()) {
+        builder.target(env_logger::Target::Pipe(Box::new(file)));
+    }
 
     // NOTE: can infer types here, but I find them a useful reminder.
     let _result = builder
@@ -14,7 +25,10 @@ pub fn enable_logging() {
             let hours_minutes = date.format("%H:%M").to_string();
             let seconds_millis = date.format("%S%.3f").to_string();
 
-            let truncated_module_path = &record.module_path().unwrap()[5..];
+            let truncated_module_path = record
+                .module_path()
+                .and_then(|path| path.get(5..))
+                .unwrap_or_default();
 
             writeln!(
                 buf,
@@ -45,7 +59,7 @@ fn level_to_emoji(level: log::Level) -> &'static str {
 
 #[test]
 fn start_the_logs() {
-    enable_logging();
+    enable_logging(None);
 
     error!("error message");
     warn!("warn message");
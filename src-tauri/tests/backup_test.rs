@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::PathBuf;
+use xiaohongshu_helper_lib::storage::backup::{
+    apply_staged_restore, read_backup_manifest, stage_backup, write_backup_archive, BackupPart,
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xhs-backup-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_backup_roundtrip() {
+    let source = temp_dir("source");
+    fs::create_dir_all(source.join("images/sub")).unwrap();
+    fs::write(source.join("images/a.png"), b"image-a").unwrap();
+    fs::write(source.join("images/sub/b.png"), b"image-b").unwrap();
+    fs::create_dir_all(source.join("profiles/13800138000/Cache")).unwrap();
+    fs::write(source.join("profiles/13800138000/Cookies"), b"cookies").unwrap();
+    fs::write(source.join("profiles/13800138000/Cache/data"), b"skip").unwrap();
+    fs::write(source.join("master.key"), b"key").unwrap();
+    let snapshot = source.join("snapshot.db");
+    fs::write(&snapshot, b"sqlite").unwrap();
+
+    let archive = source.join("backup.zip");
+    let parts = [BackupPart::Database, BackupPart::Images, BackupPart::Profiles];
+    let manifest =
+        write_backup_archive(&source, Some(&snapshot), &archive, &parts, 3, "default").unwrap();
+
+    // 浏览器缓存和主密钥不进入备份
+    assert_eq!(manifest.files.len(), 4);
+    assert!(manifest.files.iter().all(|f| !f.path.contains("Cache")));
+    assert!(manifest.files.iter().all(|f| !f.path.contains("master.key")));
+    assert_eq!(manifest.warnings.len(), 1);
+    assert_eq!(read_backup_manifest(&archive).unwrap().parts, parts.to_vec());
+
+    // 只恢复素材库到新的工作区
+    let target = temp_dir("target");
+    fs::create_dir_all(target.join("images")).unwrap();
+    fs::write(target.join("images/old.png"), b"old").unwrap();
+
+    stage_backup(&archive, &target.join("restore-pending"), &[BackupPart::Images]).unwrap();
+    fs::write(
+        target.join("restore-pending/pending.json"),
+        r#"{"parts":["images"]}"#,
+    )
+    .unwrap();
+    assert!(apply_staged_restore(&target).unwrap());
+
+    assert_eq!(fs::read(target.join("images/sub/b.png")).unwrap(), b"image-b");
+    assert!(target.join("images.before-restore/old.png").exists());
+    assert!(!target.join("restore-pending").exists());
+    assert!(!target.join("app.db").exists());
+
+    fs::remove_dir_all(&source).ok();
+    fs::remove_dir_all(&target).ok();
+}
+
+#[test]
+fn test_resume_interrupted_restore() {
+    let source = temp_dir("resume-source");
+    fs::create_dir_all(source.join("images")).unwrap();
+    fs::write(source.join("images/a.png"), b"image-a").unwrap();
+    let snapshot = source.join("snapshot.db");
+    fs::write(&snapshot, b"sqlite").unwrap();
+    let archive = source.join("backup.zip");
+    let parts = [BackupPart::Database, BackupPart::Images];
+    write_backup_archive(&source, Some(&snapshot), &archive, &parts, 3, "default").unwrap();
+
+    let target = temp_dir("resume-target");
+    fs::write(target.join("app.db"), b"old-db").unwrap();
+    fs::create_dir_all(target.join("images")).unwrap();
+    fs::write(target.join("images/old.png"), b"old").unwrap();
+    let staging = target.join("restore-pending");
+    stage_backup(&archive, &staging, &parts).unwrap();
+
+    // 上次启动时数据库已经替换完成，素材库只移走了原目录，应用又新建了空目录
+    fs::write(
+        staging.join("pending.json"),
+        r#"{"parts":["database","images"],"started":true}"#,
+    )
+    .unwrap();
+    fs::rename(target.join("app.db"), target.join("app.db.before-restore")).unwrap();
+    fs::rename(staging.join("database/app.db"), target.join("app.db")).unwrap();
+    fs::rename(target.join("images"), target.join("images.before-restore")).unwrap();
+    fs::create_dir_all(target.join("images")).unwrap();
+
+    assert!(apply_staged_restore(&target).unwrap());
+    assert_eq!(fs::read(target.join("app.db")).unwrap(), b"sqlite");
+    assert_eq!(
+        fs::read(target.join("app.db.before-restore")).unwrap(),
+        b"old-db"
+    );
+    assert_eq!(fs::read(target.join("images/a.png")).unwrap(), b"image-a");
+    assert!(target.join("images.before-restore/old.png").exists());
+    assert!(!staging.exists());
+    assert!(!apply_staged_restore(&target).unwrap());
+
+    fs::remove_dir_all(&source).ok();
+    fs::remove_dir_all(&target).ok();
+}
+
+#[test]
+fn test_restore_rejects_missing_part() {
+    let source = temp_dir("partial");
+    fs::create_dir_all(source.join("images")).unwrap();
+    let archive = source.join("backup.zip");
+    write_backup_archive(&source, None, &archive, &[BackupPart::Images], 3, "default").unwrap();
+
+    let result = stage_backup(
+        &archive,
+        &source.join("restore-pending"),
+        &[BackupPart::Database],
+    );
+    assert!(result.is_err());
+
+    fs::remove_dir_all(&source).ok();
+}
+
+#[test]
+fn test_master_key_is_opt_in() {
+    let source = temp_dir("key");
+    fs::write(source.join("master.key"), b"key").unwrap();
+    let archive = source.join("backup.zip");
+    let manifest =
+        write_backup_archive(&source, None, &archive, &[BackupPart::MasterKey], 3, "default")
+            .unwrap();
+    assert_eq!(manifest.files[0].path, "secrets/master.key");
+    assert!(!manifest.warnings.is_empty());
+
+    let target = temp_dir("key-target");
+    stage_backup(&archive, &target.join("restore-pending"), &[BackupPart::MasterKey]).unwrap();
+    fs::write(
+        target.join("restore-pending/pending.json"),
+        r#"{"parts":["master_key"]}"#,
+    )
+    .unwrap();
+    assert!(apply_staged_restore(&target).unwrap());
+    assert_eq!(fs::read(target.join("master.key")).unwrap(), b"key");
+
+    fs::remove_dir_all(&source).ok();
+    fs::remove_dir_all(&target).ok();
+}
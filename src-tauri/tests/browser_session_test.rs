@@ -0,0 +1,34 @@
+use std::time::Duration;
+use xiaohongshu_helper_lib::automation::session::{acquire, active_accounts, evict_idle};
+
+#[tokio::test]
+async fn test_session_serializes_same_account() {
+    let first = acquire("13800000001").await.unwrap();
+
+    // 同一账号的第二个任务要等第一个释放
+    let waiting = tokio::time::timeout(Duration::from_millis(200), acquire("13800000001")).await;
+    assert!(waiting.is_err());
+
+    // 其他账号不受影响
+    let other = acquire("13800000002").await.unwrap();
+    assert_eq!(other.phone(), "13800000002");
+
+    drop(first);
+    let second = tokio::time::timeout(Duration::from_millis(200), acquire("13800000001"))
+        .await
+        .expect("released session should be available")
+        .unwrap();
+    assert_eq!(second.phone(), "13800000001");
+
+    // 使用中的账号不会被当作空闲关闭
+    drop(other);
+    let evicted = evict_idle(Duration::ZERO);
+    assert!(evicted.contains(&"13800000002".to_string()));
+    assert!(!evicted.contains(&"13800000001".to_string()));
+    assert!(active_accounts().contains(&"13800000001".to_string()));
+
+    drop(second);
+    assert!(evict_idle(Duration::from_secs(3600)).is_empty());
+    evict_idle(Duration::ZERO);
+    assert!(!active_accounts().contains(&"13800000001".to_string()));
+}
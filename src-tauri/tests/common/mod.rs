@@ -0,0 +1,135 @@
+//! 本地模拟的创作者中心，页面在 tests/fixtures/creator 下
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use xiaohongshu_helper_lib::storage::workspace::{data_location, DATA_DIR_ENV};
+
+pub struct FixtureSite {
+    pub base_url: String,
+    /// 收到的发布请求内容
+    pub notes: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+/// 把数据目录指向 `dir` 并立即解析数据位置
+///
+/// 数据位置在进程内只解析一次，之后再设置环境变量不会生效，需要在任何存储调用之前调用。
+pub fn init_data_dir(dir: &Path) {
+    std::env::set_var(DATA_DIR_ENV, dir);
+    let location = data_location();
+    assert_eq!(location.root, dir, "数据位置已经在设置环境变量之前解析");
+}
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/creator")
+}
+
+/// 在随机端口启动模拟站点
+///
+/// 站点运行在单独的线程上，自动化代码里阻塞的等待不会卡住它。
+pub fn start_fixture_site() -> FixtureSite {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let notes = Arc::new(Mutex::new(Vec::new()));
+
+    let sink = notes.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let listener = TcpListener::from_std(listener).unwrap();
+            while let Ok((stream, _)) = listener.accept().await {
+                let sink = sink.clone();
+                tokio::spawn(async move {
+                    let _ = handle(stream, sink).await;
+                });
+            }
+        });
+    });
+    FixtureSite { base_url, notes }
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    notes: Arc<Mutex<Vec<serde_json::Value>>>,
+) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = &buf[header_end..];
+
+    let (status, content_type, content) = match (method.as_str(), path.as_str()) {
+        ("POST", "/web_api/sns/v2/note") => {
+            let note = serde_json::from_slice(body).unwrap_or(serde_json::Value::Null);
+            let count = {
+                let mut notes = notes.lock().unwrap();
+                notes.push(note);
+                notes.len()
+            };
+            let response = serde_json::json!({
+                "success": true,
+                "data": { "id": format!("fixture-note-{}", count) }
+            });
+            (
+                "200 OK",
+                "application/json",
+                response.to_string().into_bytes(),
+            )
+        }
+        ("GET", page) => {
+            let file = match page {
+                "/login" => Some("login.html"),
+                "/publish/publish" => Some("publish.html"),
+                "/publish/success" => Some("success.html"),
+                "/new/home" => Some("home.html"),
+                _ => None,
+            };
+            match file.and_then(|f| std::fs::read(fixture_dir().join(f)).ok()) {
+                Some(html) => ("200 OK", "text/html; charset=utf-8", html),
+                None => ("404 Not Found", "text/plain", b"not found".to_vec()),
+            }
+        }
+        _ => ("405 Method Not Allowed", "text/plain", Vec::new()),
+    };
+
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        content.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&content).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,27 @@
+use xiaohongshu_helper_lib::automation::editor::{match_suggestion, normalize_tags};
+
+#[test]
+fn test_normalize_tags() {
+    let tags = vec![
+        " #旅行 ".to_string(),
+        "旅行".to_string(),
+        "@小红薯".to_string(),
+        "#".to_string(),
+    ];
+    assert_eq!(normalize_tags(&tags), vec!["旅行", "小红薯"]);
+}
+
+#[test]
+fn test_match_suggestion() {
+    let topics = vec![
+        "#旅行攻略\n3.2亿次浏览".to_string(),
+        "#旅行\n12亿次浏览".to_string(),
+    ];
+    assert_eq!(match_suggestion(&topics, "旅行"), Some(1));
+    assert_eq!(match_suggestion(&topics, "旅"), None);
+
+    // 昵称不一致时按小红书号匹配
+    let users = vec!["小红薯\n小红书号：xhs123".to_string()];
+    assert_eq!(match_suggestion(&users, "小红薯"), Some(0));
+    assert_eq!(match_suggestion(&users, "xhs123"), Some(0));
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use xiaohongshu_helper_lib::analytics::fetch_home_text;
+use xiaohongshu_helper_lib::auth::{start_login_process, submit_verification_code};
+use xiaohongshu_helper_lib::automation::options::{PublishMode, PublishOptions};
+use xiaohongshu_helper_lib::automation::selectors::{selector_health_check, SelectorStatus};
+use xiaohongshu_helper_lib::automation::trace::{get_automation_run, RunStatus};
+use xiaohongshu_helper_lib::automation::{publish_post, site, validate_login_status};
+
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes
+}
+
+/// 在本地模拟站点上跑完登录、校验、发布和读取主页数据
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "需要本机安装 Chrome，用 cargo test -- --ignored 运行"]
+async fn test_flows_against_fixture_site() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    common::init_data_dir(&dir.join("data"));
+    xiaohongshu_helper_lib::storage::sqlite::initialize_database()
+        .await
+        .unwrap();
+
+    let fixture = common::start_fixture_site();
+    site::set_creator_base_url(&fixture.base_url);
+    let phone = "13800000000".to_string();
+
+    // 未登录的账号会被重定向到登录页
+    let err = validate_login_status("13800000009".to_string())
+        .await
+        .unwrap_err();
+    assert!(err.contains("未检测到登录状态"), "{}", err);
+
+    // 登录
+    let sent = start_login_process(phone.clone()).await.unwrap();
+    assert_eq!(sent, "Verification code sent");
+    let user = submit_verification_code(phone.clone(), "123456".to_string())
+        .await
+        .unwrap();
+    assert_eq!(user.nickname, "模拟用户0000");
+
+    let user = validate_login_status(phone.clone()).await.unwrap();
+    assert_eq!(user.nickname, "模拟用户0000");
+
+    let images: Vec<String> = ["a.png", "b.png", "c.png"]
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            std::fs::write(&path, png_header(1080, 1440)).unwrap();
+            path.to_string_lossy().to_string()
+        })
+        .collect();
+
+    // 模拟站点上所有能检查的选择器都应该命中第一个候选
+    let report = selector_health_check(phone.clone(), Some(images[0].clone()))
+        .await
+        .unwrap();
+    for health in &report {
+        assert!(
+            matches!(health.status, SelectorStatus::Ok | SelectorStatus::Skipped),
+            "{:?}",
+            health
+        );
+    }
+    assert!(report
+        .iter()
+        .any(|h| h.key == "publish.title" && h.status == SelectorStatus::Ok));
+
+    // 试运行只填写页面，不会调用发布接口
+    let dry_run = PublishOptions {
+        mode: PublishMode::DryRun,
+        ..Default::default()
+    };
+    let note = publish_post(
+        phone.clone(),
+        "模拟站点测试".to_string(),
+        "本地模拟站点的正文".to_string(),
+        images.clone(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(dry_run),
+    )
+    .await
+    .unwrap();
+    assert_eq!(note.mode, PublishMode::DryRun);
+    assert!(note.note_id.is_none());
+    assert!(fixture.notes.lock().unwrap().is_empty());
+
+    // 发布，图片按草稿顺序上传
+    let note = publish_post(
+        phone.clone(),
+        "模拟站点测试".to_string(),
+        "本地模拟站点的正文".to_string(),
+        images,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(note.note_id.as_deref(), Some("fixture-note-1"));
+    let run = get_automation_run(note.run_id.unwrap()).await.unwrap();
+    assert_eq!(run.status, RunStatus::Succeeded);
+    assert!(run.steps.iter().any(|s| s.name == "wait_result"));
+
+    let notes = fixture.notes.lock().unwrap().clone();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["title"], "模拟站点测试");
+    assert_eq!(notes[0]["desc"], "本地模拟站点的正文");
+    assert_eq!(
+        notes[0]["images"],
+        serde_json::json!(["a.png", "b.png", "c.png"])
+    );
+
+    // 数据分析读取创作者主页文字
+    let text = fetch_home_text(&phone).await.unwrap();
+    assert!(text.contains("粉丝数 345"), "{}", text);
+
+    xiaohongshu_helper_lib::automation::session::close_account(&phone);
+    xiaohongshu_helper_lib::automation::session::close_account("13800000009");
+}
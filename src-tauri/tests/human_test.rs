@@ -0,0 +1,92 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::Duration;
+use xiaohongshu_helper_lib::automation::human::{
+    click_point, mouse_path, scroll_steps, typing_delays, HumanInput, Rect,
+};
+
+#[test]
+fn test_settings_validate() {
+    assert!(HumanInput::default().validate().is_ok());
+
+    let reversed = HumanInput {
+        type_delay_ms: (200, 100),
+        ..Default::default()
+    };
+    assert!(reversed.validate().is_err());
+
+    let no_steps = HumanInput {
+        move_steps: (0, 10),
+        ..Default::default()
+    };
+    assert!(no_steps.validate().is_err());
+
+    let chance = HumanInput {
+        pause_chance: 1.5,
+        ..Default::default()
+    };
+    assert!(chance.validate().is_err());
+}
+
+#[test]
+fn test_typing_delays_in_range() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let settings = HumanInput {
+        pause_chance: 0.0,
+        ..Default::default()
+    };
+    let delays = typing_delays(&settings, 50, &mut rng);
+    assert_eq!(delays.len(), 50);
+    assert!(delays
+        .iter()
+        .all(|d| (Duration::from_millis(60)..=Duration::from_millis(180)).contains(d)));
+
+    let always_pause = HumanInput {
+        pause_chance: 1.0,
+        ..Default::default()
+    };
+    let delays = typing_delays(&always_pause, 10, &mut rng);
+    assert!(delays.iter().all(|d| *d >= Duration::from_millis(360)));
+}
+
+#[test]
+fn test_mouse_path_ends_at_target() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let path = mouse_path((10.0, 20.0), (300.0, 400.0), 20, &mut rng);
+    assert_eq!(path.len(), 20);
+    let last = path.last().unwrap();
+    assert!((last.0 - 300.0).abs() < 1e-6 && (last.1 - 400.0).abs() < 1e-6);
+
+    let still = mouse_path((5.0, 5.0), (5.0, 5.0), 3, &mut rng);
+    assert!(still.iter().all(|p| *p == (5.0, 5.0)));
+}
+
+#[test]
+fn test_scroll_steps_cover_distance() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let settings = HumanInput::default();
+    let steps = scroll_steps(1000.0, &settings, &mut rng);
+    assert!((steps.iter().sum::<f64>() - 1000.0).abs() < 1.0);
+    assert!(steps.iter().all(|s| *s > 0.0 && *s <= 220.0));
+
+    let up = scroll_steps(-300.0, &settings, &mut rng);
+    assert!(up.iter().all(|s| *s < 0.0));
+    assert!(scroll_steps(0.0, &settings, &mut rng).is_empty());
+}
+
+#[test]
+fn test_click_point_inside_rect() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let rect = Rect {
+        x: 100.0,
+        y: 50.0,
+        width: 80.0,
+        height: 30.0,
+        viewport_height: 800.0,
+    };
+    for _ in 0..20 {
+        let (x, y) = click_point(&rect, &mut rng);
+        assert!((100.0..=180.0).contains(&x));
+        assert!((50.0..=80.0).contains(&y));
+    }
+}
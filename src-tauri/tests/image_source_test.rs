@@ -0,0 +1,66 @@
+use std::net::IpAddr;
+use xiaohongshu_helper_lib::automation::image_source::{
+    check_image_bytes, is_public_ip, parse_image_source, ImageSource,
+};
+
+#[test]
+fn test_parse_image_source() {
+    assert_eq!(
+        parse_image_source("https://example.com/a.jpg"),
+        ImageSource::Url("https://example.com/a.jpg".to_string())
+    );
+    assert_eq!(
+        parse_image_source("data:image/png;base64,iVBORw0KGgo="),
+        ImageSource::Base64("iVBORw0KGgo=".to_string())
+    );
+    assert_eq!(
+        parse_image_source("base64:/9j/4AAQ"),
+        ImageSource::Base64("/9j/4AAQ".to_string())
+    );
+    assert_eq!(
+        parse_image_source("asset:imported_1.png"),
+        ImageSource::Asset("imported_1.png".to_string())
+    );
+    assert_eq!(
+        parse_image_source("/home/me/a.jpg"),
+        ImageSource::Path("/home/me/a.jpg".to_string())
+    );
+}
+
+#[test]
+fn test_check_image_bytes() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    assert_eq!(check_image_bytes(png, Some("image/png")), Ok("png"));
+    assert_eq!(
+        check_image_bytes(&[0xFF, 0xD8, 0xFF, 0xE0], None),
+        Ok("jpg")
+    );
+
+    // Content-Type 和文件头都要是图片
+    assert!(check_image_bytes(png, Some("text/html; charset=utf-8")).is_err());
+    assert!(check_image_bytes(b"GIF89a", Some("image/gif")).is_err());
+    assert!(check_image_bytes(b"", None).is_err());
+}
+
+#[test]
+fn test_is_public_ip() {
+    let public = |ip: &str| is_public_ip(ip.parse::<IpAddr>().unwrap());
+    assert!(public("93.184.216.34"));
+    assert!(public("2606:2800:220:1::1"));
+
+    for ip in [
+        "127.0.0.1",
+        "10.0.0.1",
+        "172.16.5.4",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+    ] {
+        assert!(!public(ip), "{} 应被拒绝", ip);
+    }
+}
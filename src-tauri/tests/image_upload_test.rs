@@ -0,0 +1,34 @@
+use xiaohongshu_helper_lib::automation::images::{reorder_moves, upload_order};
+
+#[test]
+fn test_upload_order() {
+    let images: Vec<String> = ["a.jpg", "b.jpg", "c.jpg", "b.jpg"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    // 封面在最前，其余保持原顺序，重复的只传一次
+    assert_eq!(
+        upload_order(&images, "c.jpg"),
+        vec!["c.jpg", "a.jpg", "b.jpg"]
+    );
+    assert_eq!(
+        upload_order(&images, "a.jpg"),
+        vec!["a.jpg", "b.jpg", "c.jpg"]
+    );
+}
+
+#[test]
+fn test_reorder_moves() {
+    assert!(reorder_moves(&[0, 1, 2]).is_empty());
+    assert_eq!(reorder_moves(&[1, 0, 2]), vec![(1, 0)]);
+    assert_eq!(reorder_moves(&[2, 0, 1]), vec![(1, 0), (2, 1)]);
+
+    // 按拖动步骤模拟一遍，结果应为正确顺序
+    let mut order = vec![3, 1, 0, 2];
+    for (from, to) in reorder_moves(&order) {
+        let item = order.remove(from);
+        order.insert(to, item);
+    }
+    assert_eq!(order, vec![0, 1, 2, 3]);
+}
@@ -0,0 +1,135 @@
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use xiaohongshu_helper_lib::storage::sqlite::{
+    current_schema_version, get_migrations, latest_schema_version, run_migrations, MIGRATIONS,
+};
+
+async fn memory_pool() -> SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
+
+async fn columns(pool: &SqlitePool, table: &str) -> Vec<String> {
+    sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.get::<String, _>("name"))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_fresh_database_reaches_latest_version() {
+    let pool = memory_pool().await;
+    run_migrations(&pool).await.unwrap();
+
+    assert_eq!(
+        current_schema_version(&pool).await.unwrap(),
+        latest_schema_version()
+    );
+    assert!(columns(&pool, "posts").await.contains(&"cover_image".to_string()));
+    assert!(!columns(&pool, "ai_providers").await.is_empty());
+
+    // 重复执行不应报错
+    run_migrations(&pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_plugin_migrations_run_on_upgraded_database() {
+    let pool = memory_pool().await;
+    run_migrations(&pool).await.unwrap();
+
+    // 前端插件会在已升级的数据库上再执行一遍，不能包含 ALTER TABLE
+    let plugin = get_migrations();
+    assert_eq!(plugin.len(), 1);
+    for statement in plugin[0].sql.split(';').filter(|s| !s.trim().is_empty()) {
+        sqlx::query(statement).execute(&pool).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_legacy_database_is_upgraded_in_place() {
+    let pool = memory_pool().await;
+
+    // 旧版 initialize_database 创建的表：已有 cover_image，但没有 schema_version
+    sqlx::query(
+        "CREATE TABLE users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            nickname TEXT NOT NULL,
+            phone TEXT NOT NULL UNIQUE,
+            avatar TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE posts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT,
+            images TEXT,
+            cover_image TEXT,
+            status TEXT DEFAULT 'draft',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        INSERT INTO users (nickname, phone) VALUES ('老用户', '13800138000');
+        INSERT INTO posts (user_id, title, cover_image) VALUES (1, '旧草稿', '/tmp/a.png');",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    run_migrations(&pool).await.unwrap();
+
+    assert_eq!(
+        current_schema_version(&pool).await.unwrap(),
+        latest_schema_version()
+    );
+    let cover: Option<String> = sqlx::query("SELECT cover_image FROM posts WHERE id = 1")
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(cover.as_deref(), Some("/tmp/a.png"));
+}
+
+#[tokio::test]
+async fn test_newer_database_is_rejected() {
+    let pool = memory_pool().await;
+    run_migrations(&pool).await.unwrap();
+
+    sqlx::query(
+        "INSERT INTO schema_version (version, description, checksum, applied_at) VALUES (?, 'future', '', '')",
+    )
+    .bind(latest_schema_version() + 1)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    assert!(run_migrations(&pool).await.is_err());
+}
+
+/// 迁移脚本按 `;` 拆分执行，字符串、注释和触发器体里的 `;` 会拆出错误的语句
+#[test]
+fn test_migration_scripts_split_cleanly() {
+    for migration in MIGRATIONS {
+        for line in migration.sql.lines() {
+            let (code, comment) = line.split_once("--").unwrap_or((line, ""));
+            assert!(!comment.contains(';'), "v{} 的注释中有 ;", migration.version);
+            // 按单引号切开后，奇数段在字符串内
+            let mut strings = code.split('\'').skip(1).step_by(2);
+            assert!(
+                strings.all(|text| !text.contains(';')),
+                "v{} 的字符串中有 ;",
+                migration.version
+            );
+        }
+        assert!(
+            !migration.sql.contains("/*") && !migration.sql.to_uppercase().contains("BEGIN"),
+            "v{} 中有块注释或触发器",
+            migration.version
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use xiaohongshu_helper_lib::automation::multi_account::apply_image_override;
+use xiaohongshu_helper_lib::model::{MediaType, Post};
+
+fn post() -> Post {
+    Post {
+        id: 1,
+        user_id: 1,
+        title: "标题".to_string(),
+        content: "正文".to_string(),
+        images: vec!["a.png".to_string(), "b.png".to_string(), "c.png".to_string()],
+        cover_image: Some("a.png".to_string()),
+        media_type: MediaType::Image,
+        video_path: None,
+        cover_time: None,
+        topics: vec!["旅行".to_string()],
+        mentions: vec![],
+        status: "draft".to_string(),
+        created_at: String::new(),
+        version: 1,
+        updated_at: None,
+        status_updated_at: None,
+        last_error: None,
+        failure_screenshot: None,
+        failure_kind: None,
+        note_id: None,
+        note_url: None,
+        warning: None,
+    }
+}
+
+#[test]
+fn test_image_override() {
+    let post = post();
+
+    let (images, cover) = apply_image_override(&post, None, None).unwrap();
+    assert_eq!(images, post.images);
+    assert_eq!(cover.as_deref(), Some("a.png"));
+
+    // 调整顺序并去掉原封面时改用第一张
+    let reordered = vec!["c.png".to_string(), "b.png".to_string()];
+    let (images, cover) = apply_image_override(&post, Some(&reordered), None).unwrap();
+    assert_eq!(images, reordered);
+    assert_eq!(cover.as_deref(), Some("c.png"));
+
+    let (_, cover) = apply_image_override(&post, Some(&reordered), Some("b.png")).unwrap();
+    assert_eq!(cover.as_deref(), Some("b.png"));
+
+    assert!(apply_image_override(&post, Some(&["x.png".to_string()]), None).is_err());
+    assert!(apply_image_override(&post, Some(&reordered), Some("a.png")).is_err());
+    assert!(apply_image_override(&post, Some(&[]), None).is_err());
+}
+
+#[test]
+fn test_video_cover_override() {
+    let post = Post {
+        images: vec![],
+        cover_image: None,
+        media_type: MediaType::Video,
+        video_path: Some("a.mp4".to_string()),
+        ..post()
+    };
+
+    let (images, cover) = apply_image_override(&post, None, Some("cover.png")).unwrap();
+    assert!(images.is_empty());
+    assert_eq!(cover.as_deref(), Some("cover.png"));
+    assert!(apply_image_override(&post, Some(&["a.png".to_string()]), None).is_err());
+}
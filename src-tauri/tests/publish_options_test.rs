@@ -0,0 +1,47 @@
+use chrono::NaiveDate;
+use xiaohongshu_helper_lib::automation::options::{
+    parse_schedule_time, PublishMode, PublishOptions, Visibility,
+};
+
+#[test]
+fn test_parse_schedule_time() {
+    let now = NaiveDate::from_ymd_opt(2026, 10, 17)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    let time = parse_schedule_time("2026-10-18 20:30", now).unwrap();
+    assert_eq!(time.format("%Y-%m-%d %H:%M").to_string(), "2026-10-18 20:30");
+    assert!(parse_schedule_time("2026-10-18T20:30", now).is_ok());
+
+    // 至少提前一小时，最多提前 14 天
+    assert!(parse_schedule_time("2026-10-17 12:30", now).is_err());
+    assert!(parse_schedule_time("2026-11-05 12:00", now).is_err());
+    assert!(parse_schedule_time("明天晚上", now).is_err());
+}
+
+#[test]
+fn test_publish_options_json() {
+    let options: PublishOptions =
+        serde_json::from_str(r#"{"visibility":"friends","original":true}"#).unwrap();
+    assert_eq!(options.visibility, Some(Visibility::Friends));
+    assert_eq!(options.original, Some(true));
+    assert_eq!(options.mode, PublishMode::Publish);
+    assert!(!options.is_empty());
+    assert!(PublishOptions::default().is_empty());
+
+    // 模式不是发布页上的设置项
+    let options: PublishOptions = serde_json::from_str(r#"{"mode":"dry_run"}"#).unwrap();
+    assert_eq!(options.mode, PublishMode::DryRun);
+    assert!(options.is_empty());
+    assert_eq!(PublishMode::parse("platform_draft"), Some(PublishMode::PlatformDraft));
+    assert_eq!(PublishMode::parse("draft"), None);
+
+    // 笔记上只保存发布页的设置，试运行不能保存
+    assert!(options.validate_saved().is_err());
+    let options = PublishOptions {
+        visibility: Some(Visibility::Private),
+        ..Default::default()
+    };
+    assert!(options.validate_saved().is_ok());
+}
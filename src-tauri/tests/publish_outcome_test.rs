@@ -0,0 +1,76 @@
+use xiaohongshu_helper_lib::automation::outcome::{
+    is_publish_api, parse_publish_response, toast_failure, PublishApiResponse, PublishErrorKind,
+    PublishedNote,
+};
+use xiaohongshu_helper_lib::automation::video::{parse_upload_state, VideoUploadState};
+
+#[test]
+fn test_parse_publish_response() {
+    assert!(is_publish_api(
+        "https://edith.xiaohongshu.com/web_api/sns/v2/note?x=1"
+    ));
+
+    let body = r#"{"success":true,"code":0,"data":{"id":"6710a0b1000000001d03a1f2","score":10},"share_link":"https://www.xiaohongshu.com/discovery/item/6710a0b1000000001d03a1f2"}"#;
+    assert_eq!(
+        parse_publish_response(body),
+        Some(PublishApiResponse::Success(PublishedNote {
+            note_id: Some("6710a0b1000000001d03a1f2".to_string()),
+            note_url: Some(
+                "https://www.xiaohongshu.com/discovery/item/6710a0b1000000001d03a1f2".to_string()
+            ),
+            ..Default::default()
+        }))
+    );
+
+    // 没有分享链接时根据 id 生成
+    let body = r#"{"code":0,"data":{"note_id":"abc"}}"#;
+    match parse_publish_response(body) {
+        Some(PublishApiResponse::Success(note)) => {
+            assert_eq!(note.note_url.unwrap(), "https://www.xiaohongshu.com/explore/abc")
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let body = r#"{"success":false,"code":-9101,"msg":"内容包含违规信息"}"#;
+    assert_eq!(
+        parse_publish_response(body),
+        Some(PublishApiResponse::Rejected("内容包含违规信息".to_string()))
+    );
+    assert_eq!(parse_publish_response("<html>"), None);
+}
+
+#[test]
+fn test_classify_failure() {
+    assert_eq!(
+        PublishErrorKind::classify("登录已过期，请重新登录"),
+        PublishErrorKind::SessionExpired
+    );
+    assert_eq!(
+        PublishErrorKind::classify("Failed to set cover image: upload"),
+        PublishErrorKind::UploadFailed
+    );
+    assert_eq!(
+        toast_failure("笔记内容不符合社区规范"),
+        Some(PublishErrorKind::ContentRejected)
+    );
+    assert_eq!(toast_failure("发布成功"), None);
+    assert!(!PublishErrorKind::ContentRejected.is_retryable());
+    assert!(PublishErrorKind::Timeout.is_retryable());
+    // 点击发布后结果不明，重试可能重复发布
+    assert!(!PublishErrorKind::Unconfirmed.is_retryable());
+    assert!(!PublishErrorKind::Unknown.is_retryable());
+}
+
+#[test]
+fn test_parse_video_upload_state() {
+    assert_eq!(
+        parse_upload_state("上传中 45%"),
+        VideoUploadState::Uploading(Some(45))
+    );
+    assert_eq!(parse_upload_state("视频转码中，请稍候"), VideoUploadState::Processing);
+    assert_eq!(parse_upload_state("上传成功"), VideoUploadState::Done);
+    assert!(matches!(
+        parse_upload_state("上传失败，请重新上传"),
+        VideoUploadState::Failed(_)
+    ));
+}
@@ -0,0 +1,314 @@
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use xiaohongshu_helper_lib::automation::options::Visibility;
+use xiaohongshu_helper_lib::automation::PublishOptions;
+use xiaohongshu_helper_lib::model::{
+    CatchUpPolicy, MediaType, PostStatus, ProxyConfig, ProxyScheme, PublishJobStatus, RetryPolicy,
+};
+use xiaohongshu_helper_lib::storage::repository::{PostEdit, PostRepo, PublishJobRepo, UserRepo};
+use xiaohongshu_helper_lib::storage::sqlite::run_migrations;
+
+async fn memory_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    run_migrations(&pool).await.unwrap();
+    pool
+}
+
+fn draft(title: &str) -> PostEdit {
+    PostEdit {
+        title: title.to_string(),
+        content: "正文".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_user_upsert_keeps_id() {
+    let pool = memory_pool().await;
+    let users = UserRepo::new(&pool);
+
+    let first = users.upsert("旧昵称", "13800138000", None).await.unwrap();
+    let second = users
+        .upsert("新昵称", "13800138000", Some("https://example.com/a.png"))
+        .await
+        .unwrap();
+
+    assert_eq!(first.id, second.id);
+    assert_eq!(second.nickname, "新昵称");
+    assert_eq!(users.list().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_post_roundtrip() {
+    let pool = memory_pool().await;
+    let user = UserRepo::new(&pool)
+        .upsert("用户", "13800138000", None)
+        .await
+        .unwrap();
+    let posts = PostRepo::new(&pool);
+
+    let images = vec!["/tmp/1.png".to_string(), "/tmp/2.png".to_string()];
+    let id = posts
+        .create(
+            user.id,
+            &PostEdit {
+                images: images.clone(),
+                cover_image: Some("/tmp/2.png".to_string()),
+                ..draft("标题")
+            },
+        )
+        .await
+        .unwrap();
+
+    let post = posts.get(id).await.unwrap().unwrap();
+    assert_eq!(post.images, images);
+    assert_eq!(post.cover_image.as_deref(), Some("/tmp/2.png"));
+    assert_eq!(post.media_type, MediaType::Image);
+    assert_eq!(post.status, "draft");
+
+    let video_id = posts
+        .create(
+            user.id,
+            &PostEdit {
+                media_type: MediaType::Video,
+                video_path: Some("/tmp/1.mp4".to_string()),
+                cover_time: Some(2.5),
+                ..draft("视频")
+            },
+        )
+        .await
+        .unwrap();
+    let video = posts.get(video_id).await.unwrap().unwrap();
+    assert_eq!(video.media_type, MediaType::Video);
+    assert_eq!(video.video_path.as_deref(), Some("/tmp/1.mp4"));
+    assert_eq!(video.cover_time, Some(2.5));
+
+    UserRepo::new(&pool)
+        .delete_by_phone("13800138000")
+        .await
+        .unwrap();
+    assert!(posts.get(id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_post_update_revisions() {
+    let pool = memory_pool().await;
+    let user = UserRepo::new(&pool)
+        .upsert("用户", "13800138000", None)
+        .await
+        .unwrap();
+    let posts = PostRepo::new(&pool);
+    let id = posts.create(user.id, &draft("初稿")).await.unwrap();
+
+    let edit = PostEdit {
+        title: "润色后的标题".to_string(),
+        content: "正文".to_string(),
+        images: vec!["/tmp/1.png".to_string()],
+        ..Default::default()
+    };
+    let updated = posts.update(id, 1, &edit, "ai").await.unwrap();
+    assert_eq!(updated.version, 2);
+
+    // 基于旧版本的保存会被拒绝
+    assert!(posts.update(id, 1, &edit, "manual").await.is_err());
+
+    let revisions = posts.list_revisions(id).await.unwrap();
+    assert_eq!(revisions.len(), 2);
+    let changes = revisions[1].diff(&revisions[0]);
+    let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+    assert_eq!(fields, vec!["title", "images"]);
+
+    let restored = posts.restore_revision(id, 1, 2).await.unwrap();
+    assert_eq!(restored.version, 3);
+    assert_eq!(restored.title, "初稿");
+    assert!(restored.images.is_empty());
+    assert_eq!(posts.list_revisions(id).await.unwrap()[0].source.as_deref(), Some("restore"));
+
+    posts.delete(id).await.unwrap();
+    assert!(posts.list_revisions(id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_post_status_lifecycle() {
+    let pool = memory_pool().await;
+    let user = UserRepo::new(&pool)
+        .upsert("用户", "13800138000", None)
+        .await
+        .unwrap();
+    let posts = PostRepo::new(&pool);
+    let id = posts.create(user.id, &draft("标题")).await.unwrap();
+
+    // 不能跳过排队直接发布
+    assert!(posts
+        .transition(id, PostStatus::Publishing, None, None)
+        .await
+        .is_err());
+
+    posts.transition(id, PostStatus::Queued, None, None).await.unwrap();
+    posts
+        .transition(id, PostStatus::Publishing, None, None)
+        .await
+        .unwrap();
+
+    // 发布中的笔记不能编辑
+    let edit = PostEdit {
+        title: "新标题".to_string(),
+        ..Default::default()
+    };
+    assert!(posts.update(id, 1, &edit, "manual").await.is_err());
+
+    // 模拟崩溃后重启
+    assert_eq!(posts.recover_interrupted().await.unwrap(), 1);
+    let post = posts.get(id).await.unwrap().unwrap();
+    assert_eq!(post.status, "failed");
+    assert!(post.last_error.is_some());
+
+    let retried = posts.transition(id, PostStatus::Queued, None, None).await.unwrap();
+    assert!(retried.last_error.is_none());
+
+    let history = posts.list_status_history(id).await.unwrap();
+    let steps: Vec<&str> = history.iter().map(|h| h.to_status.as_str()).collect();
+    assert_eq!(steps, vec!["queued", "publishing", "failed", "queued"]);
+}
+
+#[tokio::test]
+async fn test_post_publish_options() {
+    let pool = memory_pool().await;
+    let user = UserRepo::new(&pool)
+        .upsert("用户", "13800138000", None)
+        .await
+        .unwrap();
+    let posts = PostRepo::new(&pool);
+    let id = posts.create(user.id, &draft("标题")).await.unwrap();
+    assert!(posts
+        .get_publish_options::<PublishOptions>(id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let options = PublishOptions {
+        visibility: Some(Visibility::Private),
+        location: Some("上海".to_string()),
+        ..Default::default()
+    };
+    posts.set_publish_options(id, Some(&options)).await.unwrap();
+    let saved: PublishOptions = posts.get_publish_options(id).await.unwrap().unwrap();
+    assert_eq!(saved.visibility, Some(Visibility::Private));
+    assert_eq!(saved.location.as_deref(), Some("上海"));
+
+    // 发布中的笔记不能修改设置
+    posts
+        .transition(id, PostStatus::Queued, None, None)
+        .await
+        .unwrap();
+    posts
+        .transition(id, PostStatus::Publishing, None, None)
+        .await
+        .unwrap();
+    assert!(posts.set_publish_options(id, Some(&options)).await.is_err());
+
+    // 为其他账号创建的副本保留原笔记的设置
+    let copy = posts.create(user.id, &draft("标题")).await.unwrap();
+    posts.copy_publish_options(id, copy).await.unwrap();
+    let copied: PublishOptions = posts.get_publish_options(copy).await.unwrap().unwrap();
+    assert_eq!(copied.visibility, Some(Visibility::Private));
+    assert_eq!(copied.location.as_deref(), Some("上海"));
+}
+
+#[tokio::test]
+async fn test_publish_job_queue() {
+    let pool = memory_pool().await;
+    let user = UserRepo::new(&pool)
+        .upsert("用户", "13800138000", None)
+        .await
+        .unwrap();
+    let posts = PostRepo::new(&pool);
+    let post_id = posts.create(user.id, &draft("标题")).await.unwrap();
+    posts
+        .transition(post_id, PostStatus::Queued, None, None)
+        .await
+        .unwrap();
+    let jobs = PublishJobRepo::new(&pool);
+
+    let job = jobs
+        .create(
+            post_id,
+            "2026-10-20T20:00",
+            "Asia/Shanghai",
+            "2026-10-20T12:00:00Z",
+            RetryPolicy::default(),
+            CatchUpPolicy::Skip,
+        )
+        .await
+        .unwrap();
+    assert_eq!(job.catch_up, CatchUpPolicy::Skip);
+
+    assert!(jobs.due("2026-10-20T11:59:59Z").await.unwrap().is_empty());
+    assert_eq!(jobs.due("2026-10-20T12:00:00Z").await.unwrap().len(), 1);
+
+    // 只能领取一次
+    assert!(jobs.claim(job.id).await.unwrap());
+    assert!(!jobs.claim(job.id).await.unwrap());
+
+    // 失败后推迟重试
+    jobs.set_status(
+        job.id,
+        PublishJobStatus::Pending,
+        Some("网络错误"),
+        Some("2026-10-20T12:05:00Z"),
+    )
+    .await
+    .unwrap();
+    assert!(jobs.due("2026-10-20T12:01:00Z").await.unwrap().is_empty());
+    let job = jobs.get(job.id).await.unwrap().unwrap();
+    assert_eq!(job.attempts, 1);
+
+    // 重启后仍在执行中的任务视为中断
+    jobs.claim(job.id).await.unwrap();
+    assert_eq!(jobs.fail_interrupted().await.unwrap(), 1);
+    assert!(!jobs.cancel(job.id).await.unwrap());
+    assert_eq!(
+        jobs.get(job.id).await.unwrap().unwrap().status,
+        PublishJobStatus::Failed
+    );
+    // 笔记不能停在排队状态
+    assert_eq!(posts.get(post_id).await.unwrap().unwrap().status, "failed");
+}
+
+#[tokio::test]
+async fn test_user_proxy_roundtrip() {
+    let pool = memory_pool().await;
+    let users = UserRepo::new(&pool);
+    users.upsert("用户", "13800138000", None).await.unwrap();
+    assert!(users.get_proxy("13800138000").await.unwrap().is_none());
+
+    let proxy = ProxyConfig {
+        scheme: ProxyScheme::Http,
+        host: "127.0.0.1".to_string(),
+        port: 7890,
+        username: Some("user".to_string()),
+        password: Some("enc:v1:secret".to_string()),
+    };
+    assert!(proxy.validate().is_ok());
+    assert_eq!(proxy.server(), "http://127.0.0.1:7890");
+    users.set_proxy("13800138000", Some(&proxy)).await.unwrap();
+    assert_eq!(
+        users.get_proxy("13800138000").await.unwrap(),
+        Some(proxy.clone())
+    );
+
+    // 带认证的 SOCKS5 通过本地转发使用
+    let socks = ProxyConfig {
+        scheme: ProxyScheme::Socks5,
+        ..proxy
+    };
+    assert!(socks.validate().is_ok());
+    assert_eq!(socks.server(), "socks5://127.0.0.1:7890");
+
+    users.set_proxy("13800138000", None).await.unwrap();
+    assert!(users.get_proxy("13800138000").await.unwrap().is_none());
+    assert!(users.set_proxy("13900000000", None).await.is_err());
+}
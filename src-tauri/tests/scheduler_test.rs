@@ -0,0 +1,45 @@
+use chrono::{TimeDelta, TimeZone, Utc};
+use xiaohongshu_helper_lib::model::{CatchUpPolicy, RetryPolicy};
+use xiaohongshu_helper_lib::scheduler::{format_utc, retry_delay, schedule_to_utc, should_run};
+
+#[test]
+fn test_schedule_to_utc() {
+    let utc = schedule_to_utc("2026-10-20T20:00", "Asia/Shanghai").unwrap();
+    assert_eq!(format_utc(utc), "2026-10-20T12:00:00Z");
+
+    // 纽约夏令时切换当天 02:30 不存在
+    assert!(schedule_to_utc("2026-03-08T02:30", "America/New_York").is_err());
+    assert!(schedule_to_utc("2026-10-20T20:00", "Mars/Base").is_err());
+    assert!(schedule_to_utc("明天晚上", "Asia/Shanghai").is_err());
+}
+
+#[test]
+fn test_retry_backoff() {
+    let policy = RetryPolicy {
+        max_attempts: 5,
+        backoff_seconds: 60,
+    };
+    assert_eq!(retry_delay(&policy, 1), TimeDelta::seconds(60));
+    assert_eq!(retry_delay(&policy, 3), TimeDelta::seconds(240));
+    // 等待时间最多一天
+    assert_eq!(retry_delay(&policy, 30), TimeDelta::seconds(86_400));
+}
+
+#[test]
+fn test_catch_up_policy() {
+    let scheduled = Utc.with_ymd_and_hms(2026, 10, 20, 12, 0, 0).unwrap();
+    let slightly_late = scheduled + TimeDelta::seconds(30);
+    let hours_late = scheduled + TimeDelta::hours(3);
+
+    for policy in [
+        CatchUpPolicy::RunLate,
+        CatchUpPolicy::Skip,
+        CatchUpPolicy::Window { minutes: 60 },
+    ] {
+        assert!(should_run(&policy, scheduled, slightly_late));
+    }
+    assert!(should_run(&CatchUpPolicy::RunLate, scheduled, hours_late));
+    assert!(!should_run(&CatchUpPolicy::Skip, scheduled, hours_late));
+    assert!(!should_run(&CatchUpPolicy::Window { minutes: 60 }, scheduled, hours_late));
+    assert!(should_run(&CatchUpPolicy::Window { minutes: 240 }, scheduled, hours_late));
+}
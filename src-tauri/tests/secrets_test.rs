@@ -0,0 +1,31 @@
+use xiaohongshu_helper_lib::storage::secrets::{
+    decrypt_with, derive_key, encrypt_with, is_encrypted, is_masked, mask_secret, MASKED_SECRET,
+};
+
+#[test]
+fn test_encrypt_roundtrip() {
+    let key = derive_key("correct horse", b"0123456789abcdef");
+    let stored = encrypt_with(&key, "sk-test-123").unwrap();
+
+    assert!(is_encrypted(&stored));
+    assert!(!stored.contains("sk-test-123"));
+    assert_eq!(decrypt_with(&key, &stored).unwrap(), "sk-test-123");
+
+    // 相同明文每次加密结果不同
+    assert_ne!(encrypt_with(&key, "sk-test-123").unwrap(), stored);
+
+    let wrong = derive_key("wrong", b"0123456789abcdef");
+    assert!(decrypt_with(&wrong, &stored).is_err());
+}
+
+#[test]
+fn test_legacy_plaintext_and_mask() {
+    let key = [7u8; 32];
+    // 旧版本的明文原样返回，等待启动时迁移
+    assert_eq!(decrypt_with(&key, "sk-legacy").unwrap(), "sk-legacy");
+
+    assert_eq!(mask_secret("enc:v1:abc"), MASKED_SECRET);
+    assert_eq!(mask_secret(""), "");
+    assert!(is_masked(MASKED_SECRET));
+    assert!(!is_masked("sk-new"));
+}
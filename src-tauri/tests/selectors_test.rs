@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use xiaohongshu_helper_lib::automation::selectors::{
+    builtin, classify, combine, is_xpath, parse_registry, SelectorStatus,
+};
+
+#[test]
+fn test_builtin_registry() {
+    let registry = builtin();
+    assert!(registry.version >= 1);
+    for key in [
+        "login.phone_input",
+        "account.user_info",
+        "publish.upload_input",
+        "publish.editor",
+        "publish.title",
+        "publish.content",
+        "publish.publish_button",
+    ] {
+        assert!(registry.selectors.contains_key(key), "{}", key);
+    }
+    assert!(is_xpath(
+        &registry.selectors["publish.publish_button"].candidates[0]
+    ));
+    assert!(!is_xpath(
+        &registry.selectors["publish.title"].candidates[0]
+    ));
+}
+
+#[test]
+fn test_parse_registry_rejects_invalid() {
+    assert!(parse_registry("not json").is_err());
+    assert!(parse_registry(r#"{"version": 0, "selectors": {}}"#).is_err());
+    assert!(parse_registry(
+        r#"{"version": 2, "selectors": {"publish.title": {"candidates": [" "]}}}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_combine_layers() {
+    let file = parse_registry(
+        r#"{"version": 99, "selectors": {
+            "publish.title": {"page": "editor", "candidates": [".new-title", ".d-text"]},
+            "publish.extra": {"candidates": [".extra"]}
+        }}"#,
+    )
+    .unwrap();
+    let mut overrides = BTreeMap::new();
+    overrides.insert("publish.content".to_string(), vec![".editor".to_string()]);
+    overrides.insert("unknown.key".to_string(), vec![".x".to_string()]);
+
+    let loaded = combine(builtin(), Some(file), &overrides);
+    assert_eq!(loaded.warnings, vec!["忽略未知的选择器覆盖: unknown.key"]);
+    let registry = loaded.registry;
+    assert_eq!(registry.version, 99);
+    assert_eq!(
+        registry.selectors["publish.title"].candidates,
+        vec![".new-title", ".d-text"]
+    );
+    assert!(registry.selectors.contains_key("publish.extra"));
+    assert_eq!(
+        registry.selectors["publish.content"].candidates,
+        vec![".editor"]
+    );
+    assert!(!registry.selectors.contains_key("unknown.key"));
+    // 没有被覆盖的保持内置值
+    assert_eq!(
+        registry.selectors["publish.editor"],
+        builtin().selectors["publish.editor"]
+    );
+
+    // 旧版本文件的候选排在内置候选之后，不挡住新版本修正的选择器
+    let old = parse_registry(
+        r#"{"version": 1, "selectors": {
+            "publish.title": {"candidates": [".old"]},
+            "publish.content": {"candidates": [".old-content"]},
+            "publish.legacy": {"candidates": [".legacy"]}
+        }}"#,
+    )
+    .unwrap();
+    let mut current = builtin();
+    current.version = 5;
+    current
+        .selectors
+        .get_mut("publish.content")
+        .unwrap()
+        .candidates = vec![".old-content".to_string()];
+    let mut expected_title = current.selectors["publish.title"].candidates.clone();
+    expected_title.push(".old".to_string());
+    let loaded = combine(current, Some(old), &BTreeMap::new());
+    assert_eq!(loaded.registry.version, 5);
+    assert_eq!(
+        loaded.registry.selectors["publish.title"].candidates,
+        expected_title
+    );
+    assert_eq!(
+        loaded.registry.selectors["publish.content"].candidates,
+        vec![".old-content"]
+    );
+    assert!(loaded.registry.selectors.contains_key("publish.legacy"));
+    assert_eq!(
+        loaded.warnings,
+        vec!["选择器文件版本 1 低于内置版本 5，以下选择器中文件的候选排在内置候选之后: publish.title"]
+    );
+}
+
+#[test]
+fn test_classify() {
+    assert_eq!(classify(&[1, 0]), (SelectorStatus::Ok, Some(0)));
+    assert_eq!(classify(&[0, 0, 2]), (SelectorStatus::Fallback, Some(2)));
+    assert_eq!(classify(&[0, 0]), (SelectorStatus::Broken, None));
+}
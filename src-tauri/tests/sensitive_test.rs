@@ -0,0 +1,85 @@
+use sqlx::sqlite::SqlitePoolOptions;
+use xiaohongshu_helper_lib::model::SensitiveWord;
+use xiaohongshu_helper_lib::sensitive::{check_post, find_hits, parse_dictionary};
+use xiaohongshu_helper_lib::storage::repository::SensitiveWordRepo;
+use xiaohongshu_helper_lib::storage::sqlite::run_migrations;
+
+fn word(word: &str, category: &str, replacement: Option<&str>) -> SensitiveWord {
+    SensitiveWord {
+        id: None,
+        word: word.to_string(),
+        category: category.to_string(),
+        replacement: replacement.map(str::to_string),
+        enabled: true,
+    }
+}
+
+#[test]
+fn test_parse_dictionary() {
+    let text = "# 站外引流\n微信,私信\n\nVX\n微信，重复\n淘宝\t\n";
+    assert_eq!(
+        parse_dictionary(text),
+        vec![
+            ("微信".to_string(), Some("私信".to_string())),
+            ("VX".to_string(), None),
+            ("淘宝".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn test_find_hits() {
+    let words = vec![
+        word("最", "极限词", None),
+        word("全网最低", "极限词", Some("超值")),
+        word("vx", "站外引流", Some("私信")),
+    ];
+
+    let hits = find_hits(&words, "content", "全网最低价，最后一天，加VX");
+    let found: Vec<(&str, usize, usize)> = hits
+        .iter()
+        .map(|h| (h.word.as_str(), h.start, h.end))
+        .collect();
+    // 「全网最低」中的「最」不重复报告
+    assert_eq!(found, vec![("全网最低", 0, 4), ("最", 6, 7), ("vx", 12, 14)]);
+    assert_eq!(hits[0].replacement.as_deref(), Some("超值"));
+
+    let mut disabled = words.clone();
+    disabled[0].enabled = false;
+    let hits = check_post(&disabled, "最好用", "");
+    assert!(hits.is_empty());
+}
+
+#[tokio::test]
+async fn test_dictionary_import() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    run_migrations(&pool).await.unwrap();
+    let repo = SensitiveWordRepo::new(&pool);
+
+    // 迁移时写入了默认词库
+    let defaults = repo.list().await.unwrap();
+    assert!(defaults.iter().any(|w| w.word == "第一" && w.category == "极限词"));
+
+    let entries = parse_dictionary("某竞品\n第一\n");
+    assert_eq!(repo.import("竞品", &entries).await.unwrap(), 2);
+    assert_eq!(repo.import("竞品", &entries).await.unwrap(), 0);
+
+    let mut saved = word("某竞品", "竞品", Some("某品牌"));
+    let id = repo.save(&saved).await.unwrap();
+    saved.id = Some(id);
+    saved.enabled = false;
+    repo.save(&saved).await.unwrap();
+    assert!(!repo
+        .list_enabled()
+        .await
+        .unwrap()
+        .iter()
+        .any(|w| w.word == "某竞品"));
+
+    repo.delete(id).await.unwrap();
+    assert_eq!(repo.list().await.unwrap().len(), defaults.len() + 1);
+}
@@ -0,0 +1,108 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use xiaohongshu_helper_lib::automation::socks::SocksForwarder;
+use xiaohongshu_helper_lib::model::{ProxyConfig, ProxyScheme};
+
+/// 要求用户名密码的 SOCKS5 代理，连接成功后把收到的数据原样发回
+async fn fake_upstream(username: &'static str, password: &'static str) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut greeting = [0u8; 3];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [5, 1, 2]);
+        stream.write_all(&[5, 2]).await.unwrap();
+
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).await.unwrap();
+        let mut user = vec![0u8; head[1] as usize];
+        stream.read_exact(&mut user).await.unwrap();
+        let mut len = [0u8; 1];
+        stream.read_exact(&mut len).await.unwrap();
+        let mut pass = vec![0u8; len[0] as usize];
+        stream.read_exact(&mut pass).await.unwrap();
+        let ok = user == username.as_bytes() && pass == password.as_bytes();
+        stream
+            .write_all(&[1, if ok { 0 } else { 1 }])
+            .await
+            .unwrap();
+        if !ok {
+            return;
+        }
+
+        // CONNECT example.com:80
+        let mut request = [0u8; 5];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request[..4], [5, 1, 0, 3]);
+        let mut host = vec![0u8; request[4] as usize + 2];
+        stream.read_exact(&mut host).await.unwrap();
+        assert_eq!(&host[..host.len() - 2], b"example.com");
+        stream
+            .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80])
+            .await
+            .unwrap();
+
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(&buf).await.unwrap();
+    });
+    port
+}
+
+async fn connect_through(forwarder: &SocksForwarder) -> (TcpStream, [u8; 10]) {
+    let addr = forwarder
+        .server()
+        .trim_start_matches("socks5://")
+        .to_string();
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client.write_all(&[5, 1, 0]).await.unwrap();
+    let mut choice = [0u8; 2];
+    client.read_exact(&mut choice).await.unwrap();
+    assert_eq!(choice, [5, 0]);
+
+    let mut request = vec![5, 1, 0, 3, 11];
+    request.extend_from_slice(b"example.com");
+    request.extend_from_slice(&[0, 80]);
+    client.write_all(&request).await.unwrap();
+    let mut reply = [0u8; 10];
+    client.read_exact(&mut reply).await.unwrap();
+    (client, reply)
+}
+
+fn upstream(port: u16, password: &str) -> ProxyConfig {
+    ProxyConfig {
+        scheme: ProxyScheme::Socks5,
+        host: "127.0.0.1".to_string(),
+        port,
+        username: Some("user".to_string()),
+        password: Some(password.to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_forward_with_credentials() {
+    let port = fake_upstream("user", "secret").await;
+    let forwarder = SocksForwarder::start(upstream(port, "secret"))
+        .await
+        .unwrap();
+
+    let (mut client, reply) = connect_through(&forwarder).await;
+    assert_eq!(reply[1], 0);
+    client.write_all(b"ping").await.unwrap();
+    let mut echo = [0u8; 4];
+    client.read_exact(&mut echo).await.unwrap();
+    assert_eq!(&echo, b"ping");
+}
+
+#[tokio::test]
+async fn test_forward_rejects_wrong_password() {
+    let port = fake_upstream("user", "secret").await;
+    let forwarder = SocksForwarder::start(upstream(port, "wrong"))
+        .await
+        .unwrap();
+
+    // 上游认证失败时告诉浏览器代理故障
+    let (_, reply) = connect_through(&forwarder).await;
+    assert_eq!(reply[1], 1);
+}
@@ -0,0 +1,63 @@
+use xiaohongshu_helper_lib::automation::trace::{
+    self, list_runs, prune, read_run, RetentionPolicy, RunKind, RunStatus, TRACE_FILE,
+};
+use xiaohongshu_helper_lib::storage::get_runs_dir;
+use xiaohongshu_helper_lib::storage::workspace::DATA_DIR_ENV;
+
+#[tokio::test]
+async fn test_run_trace() {
+    let dir = std::env::temp_dir().join(format!("xhs-trace-{}", std::process::id()));
+    std::env::set_var(DATA_DIR_ENV, &dir);
+
+    let result: Result<(), String> = trace::scope(RunKind::Validate, "13800000000", async {
+        assert!(trace::current_id().is_some());
+        trace::step("navigate");
+        let path = trace::file_path("page.png").unwrap();
+        std::fs::write(&path, b"png").unwrap();
+        trace::add_file(&path);
+        trace::step("read_user");
+        Err("未检测到登录状态".to_string())
+    })
+    .await;
+    assert!(result.is_err());
+    assert!(trace::current_id().is_none());
+    assert!(trace::file_path("outside.png").is_none());
+
+    let root = get_runs_dir();
+    let runs = list_runs(&root);
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run.kind, RunKind::Validate);
+    assert_eq!(run.status, RunStatus::Failed);
+    assert_eq!(run.error.as_deref(), Some("未检测到登录状态"));
+    assert!(run.duration_ms.is_some());
+    let names: Vec<&str> = run.steps.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["navigate", "read_user"]);
+    assert_eq!(run.steps[0].files, ["01_page.png"]);
+    assert!(run.steps[0].duration_ms.is_some());
+    assert!(run.steps[1].error.is_some());
+    assert!(root.join(&run.id).join("01_page.png").exists());
+
+    assert_eq!(read_run(&root, &run.id).unwrap().id, run.id);
+    assert!(read_run(&root, "../app.db").is_err());
+}
+
+#[test]
+fn test_prune_keeps_newest() {
+    let root = std::env::temp_dir().join(format!("xhs-trace-prune-{}", std::process::id()));
+    for id in ["20260101-1", "20260102-1", "20260103-1"] {
+        std::fs::create_dir_all(root.join(id)).unwrap();
+        std::fs::write(root.join(id).join(TRACE_FILE), "{}").unwrap();
+    }
+    std::fs::create_dir_all(root.join("not-a-run")).unwrap();
+
+    let policy = RetentionPolicy {
+        max_runs: 2,
+        max_days: 30,
+    };
+    assert_eq!(prune(&root, &policy).unwrap(), 1);
+    assert!(!root.join("20260101-1").exists());
+    assert!(root.join("20260103-1").exists());
+    assert!(root.join("not-a-run").exists());
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,81 @@
+use xiaohongshu_helper_lib::automation::validate::{
+    image_dimensions, title_length, validate_post, IssueLevel,
+};
+use xiaohongshu_helper_lib::model::MediaType;
+use xiaohongshu_helper_lib::storage::repository::PostEdit;
+
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn test_title_length() {
+    assert_eq!(title_length("春日限定"), 4);
+    assert_eq!(title_length("好看😍"), 4);
+    // 带变体选择符和肤色修饰的 emoji 仍按 2 计算
+    assert_eq!(title_length("❤️"), 2);
+    assert_eq!(title_length("👍🏻"), 2);
+}
+
+#[test]
+fn test_image_dimensions() {
+    assert_eq!(image_dimensions(&png_header(1080, 1440)), Some((1080, 1440)));
+
+    let jpeg = [
+        0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x05,
+        0xA0, 0x04, 0x38, 0x03,
+    ];
+    assert_eq!(image_dimensions(&jpeg), Some((1080, 1440)));
+    assert_eq!(image_dimensions(b"not an image"), None);
+}
+
+#[test]
+fn test_validate_post() {
+    let dir = std::env::temp_dir().join(format!("xhs_validate_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.png");
+    let wide = dir.join("wide.png");
+    std::fs::write(&good, png_header(1080, 1440)).unwrap();
+    std::fs::write(&wide, png_header(1920, 1080)).unwrap();
+    let good = good.to_string_lossy().to_string();
+    let wide = wide.to_string_lossy().to_string();
+
+    let post = PostEdit {
+        title: "周末去哪儿".to_string(),
+        content: "正文".to_string(),
+        images: vec![good.clone(), wide.clone()],
+        cover_image: Some(good.clone()),
+        ..Default::default()
+    };
+    let report = validate_post(&post);
+    assert!(!report.has_errors());
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].level, IssueLevel::Warning);
+    assert_eq!(report.issues[0].field, "images[1]");
+
+    let post = PostEdit {
+        title: "这是一个非常非常长的标题超过了平台限制的二十个字".to_string(),
+        images: vec![good.clone(), "/nonexistent/a.png".to_string(), "a.gif".to_string()],
+        cover_image: Some(wide),
+        ..post
+    };
+    let fields: Vec<String> = validate_post(&post)
+        .issues
+        .into_iter()
+        .filter(|i| i.level == IssueLevel::Error)
+        .map(|i| i.field)
+        .collect();
+    assert_eq!(fields, vec!["title", "images[1]", "images[2]", "cover_image"]);
+
+    let video = PostEdit {
+        title: "视频".to_string(),
+        media_type: MediaType::Video,
+        ..Default::default()
+    };
+    assert!(validate_post(&video).has_errors());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
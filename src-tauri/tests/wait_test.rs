@@ -0,0 +1,60 @@
+use std::time::Duration;
+use xiaohongshu_helper_lib::automation::outcome::PublishErrorKind;
+use xiaohongshu_helper_lib::automation::wait::{self, parse_timeouts, Step, Timeout};
+use xiaohongshu_helper_lib::automation::PublishFailure;
+
+#[test]
+fn test_step_names() {
+    for step in Step::ALL {
+        assert_eq!(Step::parse(step.as_str()), Some(step));
+        assert_eq!(
+            serde_json::to_value(step).unwrap(),
+            serde_json::json!(step.as_str())
+        );
+    }
+    assert_eq!(Step::parse("unknown"), None);
+    assert_eq!(wait::timeout(Step::Suggestions), Duration::from_secs(4));
+}
+
+#[test]
+fn test_parse_timeouts() {
+    let parsed =
+        parse_timeouts(r#"{"image_upload": 120, "element": 0, "draft_save": 99999, "unknown": 5}"#);
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[&Step::ImageUpload], Duration::from_secs(120));
+    assert!(parse_timeouts("not json").is_empty());
+}
+
+#[test]
+fn test_timeout_error() {
+    let timeout = Timeout {
+        step: Step::CoverUpload,
+        what: "页面元素 publish.editor".to_string(),
+        after: Duration::from_secs(60),
+    };
+    let message = timeout.to_string();
+    assert!(message.contains("publish.editor"), "{}", message);
+    assert!(message.contains("60 秒"), "{}", message);
+    assert!(message.contains("cover_upload"), "{}", message);
+    assert_eq!(
+        PublishFailure::from(timeout).kind,
+        PublishErrorKind::Timeout
+    );
+}
+
+#[tokio::test]
+async fn test_until() {
+    let mut polls = 0;
+    let result = wait::until(Step::Suggestions, "候选列表", || {
+        polls += 1;
+        (polls == 3).then_some(polls)
+    })
+    .await;
+    assert_eq!(result.unwrap(), 3);
+
+    let err = wait::until(Step::Suggestions, "候选列表", || None::<()>)
+        .await
+        .unwrap_err();
+    assert_eq!(err.step, Step::Suggestions);
+    assert_eq!(err.after, Duration::from_secs(4));
+}
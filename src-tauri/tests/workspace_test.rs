@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+use xiaohongshu_helper_lib::storage::workspace::{
+    pinned_workspace, resolve_data_root, resolve_workspace, validate_workspace_name, workspace_dir,
+    DataDirSource, PORTABLE_MARKER,
+};
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_data_root_priority() {
+    let home = Path::new("/home/test");
+    let exe_dir = std::env::temp_dir().join(format!("xhs-portable-{}", std::process::id()));
+    std::fs::create_dir_all(&exe_dir).unwrap();
+    std::fs::write(exe_dir.join(PORTABLE_MARKER), "").unwrap();
+
+    let (root, source) = resolve_data_root(
+        &args(&["app", "--data-dir", "/data/cli"]),
+        Some("/data/env".to_string()),
+        Some(&exe_dir),
+        home,
+    );
+    assert_eq!((root, source), (PathBuf::from("/data/cli"), DataDirSource::Cli));
+
+    let (root, source) = resolve_data_root(
+        &args(&["app"]),
+        Some("/data/env".to_string()),
+        Some(&exe_dir),
+        home,
+    );
+    assert_eq!((root, source), (PathBuf::from("/data/env"), DataDirSource::Env));
+
+    let (root, source) = resolve_data_root(&args(&["app"]), None, Some(&exe_dir), home);
+    assert_eq!(source, DataDirSource::Portable);
+    assert!(root.starts_with(&exe_dir));
+
+    let (root, source) = resolve_data_root(&args(&["app"]), None, None, home);
+    assert_eq!(source, DataDirSource::Home);
+    assert_eq!(root, home.join("xiaohongshu-helper-data"));
+
+    std::fs::remove_dir_all(&exe_dir).ok();
+}
+
+#[test]
+fn test_workspace_resolution() {
+    let root = Path::new("/nonexistent-root");
+    assert_eq!(
+        resolve_workspace(&args(&["app"]), None, root).unwrap(),
+        "default"
+    );
+    assert_eq!(
+        resolve_workspace(&args(&["app", "--workspace=品牌A"]), None, root).unwrap(),
+        "品牌A"
+    );
+    // 指定的名称无效时报错，不回退到 default
+    assert!(resolve_workspace(&args(&["app"]), Some("../etc".to_string()), root).is_err());
+    assert!(resolve_workspace(&args(&["app", "--workspace=a/b"]), None, root).is_err());
+    // 指定了工作区时不能在应用内切换
+    assert!(pinned_workspace(&args(&["app"]), None).is_none());
+    assert!(pinned_workspace(&args(&["app"]), Some(String::new())).is_none());
+    assert_eq!(
+        pinned_workspace(&args(&["app", "--workspace", "品牌A"]), None).as_deref(),
+        Some("品牌A")
+    );
+
+    assert_eq!(workspace_dir(root, "default"), root);
+    assert_eq!(
+        workspace_dir(root, "品牌A"),
+        root.join("workspaces").join("品牌A")
+    );
+    assert!(validate_workspace_name("a/b").is_err());
+    assert!(validate_workspace_name("").is_err());
+}
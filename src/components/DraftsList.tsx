@@ -20,7 +20,8 @@ export const DraftsList = () => {
             title: draft.title,
             content: draft.content,
             images: draft.images,
-            coverImage: draft.coverImage
+            coverImage: draft.coverImage,
+            version: draft.version
         });
     };
 
@@ -47,7 +48,8 @@ export const DraftsList = () => {
             title: '',
             content: '',
             images: [],
-            coverImage: undefined
+            coverImage: undefined,
+            version: undefined
         });
     };
 
@@ -19,7 +19,7 @@ import { SxProps, Theme } from '@mui/material';
 import { Sparkles, Send, Plus, X, Image as ImageIcon, Wand2, Bot, ChevronDown, Flame, Settings as SettingsIcon, Layout as LayoutIcon, LayoutTemplate } from 'lucide-react';
 import { useAppStore } from '../store';
 import { invoke, convertFileSrc } from '@tauri-apps/api/core';
-import { open, message } from '@tauri-apps/plugin-dialog';
+import { open, message, ask } from '@tauri-apps/plugin-dialog';
 import { AssetSelectorDialog } from './AssetSelectorDialog';
 import { AIPolishDialog } from './AIPolishDialog';
 import { ImagePromptDialog } from './ImagePromptDialog';
@@ -371,18 +371,36 @@ export const PublishView = () => {
             return;
         }
 
+        // 发布前用本地词库检查敏感词
+        try {
+            const hits: { field: string; word: string; category: string; replacement?: string }[] =
+                await invoke('check_sensitive_words', { title: currentPost.title, content: currentPost.content });
+            if (hits.length > 0) {
+                const lines = hits.map(h =>
+                    `${h.field === 'title' ? '标题' : '正文'}：${h.category}「${h.word}」${h.replacement ? `，建议改为「${h.replacement}」` : ''}`
+                );
+                const proceed = await ask(`发现以下敏感词，可能导致笔记限流：\n${lines.join('\n')}\n\n仍然发布？`, {
+                    title: '敏感词提示',
+                    kind: 'warning'
+                });
+                if (!proceed) return;
+            }
+        } catch (e) {
+            console.error('敏感词检查失败', e);
+        }
+
         setPublishing(true);
         try {
-            await invoke('publish_post', {
+            const note: { note_id?: string; note_url?: string } = await invoke('publish_post', {
                 phone: currentUser.phone,
                 title: currentPost.title,
                 content: currentPost.content,
                 images: currentPost.images,
                 coverImage: currentPost.coverImage
             });
-            await message('发布流程已启动，请观察浏览器操作', { title: '发布中', kind: 'info' });
-        } catch (e) {
-            await message('发布失败: ' + e, { title: '错误', kind: 'error' });
+            await message(note.note_url ? `发布成功: ${note.note_url}` : '发布成功', { title: '成功', kind: 'info' });
+        } catch (e: any) {
+            await message('发布失败: ' + (e?.message ?? e), { title: '错误', kind: 'error' });
         } finally {
             setPublishing(false);
         }
@@ -394,14 +412,26 @@ export const PublishView = () => {
             return;
         }
         try {
-            const id: number = await invoke('save_post', {
-                userId: currentUser.id,
-                title: currentPost.title,
-                content: currentPost.content,
-                images: currentPost.images,
-                coverImage: currentPost.coverImage
-            });
-            setCurrentPost({ id });
+            if (currentPost.id && currentPost.version) {
+                const updated: any = await invoke('update_post', {
+                    postId: currentPost.id,
+                    expectedVersion: currentPost.version,
+                    title: currentPost.title,
+                    content: currentPost.content,
+                    images: currentPost.images,
+                    coverImage: currentPost.coverImage
+                });
+                setCurrentPost({ version: updated.version });
+            } else {
+                const id: number = await invoke('save_post', {
+                    userId: currentUser.id,
+                    title: currentPost.title,
+                    content: currentPost.content,
+                    images: currentPost.images,
+                    coverImage: currentPost.coverImage
+                });
+                setCurrentPost({ id, version: 1 });
+            }
             useAppStore.getState().fetchDrafts();
             await message('草稿已保存', { title: '成功', kind: 'info' });
         } catch (e) {
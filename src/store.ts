@@ -29,6 +29,7 @@ interface Post {
     images: string[];
     coverImage?: string;
     created_at?: string;
+    version?: number;
 }
 
 export interface TrendItem {
@@ -155,8 +156,8 @@ export const useAppStore = create<AppState>((set) => ({
         const { currentUser } = useAppStore.getState();
         if (currentUser) {
             try {
-                const posts: Post[] = await invoke('get_posts', { userId: currentUser.id });
-                set({ drafts: posts });
+                const posts: (Post & { cover_image?: string })[] = await invoke('get_posts', { userId: currentUser.id });
+                set({ drafts: posts.map(({ cover_image, ...p }) => ({ ...p, coverImage: cover_image ?? undefined })) });
             } catch (e) {
                 console.error('Failed to fetch drafts', e);
             }